@@ -114,6 +114,19 @@ pub extern "C" fn ambuild(
         .execute()
         .expect("failed to add index to alias during CREATE INDEX");
 
+    // once the index is committed, block further metadata changes, if configured to.  Waiting
+    // until then lets the abort callback above still delete the index
+    if ZDBIndexOptions::from_relation(&index_relation).block_metadata() {
+        warning!("block_metadata is enabled.  Elasticsearch will reject DROP INDEX, and any changes made outside of ZomboDB, until it is disabled via ALTER INDEX");
+
+        let block_on_commit = elasticsearch.update_settings();
+        register_xact_callback(PgXactCallbackEvent::PreCommit, move || {
+            block_on_commit
+                .apply_metadata_block()
+                .expect("failed to block index metadata during CREATE INDEX");
+        });
+    }
+
     // create the triggers we need on the table to which this index is attached
     if !heap_relation.is_matview() {
         create_triggers(&index_relation);
@@ -256,6 +269,60 @@ unsafe extern "C" fn build_callback_internal(
     state.memcxt.reset();
 }
 
+/// Re-sends every row of the index's table whose `xmin` is at or after `since_xid` (a 64-bit
+/// transaction id, as returned by `txid_current()`) to Elasticsearch, returning how many were sent.
+/// This is useful after changes were lost by Elasticsearch, such as during an outage
+#[pg_extern(volatile, parallel_unsafe)]
+fn bulk_replay(index: PgRelation, since_xid: i64) -> i64 {
+    let heap_relation = index.heap_relation().expect("not an index");
+    let bulk = get_executor_manager().checkout_bulk_context(index.oid());
+    if bulk.is_shadow {
+        panic!("cannot replay changes to a shadow index");
+    } else if bulk.tupdesc.tdtypeid != unsafe { (*heap_relation.rd_rel).reltype } {
+        panic!("zdb.bulk_replay() only supports indexes on the whole row of their table");
+    }
+
+    // regclass's output is the table name, quoted and schema-qualified as necessary
+    let table_name =
+        Spi::get_one::<String>(&format!("SELECT {}::regclass::text", heap_relation.oid()))
+            .expect("failed to lookup table name");
+    let query = format!(
+        "SELECT t.ctid, t.xmin::text::bigint, t FROM {} t",
+        table_name
+    );
+
+    Spi::connect(|client| {
+        let mut table = client.select(&query, None, None);
+        let mut cnt = 0i64;
+
+        while table.next().is_some() {
+            check_for_interrupts!();
+
+            let xmin = xid_to_64bit(table.get_datum::<i64>(2).expect("xmin is null") as u32);
+            if xmin < since_xid as u64 {
+                continue;
+            }
+
+            let ctid = table
+                .get_datum::<pg_sys::ItemPointerData>(1)
+                .expect("ctid is null");
+            let row = table.get_datum::<pg_sys::Datum>(3).expect("row is null");
+            let builder = unsafe { row_to_json(row, bulk.tupdesc, &bulk.attributes) };
+            let cmin = pg_sys::FirstCommandId;
+            let cmax = cmin;
+            let xmax = pg_sys::InvalidTransactionId as u64;
+
+            bulk.bulk
+                .insert(ctid, cmin, cmax, xmin, xmax, builder)
+                .expect("Unable to send tuple for insert");
+            cnt += 1;
+        }
+
+        Ok(Some(cnt))
+    })
+    .unwrap_or_default()
+}
+
 unsafe fn row_to_json<'a>(
     row: pg_sys::Datum,
     tupdesc: &'a PgTupleDesc,
@@ -311,3 +378,44 @@ unsafe fn decon_row<'a>(
         }
     })
 }
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_bulk_replay() {
+        Spi::run("CREATE TABLE test_bulk_replay AS SELECT 'hello'::text AS title FROM generate_series(1, 3);");
+        Spi::run("CREATE INDEX idxtest_bulk_replay ON test_bulk_replay USING zombodb ((test_bulk_replay.*));");
+
+        // lose the documents out from under ZomboDB
+        Spi::run(
+            r#"SELECT zdb.request('idxtest_bulk_replay', '_delete_by_query?refresh=true', 'POST', '{"query": {"match": {"title": "hello"}}}');"#,
+        );
+        let count = Spi::get_one::<i64>("SELECT zdb.count('idxtest_bulk_replay', 'title:hello');")
+            .expect("failed to get SPI result");
+        assert_eq!(count, 0);
+
+        let replayed = Spi::get_one::<i64>("SELECT zdb.bulk_replay('idxtest_bulk_replay', 0);")
+            .expect("failed to get SPI result");
+        assert_eq!(replayed, 3);
+
+        let count = Spi::get_one::<i64>("SELECT zdb.count('idxtest_bulk_replay', 'title:hello');")
+            .expect("failed to get SPI result");
+        assert_eq!(count, 3);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_bulk_replay_skips_older_rows() {
+        Spi::run("CREATE TABLE test_bulk_replay AS SELECT 'hello'::text AS title FROM generate_series(1, 3);");
+        Spi::run("CREATE INDEX idxtest_bulk_replay ON test_bulk_replay USING zombodb ((test_bulk_replay.*));");
+
+        let replayed = Spi::get_one::<i64>(
+            "SELECT zdb.bulk_replay('idxtest_bulk_replay', txid_current() + 1);",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(replayed, 0);
+    }
+}
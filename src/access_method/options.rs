@@ -1,6 +1,6 @@
 use crate::elasticsearch::Elasticsearch;
-use crate::gucs::{ZDB_DEFAULT_ELASTICSEARCH_URL, ZDB_DEFAULT_REPLICAS};
-use crate::utils::find_zdb_index;
+use crate::gucs::{max_bulk_concurrency, ZDB_DEFAULT_ELASTICSEARCH_URL, ZDB_DEFAULT_REPLICAS};
+use crate::utils::{find_zdb_index, is_non_shadow_zdb_index};
 use crate::zql::ast::{IndexLink, QualifiedField};
 use crate::zql::transformations::field_finder::find_link_for_field;
 use crate::zql::{parse_field_lists, INDEX_LINK_PARSER};
@@ -13,17 +13,38 @@ use std::ffi::CStr;
 use std::fmt::Debug;
 
 const DEFAULT_BATCH_SIZE: i32 = 8 * 1024 * 1024;
+const MAX_BATCH_SIZE: i32 = (std::i32::MAX / 2) - 1;
 const DEFAULT_COMPRESSION_LEVEL: i32 = 1;
 const DEFAULT_SHARDS: i32 = 5;
+// the `replicas` reloption's default, which means "use the current zdb.default_replicas".  It's
+// outside the option's range, so it can't be set explicitly
+const REPLICAS_FROM_GUC: i32 = -1;
 const DEFAULT_OPTIMIZE_AFTER: i32 = 0;
 const DEFAULT_MAX_RESULT_WINDOW: i32 = 10000;
 const DEFAULT_NESTED_FIELDS_LIMIT: i32 = 1000;
 const DEFAULT_TOTAL_FIELDS_LIMIT: i32 = 1000;
 const DEFAULT_MAX_TERMS_COUNT: i32 = 65535;
+const DEFAULT_RECOVERY_PRIORITY: i32 = 1;
 const DEFAULT_URL: &str = "default";
 const DEFAULT_TYPE_NAME: &str = "doc";
 const DEFAULT_REFRESH_INTERVAL: &str = "-1";
 const DEFAULT_TRANSLOG_DURABILITY: &str = "request";
+const DEFAULT_CHECK_ON_STARTUP: &str = "false";
+const DEFAULT_WAIT_FOR_ACTIVE_SHARDS: &str = "1";
+const DEFAULT_QUERY_BOOST: f32 = 1.0;
+const DEFAULT_FLOOR_SEGMENT: &str = "2mb";
+const DEFAULT_SLOWLOG_SOURCE_LENGTH: i32 = 1000;
+const DEFAULT_AGGREGATION_TIMEZONE: &str = "UTC";
+const DEFAULT_ROUTING_PARTITION_SIZE: i32 = 1;
+const DEFAULT_FLUSH_MODE: &str = "transaction";
+const DEFAULT_MAX_SCRIPT_FIELDS: i32 = 32;
+const DEFAULT_STORE_TYPE: &str = "fs";
+const DEFAULT_OPTIMIZE_MAX_SEGMENTS: i32 = 1;
+const DEFAULT_COMPRESSION_TYPE: &str = "deflate";
+const DEFAULT_MAX_SHINGLE_DIFF: i32 = 3;
+const DEFAULT_OP_TYPE: &str = "index";
+const DEFAULT_TIEBREAKER_FIELD: &str = "zdb_ctid";
+const DEFAULT_FIELD_NAME_LENGTH_LIMIT: i32 = 50000;
 
 lazy_static! {
     static ref DEFAULT_BULK_CONCURRENCY: i32 = num_cpus::get() as i32;
@@ -46,6 +67,34 @@ impl RefreshInterval {
     }
 }
 
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum FlushMode {
+    Transaction,
+    Statement,
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum OpType {
+    Index,
+    Create,
+}
+
+impl OpType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OpType::Index => "index",
+            OpType::Create => "create",
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub enum CompressionType {
+    None,
+    Deflate,
+    Gzip,
+}
+
 #[repr(C)]
 struct ZDBIndexOptionsInternal {
     /* varlena header (do not touch directly!) */
@@ -71,12 +120,61 @@ struct ZDBIndexOptionsInternal {
     shards: i32,
     replicas: i32,
     bulk_concurrency: i32,
-    batch_size: i32,
+    batch_size_offset: i32,
     llapi: bool,
 
     nested_object_date_detection: bool,
     nested_object_numeric_detection: bool,
     nested_object_text_mapping_offset: i32,
+
+    recovery_priority: i32,
+    check_on_startup_offset: i32,
+    default_wait_for_active_shards_offset: i32,
+    soft_deletes: bool,
+    default_query_boost_offset: i32,
+    block_metadata: bool,
+    floor_segment_offset: i32,
+    trace_requests: bool,
+    replicas_step: bool,
+    slowlog_source_length: i32,
+    default_pipeline_offset: i32,
+    store_ctid_field: bool,
+    aggregation_timezone_offset: i32,
+    stopwords_offset: i32,
+    routing_partition_size: i32,
+    allow_leading_wildcard: bool,
+    disable_norms_fields_offset: i32,
+    tier_preference_offset: i32,
+    flush_mode_offset: i32,
+    final_pipeline_offset: i32,
+    max_script_fields: i32,
+    mapping_coerce: bool,
+    store_type_offset: i32,
+    username_offset: i32,
+    password_offset: i32,
+    search_throttled: bool,
+    verify_index_on_scan: bool,
+    optimize_max_segments: i32,
+    max_docs: i32,
+    search_preference_offset: i32,
+    compression_type_offset: i32,
+    ignore_malformed: bool,
+    request_timeout: i32,
+    max_concurrent_writers: i32,
+    max_shingle_diff: i32,
+    synthetic_source: bool,
+    op_type_offset: i32,
+    updatable_synonyms: bool,
+    eager_bitset_filters: bool,
+    rank_feature_fields_offset: i32,
+    dense_vector_fields_offset: i32,
+    auto_search_after: bool,
+    minimize_bulk_metadata: bool,
+    default_minimum_should_match_offset: i32,
+    tiebreaker_field_offset: i32,
+    store_doc_size: bool,
+    agg_max_buckets: i32,
+    field_name_length_limit: i32,
 }
 
 #[allow(dead_code)]
@@ -91,7 +189,6 @@ impl ZDBIndexOptionsInternal {
             ops.shards = DEFAULT_SHARDS;
             ops.replicas = ZDB_DEFAULT_REPLICAS.get();
             ops.bulk_concurrency = *DEFAULT_BULK_CONCURRENCY;
-            ops.batch_size = DEFAULT_BATCH_SIZE;
             ops.optimize_after = DEFAULT_OPTIMIZE_AFTER;
             ops.max_result_window = DEFAULT_MAX_RESULT_WINDOW;
             ops.nested_fields_limit = DEFAULT_NESTED_FIELDS_LIMIT;
@@ -99,6 +196,18 @@ impl ZDBIndexOptionsInternal {
             ops.max_terms_count = DEFAULT_MAX_TERMS_COUNT;
             ops.nested_object_date_detection = false;
             ops.nested_object_numeric_detection = false;
+            ops.recovery_priority = DEFAULT_RECOVERY_PRIORITY;
+            ops.soft_deletes = true;
+            ops.slowlog_source_length = DEFAULT_SLOWLOG_SOURCE_LENGTH;
+            ops.routing_partition_size = DEFAULT_ROUTING_PARTITION_SIZE;
+            ops.max_script_fields = DEFAULT_MAX_SCRIPT_FIELDS;
+            ops.mapping_coerce = true;
+            ops.optimize_max_segments = DEFAULT_OPTIMIZE_MAX_SEGMENTS;
+            ops.max_shingle_diff = DEFAULT_MAX_SHINGLE_DIFF;
+            ops.eager_bitset_filters = true;
+            ops.auto_search_after = true;
+            ops.minimize_bulk_metadata = true;
+            ops.field_name_length_limit = DEFAULT_FIELD_NAME_LENGTH_LIMIT;
             ops
         } else {
             PgBox::from_pg(relation.rd_options as *mut ZDBIndexOptionsInternal)
@@ -123,6 +232,10 @@ impl ZDBIndexOptionsInternal {
         }
     }
 
+    fn urls(&self) -> Vec<String> {
+        parse_urls(&self.url())
+    }
+
     fn type_name(&self) -> String {
         self.get_str(self.type_name_offset, || DEFAULT_TYPE_NAME.to_owned())
     }
@@ -217,6 +330,202 @@ impl ZDBIndexOptionsInternal {
         }
     }
 
+    fn check_on_startup(&self) -> String {
+        self.get_str(self.check_on_startup_offset, || DEFAULT_CHECK_ON_STARTUP.to_owned())
+    }
+
+    fn default_wait_for_active_shards(&self) -> String {
+        self.get_str(self.default_wait_for_active_shards_offset, || {
+            DEFAULT_WAIT_FOR_ACTIVE_SHARDS.to_owned()
+        })
+    }
+
+    fn default_query_boost(&self) -> f32 {
+        let value = self.get_str(self.default_query_boost_offset, || "".to_owned());
+        if value.is_empty() {
+            DEFAULT_QUERY_BOOST
+        } else {
+            value
+                .parse::<f32>()
+                .expect("invalid default_query_boost value")
+        }
+    }
+
+    fn floor_segment(&self) -> String {
+        self.get_str(self.floor_segment_offset, || DEFAULT_FLOOR_SEGMENT.to_owned())
+    }
+
+    fn default_pipeline(&self) -> Option<String> {
+        let pipeline = self.get_str(self.default_pipeline_offset, || "".to_owned());
+        if pipeline.is_empty() {
+            None
+        } else {
+            Some(pipeline)
+        }
+    }
+
+    fn aggregation_timezone(&self) -> String {
+        self.get_str(self.aggregation_timezone_offset, || {
+            DEFAULT_AGGREGATION_TIMEZONE.to_owned()
+        })
+    }
+
+    fn stopwords(&self) -> Option<Vec<String>> {
+        let stopwords = self.get_str(self.stopwords_offset, || "".to_owned());
+        if stopwords.is_empty() {
+            None
+        } else if stopwords == "_none_" {
+            Some(Vec::new())
+        } else {
+            Some(
+                stopwords
+                    .split(',')
+                    .map(|word| word.trim())
+                    .filter(|word| !word.is_empty())
+                    .map(|word| word.to_owned())
+                    .collect(),
+            )
+        }
+    }
+
+    fn disable_norms_fields(&self) -> Vec<String> {
+        let fields = self.get_str(self.disable_norms_fields_offset, || "".to_owned());
+        if fields.is_empty() {
+            Vec::new()
+        } else {
+            fields
+                .split(',')
+                .map(|field| field.trim().to_owned())
+                .collect()
+        }
+    }
+
+    fn tier_preference(&self) -> Option<String> {
+        let tiers = self.get_str(self.tier_preference_offset, || "".to_owned());
+        if tiers.is_empty() {
+            None
+        } else {
+            Some(tiers)
+        }
+    }
+
+    fn flush_mode(&self) -> FlushMode {
+        match self
+            .get_str(self.flush_mode_offset, || DEFAULT_FLUSH_MODE.to_owned())
+            .as_str()
+        {
+            "statement" => FlushMode::Statement,
+            _ => FlushMode::Transaction,
+        }
+    }
+
+    fn final_pipeline(&self) -> Option<String> {
+        let pipeline = self.get_str(self.final_pipeline_offset, || "".to_owned());
+        if pipeline.is_empty() {
+            None
+        } else {
+            Some(pipeline)
+        }
+    }
+
+    fn store_type(&self) -> String {
+        self.get_str(self.store_type_offset, || DEFAULT_STORE_TYPE.to_owned())
+    }
+
+    fn username(&self) -> Option<String> {
+        if self.username_offset == 0 {
+            None
+        } else {
+            Some(self.get_str(self.username_offset, || unreachable!()))
+        }
+    }
+
+    fn password(&self) -> Option<String> {
+        if self.password_offset == 0 {
+            None
+        } else {
+            Some(self.get_str(self.password_offset, || unreachable!()))
+        }
+    }
+
+    fn batch_size(&self) -> i32 {
+        if self.batch_size_offset == 0 {
+            DEFAULT_BATCH_SIZE
+        } else {
+            let batch_size = self.get_str(self.batch_size_offset, || unreachable!());
+            parse_batch_size(&batch_size).expect("invalid batch_size")
+        }
+    }
+
+    fn search_preference(&self) -> Option<String> {
+        if self.search_preference_offset == 0 {
+            None
+        } else {
+            Some(self.get_str(self.search_preference_offset, || unreachable!()))
+        }
+    }
+
+    fn compression_type(&self) -> CompressionType {
+        match self
+            .get_str(self.compression_type_offset, || DEFAULT_COMPRESSION_TYPE.to_owned())
+            .as_str()
+        {
+            "none" => CompressionType::None,
+            "gzip" => CompressionType::Gzip,
+            _ => CompressionType::Deflate,
+        }
+    }
+
+    fn op_type(&self) -> OpType {
+        match self
+            .get_str(self.op_type_offset, || DEFAULT_OP_TYPE.to_owned())
+            .as_str()
+        {
+            "create" => OpType::Create,
+            _ => OpType::Index,
+        }
+    }
+
+    fn rank_feature_fields(&self) -> Vec<String> {
+        let fields = self.get_str(self.rank_feature_fields_offset, || "".to_owned());
+        if fields.is_empty() {
+            Vec::new()
+        } else {
+            fields
+                .split(',')
+                .map(|field| field.trim().to_owned())
+                .collect()
+        }
+    }
+
+    fn dense_vector_fields(&self) -> Vec<(String, u32)> {
+        let fields = self.get_str(self.dense_vector_fields_offset, || "".to_owned());
+        if fields.is_empty() {
+            Vec::new()
+        } else {
+            fields
+                .split(',')
+                .map(|pair| parse_dense_vector_field(pair).expect("invalid dense_vector_fields"))
+                .collect()
+        }
+    }
+
+    fn default_minimum_should_match(&self) -> Option<String> {
+        let minimum_should_match =
+            self.get_str(self.default_minimum_should_match_offset, || "".to_owned());
+        if minimum_should_match.is_empty() {
+            None
+        } else {
+            Some(minimum_should_match)
+        }
+    }
+
+    fn tiebreaker_field(&self) -> String {
+        self.get_str(self.tiebreaker_field_offset, || {
+            DEFAULT_TIEBREAKER_FIELD.to_owned()
+        })
+    }
+
     fn get_str<F: FnOnce() -> String>(&self, offset: i32, default: F) -> String {
         if offset == 0 {
             default()
@@ -230,10 +539,11 @@ impl ZDBIndexOptionsInternal {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ZDBIndexOptions {
     oid: pg_sys::Oid,
     url: String,
+    urls: Vec<String>,
     type_name: String,
     refresh_interval: RefreshInterval,
     max_result_window: i32,
@@ -258,6 +568,55 @@ pub struct ZDBIndexOptions {
     nested_object_date_detection: bool,
     nested_object_numeric_detection: bool,
     nested_object_text_mapping: serde_json::Value,
+
+    recovery_priority: i32,
+    check_on_startup: String,
+    default_wait_for_active_shards: String,
+    soft_deletes: bool,
+    default_query_boost: f32,
+    block_metadata: bool,
+    floor_segment: String,
+    trace_requests: bool,
+    replicas_step: bool,
+    slowlog_source_length: i32,
+    default_pipeline: Option<String>,
+    store_ctid_field: bool,
+    aggregation_timezone: String,
+    stopwords: Option<Vec<String>>,
+    routing_partition_size: i32,
+    allow_leading_wildcard: bool,
+    disable_norms_fields: Vec<String>,
+    tier_preference: Option<String>,
+    flush_mode: FlushMode,
+    final_pipeline: Option<String>,
+    max_script_fields: i32,
+    mapping_coerce: bool,
+    store_type: String,
+    username: Option<String>,
+    password: Option<String>,
+    search_throttled: bool,
+    verify_index_on_scan: bool,
+    optimize_max_segments: i32,
+    max_docs: i32,
+    search_preference: Option<String>,
+    compression_type: CompressionType,
+    ignore_malformed: bool,
+    request_timeout: i32,
+    max_concurrent_writers: i32,
+    max_shingle_diff: i32,
+    synthetic_source: bool,
+    op_type: OpType,
+    updatable_synonyms: bool,
+    eager_bitset_filters: bool,
+    rank_feature_fields: Vec<String>,
+    dense_vector_fields: Vec<(String, u32)>,
+    auto_search_after: bool,
+    minimize_bulk_metadata: bool,
+    default_minimum_should_match: Option<String>,
+    tiebreaker_field: String,
+    store_doc_size: bool,
+    agg_max_buckets: i32,
+    field_name_length_limit: i32,
 }
 
 #[allow(dead_code)]
@@ -267,15 +626,26 @@ impl ZDBIndexOptions {
         ZDBIndexOptions::from_relation_no_lookup(&relation, options)
     }
 
+    /// Returns the options of the first ZomboDB index on `heaprel`, or `None` if the table
+    /// doesn't have one
+    pub fn from_heap(heaprel: &PgRelation) -> Option<ZDBIndexOptions> {
+        heaprel
+            .indicies(pg_sys::AccessShareLock as pg_sys::LOCKMODE)
+            .find(|index| is_non_shadow_zdb_index(index))
+            .map(|index| ZDBIndexOptions::from_relation_no_lookup(&index, None))
+    }
+
     pub fn from_relation_no_lookup(
         relation: &PgRelation,
         options: Option<Vec<String>>,
     ) -> ZDBIndexOptions {
         let internal = ZDBIndexOptionsInternal::from_relation(&relation);
         let heap_relation = relation.heap_relation().expect("not an index");
+        let urls = internal.urls();
         ZDBIndexOptions {
             oid: relation.oid(),
-            url: internal.url(),
+            url: urls[0].clone(),
+            urls,
             type_name: internal.type_name(),
             refresh_interval: internal.refresh_interval(),
             max_result_window: internal.max_result_window,
@@ -289,15 +659,67 @@ impl ZDBIndexOptions {
             shadow_index: internal.shadow_index,
             compression_level: internal.compression_level,
             shards: internal.shards,
-            replicas: internal.replicas,
+            replicas: if internal.replicas == REPLICAS_FROM_GUC {
+                ZDB_DEFAULT_REPLICAS.get()
+            } else {
+                internal.replicas
+            },
             bulk_concurrency: internal.bulk_concurrency,
-            batch_size: internal.batch_size,
+            batch_size: internal.batch_size(),
             optimize_after: internal.optimize_after,
             translog_durability: internal.translog_durability(),
             llapi: internal.llapi,
             nested_object_date_detection: internal.nested_object_date_detection,
             nested_object_numeric_detection: internal.nested_object_numeric_detection,
             nested_object_text_mapping: internal.nested_object_text_mapping(),
+            recovery_priority: internal.recovery_priority,
+            check_on_startup: internal.check_on_startup(),
+            default_wait_for_active_shards: internal.default_wait_for_active_shards(),
+            soft_deletes: internal.soft_deletes,
+            default_query_boost: internal.default_query_boost(),
+            block_metadata: internal.block_metadata,
+            floor_segment: internal.floor_segment(),
+            trace_requests: internal.trace_requests,
+            replicas_step: internal.replicas_step,
+            slowlog_source_length: internal.slowlog_source_length,
+            default_pipeline: internal.default_pipeline(),
+            store_ctid_field: internal.store_ctid_field,
+            aggregation_timezone: internal.aggregation_timezone(),
+            stopwords: internal.stopwords(),
+            routing_partition_size: internal.routing_partition_size,
+            allow_leading_wildcard: internal.allow_leading_wildcard,
+            disable_norms_fields: internal.disable_norms_fields(),
+            tier_preference: internal.tier_preference(),
+            flush_mode: internal.flush_mode(),
+            final_pipeline: internal.final_pipeline(),
+            max_script_fields: internal.max_script_fields,
+            mapping_coerce: internal.mapping_coerce,
+            store_type: internal.store_type(),
+            username: internal.username(),
+            password: internal.password(),
+            search_throttled: internal.search_throttled,
+            verify_index_on_scan: internal.verify_index_on_scan,
+            optimize_max_segments: internal.optimize_max_segments,
+            max_docs: internal.max_docs,
+            search_preference: internal.search_preference(),
+            compression_type: internal.compression_type(),
+            ignore_malformed: internal.ignore_malformed,
+            request_timeout: internal.request_timeout,
+            max_concurrent_writers: internal.max_concurrent_writers,
+            max_shingle_diff: internal.max_shingle_diff,
+            synthetic_source: internal.synthetic_source,
+            op_type: internal.op_type(),
+            updatable_synonyms: internal.updatable_synonyms,
+            eager_bitset_filters: internal.eager_bitset_filters,
+            rank_feature_fields: internal.rank_feature_fields(),
+            dense_vector_fields: internal.dense_vector_fields(),
+            auto_search_after: internal.auto_search_after,
+            minimize_bulk_metadata: internal.minimize_bulk_metadata,
+            default_minimum_should_match: internal.default_minimum_should_match(),
+            tiebreaker_field: internal.tiebreaker_field(),
+            store_doc_size: internal.store_doc_size,
+            agg_max_buckets: internal.agg_max_buckets,
+            field_name_length_limit: internal.field_name_length_limit,
         }
     }
 
@@ -329,8 +751,9 @@ impl ZDBIndexOptions {
         self.replicas
     }
 
+    /// The index's `bulk_concurrency`, clamped to the `zdb.max_bulk_concurrency` GUC
     pub fn bulk_concurrency(&self) -> i32 {
-        self.bulk_concurrency
+        self.bulk_concurrency.min(max_bulk_concurrency())
     }
 
     pub fn batch_size(&self) -> i32 {
@@ -345,6 +768,10 @@ impl ZDBIndexOptions {
         &self.url
     }
 
+    pub fn urls(&self) -> Vec<String> {
+        self.urls.clone()
+    }
+
     pub fn type_name(&self) -> &str {
         &self.type_name
     }
@@ -411,6 +838,221 @@ impl ZDBIndexOptions {
     pub fn nested_object_text_mapping(&self) -> &serde_json::Value {
         &self.nested_object_text_mapping
     }
+
+    pub fn recovery_priority(&self) -> i32 {
+        self.recovery_priority
+    }
+
+    pub fn check_on_startup(&self) -> &str {
+        &self.check_on_startup
+    }
+
+    pub fn default_wait_for_active_shards(&self) -> &str {
+        &self.default_wait_for_active_shards
+    }
+
+    pub fn soft_deletes(&self) -> bool {
+        self.soft_deletes
+    }
+
+    pub fn default_query_boost(&self) -> f32 {
+        self.default_query_boost
+    }
+
+    pub fn block_metadata(&self) -> bool {
+        self.block_metadata
+    }
+
+    pub fn floor_segment(&self) -> &str {
+        &self.floor_segment
+    }
+
+    pub fn trace_requests(&self) -> bool {
+        self.trace_requests
+    }
+
+    pub fn replicas_step(&self) -> bool {
+        self.replicas_step
+    }
+
+    pub fn slowlog_source_length(&self) -> i32 {
+        self.slowlog_source_length
+    }
+
+    pub fn default_pipeline(&self) -> Option<String> {
+        self.default_pipeline.clone()
+    }
+
+    pub fn store_ctid_field(&self) -> bool {
+        self.store_ctid_field
+    }
+
+    pub fn aggregation_timezone(&self) -> &str {
+        &self.aggregation_timezone
+    }
+
+    pub fn stopwords(&self) -> Option<&Vec<String>> {
+        self.stopwords.as_ref()
+    }
+
+    pub fn routing_partition_size(&self) -> i32 {
+        self.routing_partition_size
+    }
+
+    pub fn allow_leading_wildcard(&self) -> bool {
+        self.allow_leading_wildcard
+    }
+
+    pub fn disable_norms_fields(&self) -> &Vec<String> {
+        &self.disable_norms_fields
+    }
+
+    pub fn tier_preference(&self) -> Option<String> {
+        self.tier_preference.clone()
+    }
+
+    pub fn flush_mode(&self) -> FlushMode {
+        self.flush_mode
+    }
+
+    pub fn final_pipeline(&self) -> Option<String> {
+        self.final_pipeline.clone()
+    }
+
+    pub fn max_script_fields(&self) -> i32 {
+        self.max_script_fields
+    }
+
+    pub fn mapping_coerce(&self) -> bool {
+        self.mapping_coerce
+    }
+
+    pub fn store_type(&self) -> &str {
+        &self.store_type
+    }
+
+    pub fn username(&self) -> Option<String> {
+        self.username.clone()
+    }
+
+    pub fn password(&self) -> Option<String> {
+        self.password.clone()
+    }
+
+    /// The value for an HTTP `Authorization` header, when both `username` and `password` are set
+    pub fn basic_auth_header(&self) -> Option<String> {
+        match (&self.username, &self.password) {
+            (Some(username), Some(password)) => Some(format!(
+                "Basic {}",
+                base64_encode(format!("{}:{}", username, password).as_bytes())
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn search_throttled(&self) -> bool {
+        self.search_throttled
+    }
+
+    pub fn verify_index_on_scan(&self) -> bool {
+        self.verify_index_on_scan
+    }
+
+    pub fn optimize_max_segments(&self) -> i32 {
+        self.optimize_max_segments
+    }
+
+    pub fn max_docs(&self) -> i32 {
+        self.max_docs
+    }
+
+    pub fn search_preference(&self) -> Option<String> {
+        self.search_preference.clone()
+    }
+
+    /// The HTTP(s) request body compression encoding.  When this is [`CompressionType::None`]
+    /// callers should ignore [`ZDBIndexOptions::compression_level()`] entirely
+    pub fn compression_type(&self) -> CompressionType {
+        self.compression_type
+    }
+
+    /// Should request bodies be compressed at all?  This is false when `compression_type` is
+    /// `none` or when `compression_level` is zero
+    pub fn is_compression_enabled(&self) -> bool {
+        self.compression_type != CompressionType::None && self.compression_level > 0
+    }
+
+    pub fn ignore_malformed(&self) -> bool {
+        self.ignore_malformed
+    }
+
+    /// The timeout for HTTP requests to Elasticsearch, or `None` if requests shouldn't time out
+    pub fn request_timeout(&self) -> Option<std::time::Duration> {
+        match self.request_timeout {
+            0 => None,
+            secs => Some(std::time::Duration::from_secs(secs as u64)),
+        }
+    }
+
+    pub fn max_concurrent_writers(&self) -> i32 {
+        self.max_concurrent_writers
+    }
+
+    pub fn max_shingle_diff(&self) -> i32 {
+        self.max_shingle_diff
+    }
+
+    pub fn synthetic_source(&self) -> bool {
+        self.synthetic_source
+    }
+
+    pub fn op_type(&self) -> OpType {
+        self.op_type
+    }
+
+    pub fn updatable_synonyms(&self) -> bool {
+        self.updatable_synonyms
+    }
+
+    pub fn eager_bitset_filters(&self) -> bool {
+        self.eager_bitset_filters
+    }
+
+    pub fn rank_feature_fields(&self) -> &Vec<String> {
+        &self.rank_feature_fields
+    }
+
+    pub fn dense_vector_fields(&self) -> &Vec<(String, u32)> {
+        &self.dense_vector_fields
+    }
+
+    pub fn auto_search_after(&self) -> bool {
+        self.auto_search_after
+    }
+
+    pub fn minimize_bulk_metadata(&self) -> bool {
+        self.minimize_bulk_metadata
+    }
+
+    pub fn default_minimum_should_match(&self) -> Option<&str> {
+        self.default_minimum_should_match.as_deref()
+    }
+
+    pub fn tiebreaker_field(&self) -> &str {
+        &self.tiebreaker_field
+    }
+
+    pub fn store_doc_size(&self) -> bool {
+        self.store_doc_size
+    }
+
+    pub fn agg_max_buckets(&self) -> i32 {
+        self.agg_max_buckets
+    }
+
+    pub fn field_name_length_limit(&self) -> i32 {
+        self.field_name_length_limit
+    }
 }
 
 /// ```sql
@@ -495,6 +1137,35 @@ pub(crate) fn index_options(index_relation: PgRelation) -> Option<Vec<String>> {
         .clone()
 }
 
+/// Returns the index's options as ZomboDB will use them, with defaults applied to the ones that
+/// weren't set in `CREATE INDEX` or `ALTER INDEX`
+#[pg_extern(volatile, parallel_safe)]
+fn resolved_index_options(index_relation: PgRelation) -> JsonB {
+    let options = ZDBIndexOptions::from_relation(&index_relation);
+    let refresh_interval = match options.refresh_interval() {
+        RefreshInterval::Immediate => "immediate".to_owned(),
+        RefreshInterval::ImmediateAsync => "async".to_owned(),
+        RefreshInterval::Background(interval) => interval,
+    };
+
+    JsonB(serde_json::json! {
+        {
+            "url": options.url(),
+            "type_name": options.type_name(),
+            "refresh_interval": refresh_interval,
+            "alias": options.alias(),
+            "uuid": options.uuid(),
+            "shards": options.shards(),
+            "replicas": options.replicas(),
+            "bulk_concurrency": options.bulk_concurrency(),
+            "batch_size": options.batch_size(),
+            "compression_level": options.compression_level(),
+            "optimize_after": options.optimize_after(),
+            "llapi": options.llapi(),
+        }
+    })
+}
+
 #[pg_extern(volatile, parallel_safe)]
 fn index_field_lists(
     index_relation: PgRelation,
@@ -552,6 +1223,82 @@ fn field_mapping(index_relation: PgRelation, field: &str) -> Option<JsonB> {
     })
 }
 
+/// Generates an `ALTER INDEX ... SET (...)` statement for every ZomboDB index that has
+/// options explicitly set, which can be used to restore those options after a migration
+#[pg_extern(volatile, parallel_safe)]
+fn export_options_sql() -> impl std::iter::Iterator<Item = String> {
+    let mut statements = Vec::new();
+
+    Spi::connect(|client| {
+        let mut results = client.select(
+            "SELECT format('ALTER INDEX %s SET (%s);', c.oid::regclass,
+                           (SELECT string_agg(quote_ident(split_part(opt, '=', 1)) || '=' || quote_literal(substr(opt, strpos(opt, '=') + 1)), ', ')
+                              FROM unnest(c.reloptions) opt))
+               FROM pg_class c
+              WHERE c.relkind = 'i'
+                AND c.relam = (SELECT oid FROM pg_am WHERE amname = 'zombodb')
+                AND c.reloptions IS NOT NULL
+              ORDER BY c.oid;",
+            None,
+            None,
+        );
+
+        while results.next().is_some() {
+            statements.push(results.get_one().expect("ALTER INDEX statement was null"))
+        }
+
+        Ok(Some(()))
+    });
+
+    statements.into_iter()
+}
+
+#[pg_extern(volatile, parallel_safe)]
+pub(crate) fn query_fields(
+    index_relation: PgRelation,
+) -> impl std::iter::Iterator<Item = (name!(field, String), name!(es_type, String))> {
+    let options = ZDBIndexOptions::from_relation(&index_relation);
+    let mut mapping = index_mapping(index_relation).0;
+    let properties = mapping
+        .get_mut(options.index_name())
+        .and_then(|index| index.get_mut("mappings"))
+        .and_then(|mappings| mappings.get_mut("properties"))
+        .map(|properties| properties.take())
+        .expect("no properties object in mapping");
+
+    let mut fields = Vec::new();
+    if let serde_json::Value::Object(properties) = properties {
+        collect_query_fields(None, &properties, &mut fields);
+    }
+    fields.into_iter()
+}
+
+fn collect_query_fields(
+    prefix: Option<&str>,
+    properties: &serde_json::Map<String, serde_json::Value>,
+    fields: &mut Vec<(String, String)>,
+) {
+    for (name, definition) in properties {
+        let field = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, name),
+            None => name.clone(),
+        };
+        let nested = definition.get("properties").and_then(|p| p.as_object());
+        let es_type = definition.get("type").and_then(|t| t.as_str());
+
+        // fields without an explicit type, but with sub-properties, are plain objects
+        match (es_type, nested) {
+            (Some(es_type), _) => fields.push((field.clone(), es_type.to_owned())),
+            (None, Some(_)) => fields.push((field.clone(), "object".to_owned())),
+            (None, None) => {}
+        }
+
+        if let Some(nested) = nested {
+            collect_query_fields(Some(&field), nested, fields);
+        }
+    }
+}
+
 static mut RELOPT_KIND_ZDB: pg_sys::relopt_kind = 0;
 
 #[pg_guard]
@@ -565,18 +1312,79 @@ extern "C" fn validate_url(url: *const std::os::raw::c_char) {
         return;
     }
 
-    if !url.ends_with('/') {
-        panic!("url must end with a forward slash");
-    }
+    // the url can also be a comma-separated list of urls, each of which must be valid
+    for (i, segment) in url.split(',').map(|segment| segment.trim()).enumerate() {
+        if segment.is_empty() {
+            panic!("url entry #{} is empty: '{}'", i + 1, url);
+        }
 
-    if let Err(e) = url::Url::parse(url) {
-        panic!("{}", e.to_string())
+        // a missing trailing slash is fine as ZDBIndexOptions adds it back
+        match url::Url::parse(segment) {
+            Ok(parsed) if !matches!(parsed.scheme(), "http" | "https") => {
+                panic!("url must use the http or https scheme: {}", segment)
+            }
+            Ok(_) => {}
+            Err(e) => panic!("{}", e.to_string()),
+        }
     }
 }
 
-#[pg_guard]
-extern "C" fn validate_translog_durability(value: *const std::os::raw::c_char) {
-    if value.is_null() {
+/// Splits a comma-separated `url` into its urls.  zdb.default_elasticsearch_url isn't checked by
+/// `validate_url()`, so this also needs to reject a value without any urls in it
+fn parse_urls(url: &str) -> Vec<String> {
+    let urls = url
+        .split(',')
+        .map(|url| url.trim())
+        .filter(|url| !url.is_empty())
+        .map(|url| {
+            // we build request urls by appending to this one, so it needs a trailing slash
+            if url.ends_with('/') {
+                url.to_owned()
+            } else {
+                format!("{}/", url)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if urls.is_empty() {
+        panic!(
+            "no Elasticsearch url in '{}'.  Set the index's url option or zdb.default_elasticsearch_url",
+            url
+        );
+    }
+    urls
+}
+
+#[pg_guard]
+extern "C" fn validate_alias(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll generate an alias
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert alias to utf8");
+
+    // these are Elasticsearch's rules for index and alias names
+    if value.is_empty() {
+        panic!("alias must not be empty")
+    } else if value.len() > 255 {
+        panic!("alias must not be longer than 255 bytes: {}", value)
+    } else if value == "." || value == ".." {
+        panic!("alias must not be '.' or '..'")
+    } else if value.chars().any(|c| c.is_uppercase()) {
+        panic!("alias must be lowercase: {}", value)
+    } else if value.starts_with(|c| matches!(c, '-' | '_' | '+')) {
+        panic!("alias must not start with '-', '_', or '+': {}", value)
+    } else if let Some(c) = value.chars().find(|c| r#"\/*?"<>| ,#:"#.contains(*c)) {
+        panic!("alias must not contain '{}': {}", c, value)
+    }
+}
+
+#[pg_guard]
+extern "C" fn validate_translog_durability(value: *const std::os::raw::c_char) {
+    if value.is_null() {
         // null is fine -- we'll just use our default
         return;
     }
@@ -650,542 +1458,3533 @@ extern "C" fn validate_text_mapping(value: *const std::os::raw::c_char) {
     .expect("invalid nested_object_text_mapping");
 }
 
-const NUM_REL_OPTS: usize = 23;
-#[allow(clippy::unneeded_field_pattern)] // b/c of offset_of!()
 #[pg_guard]
-pub unsafe extern "C" fn amoptions(
-    reloptions: pg_sys::Datum,
-    validate: bool,
-) -> *mut pg_sys::bytea {
-    // TODO:  how to make this const?  we can't use offset_of!() macro in const definitions, apparently
-    let tab: [pg_sys::relopt_parse_elt; NUM_REL_OPTS] = [
-        pg_sys::relopt_parse_elt {
-            optname: "url".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
-            offset: offset_of!(ZDBIndexOptionsInternal, url_offset) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "type_name".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
-            offset: offset_of!(ZDBIndexOptionsInternal, type_name_offset) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "refresh_interval".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
-            offset: offset_of!(ZDBIndexOptionsInternal, refresh_interval_offset) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "shards".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
-            offset: offset_of!(ZDBIndexOptionsInternal, shards) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "replicas".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
-            offset: offset_of!(ZDBIndexOptionsInternal, replicas) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "bulk_concurrency".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
-            offset: offset_of!(ZDBIndexOptionsInternal, bulk_concurrency) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "batch_size".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
-            offset: offset_of!(ZDBIndexOptionsInternal, batch_size) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "compression_level".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
-            offset: offset_of!(ZDBIndexOptionsInternal, compression_level) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "max_result_window".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
-            offset: offset_of!(ZDBIndexOptionsInternal, max_result_window) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "nested_fields_limit".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
-            offset: offset_of!(ZDBIndexOptionsInternal, nested_fields_limit) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "total_fields_limit".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
-            offset: offset_of!(ZDBIndexOptionsInternal, total_fields_limit) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "max_terms_count".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
-            offset: offset_of!(ZDBIndexOptionsInternal, max_terms_count) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "alias".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
-            offset: offset_of!(ZDBIndexOptionsInternal, alias_offset) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "optimize_after".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
-            offset: offset_of!(ZDBIndexOptionsInternal, optimize_after) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "llapi".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
-            offset: offset_of!(ZDBIndexOptionsInternal, llapi) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "uuid".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
-            offset: offset_of!(ZDBIndexOptionsInternal, uuid_offset) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "translog_durability".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
-            offset: offset_of!(ZDBIndexOptionsInternal, translog_durability_offset) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "options".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
-            offset: offset_of!(ZDBIndexOptionsInternal, options_offset) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "field_lists".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
-            offset: offset_of!(ZDBIndexOptionsInternal, field_lists_offset) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "shadow".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
-            offset: offset_of!(ZDBIndexOptionsInternal, shadow_index) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "nested_object_date_detection".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
-            offset: offset_of!(ZDBIndexOptionsInternal, nested_object_date_detection) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "nested_object_numeric_detection".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
-            offset: offset_of!(ZDBIndexOptionsInternal, nested_object_numeric_detection) as i32,
-        },
-        pg_sys::relopt_parse_elt {
-            optname: "nested_object_text_mapping".as_pg_cstr(),
-            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
-            offset: offset_of!(ZDBIndexOptionsInternal, nested_object_text_mapping_offset) as i32,
-        },
-    ];
+extern "C" fn validate_check_on_startup(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
 
-    build_relopts(reloptions, validate, tab)
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert check_on_startup to utf8");
+    if value != "false" && value != "checksum" && value != "true" {
+        panic!(
+            "invalid check_on_startup setting.  Must be one of 'false', 'checksum', or 'true': {}",
+            value
+        )
+    }
 }
 
-#[cfg(feature = "pg13")]
-unsafe fn build_relopts(
-    reloptions: pg_sys::Datum,
-    validate: bool,
-    tab: [pg_sys::relopt_parse_elt; NUM_REL_OPTS],
-) -> *mut pg_sys::bytea {
-    let rdopts;
-
-    /* Parse the user-given reloptions */
-    rdopts = pg_sys::build_reloptions(
-        reloptions,
-        validate,
-        RELOPT_KIND_ZDB,
-        std::mem::size_of::<ZDBIndexOptionsInternal>(),
-        tab.as_ptr(),
-        NUM_REL_OPTS as i32,
-    );
+#[pg_guard]
+extern "C" fn validate_default_wait_for_active_shards(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
 
-    rdopts as *mut pg_sys::bytea
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert default_wait_for_active_shards to utf8");
+    if value != "all" && value.parse::<u32>().is_err() {
+        panic!(
+            "invalid default_wait_for_active_shards setting.  Must be a non-negative number or 'all': {}",
+            value
+        )
+    }
 }
 
-#[cfg(not(feature = "pg13"))]
-unsafe fn build_relopts(
-    reloptions: pg_sys::Datum,
-    validate: bool,
-    tab: [pg_sys::relopt_parse_elt; NUM_REL_OPTS],
-) -> *mut pg_sys::bytea {
-    let mut noptions = 0;
-    let options = pg_sys::parseRelOptions(reloptions, validate, RELOPT_KIND_ZDB, &mut noptions);
-    if noptions == 0 {
-        return std::ptr::null_mut();
+#[pg_guard]
+extern "C" fn validate_default_query_boost(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
     }
 
-    for relopt in std::slice::from_raw_parts_mut(options, noptions as usize) {
-        relopt.gen.as_mut().unwrap().lockmode = pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE;
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert default_query_boost to utf8");
+    match value.parse::<f32>() {
+        Ok(boost) if boost.is_finite() && boost >= 0.0 => {}
+        _ => panic!(
+            "invalid default_query_boost setting.  Must be a non-negative number: {}",
+            value
+        ),
     }
+}
 
-    let rdopts = pg_sys::allocateReloptStruct(
-        std::mem::size_of::<ZDBIndexOptionsInternal>(),
-        options,
-        noptions,
-    );
-    pg_sys::fillRelOptions(
-        rdopts,
-        std::mem::size_of::<ZDBIndexOptionsInternal>(),
-        options,
-        noptions,
-        validate,
-        tab.as_ptr(),
-        tab.len() as i32,
-    );
-    pg_sys::pfree(options as void_mut_ptr);
+/// Parses an Elasticsearch byte size value, such as `512kb` or `2mb`, into a number of bytes
+fn parse_byte_size(value: &str) -> Option<u64> {
+    let value = value.trim().to_lowercase();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier: u64 = match unit {
+        "b" => 1,
+        "kb" => 1 << 10,
+        "mb" => 1 << 20,
+        "gb" => 1 << 30,
+        _ => return None,
+    };
 
-    rdopts as *mut pg_sys::bytea
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
 }
 
-pub unsafe fn init() {
-    RELOPT_KIND_ZDB = pg_sys::add_reloption_kind();
+/// Parses a `batch_size` value, which is either a plain number of bytes or a byte size such as
+/// `16mb`, and ensures it's within the range ZomboDB supports
+fn parse_batch_size(value: &str) -> Option<i32> {
+    let bytes = if value.trim().chars().all(|c| c.is_ascii_digit()) {
+        value.trim().parse::<u64>().ok()?
+    } else {
+        parse_byte_size(value)?
+    };
 
-    pg_sys::add_string_reloption(
-        RELOPT_KIND_ZDB,
-        "url".as_pg_cstr(),
-        "Server URL and port".as_pg_cstr(),
-        "default".as_pg_cstr(),
-        Some(validate_url),
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_string_reloption(
-        RELOPT_KIND_ZDB,
-        "type_name".as_pg_cstr(),
-        "What Elasticsearch index type name should ZDB use?  Default is 'doc'".as_pg_cstr(),
-        "doc".as_pg_cstr(),
-        None,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_string_reloption(
-        RELOPT_KIND_ZDB,
-        "refresh_interval".as_pg_cstr(),
-        "Frequency in which Elasticsearch indexes are refreshed.  Related to ES' index.refresh_interval setting".as_pg_cstr(),
-        DEFAULT_REFRESH_INTERVAL.as_pg_cstr(),
-        None,
-        #[cfg(feature = "pg13")]
-            { pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE },
-    );
-    pg_sys::add_int_reloption(
-        RELOPT_KIND_ZDB,
-        "shards".as_pg_cstr(),
-        "The number of shards for the index".as_pg_cstr(),
-        DEFAULT_SHARDS,
-        1,
-        32768,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_int_reloption(
-        RELOPT_KIND_ZDB,
-        "replicas".as_pg_cstr(),
-        "The number of replicas for the index".as_pg_cstr(),
-        ZDB_DEFAULT_REPLICAS.get(),
-        0,
-        32768,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_int_reloption(
-        RELOPT_KIND_ZDB,
-        "bulk_concurrency".as_pg_cstr(),
-        "The maximum number of concurrent _bulk API requests".as_pg_cstr(),
-        *DEFAULT_BULK_CONCURRENCY,
-        1,
-        num_cpus::get() as i32,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_int_reloption(
-        RELOPT_KIND_ZDB,
-        "batch_size".as_pg_cstr(),
-        "The size in bytes of batch calls to the _bulk API".as_pg_cstr(),
-        DEFAULT_BATCH_SIZE,
-        1,
-        (std::i32::MAX / 2) - 1,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_int_reloption(
-        RELOPT_KIND_ZDB,
-        "compression_level".as_pg_cstr(),
-        "0-9 value to indicate the level of HTTP compression".as_pg_cstr(),
-        DEFAULT_COMPRESSION_LEVEL,
-        0,
-        9,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_int_reloption(
-        RELOPT_KIND_ZDB,
-        "max_result_window".as_pg_cstr(),
-        "The number of docs to page in from Elasticsearch at one time.  Default is 10,000"
-            .as_pg_cstr(),
-        DEFAULT_MAX_RESULT_WINDOW,
-        1,
-        std::i32::MAX,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_int_reloption(
-        RELOPT_KIND_ZDB,
-        "nested_fields_limit".as_pg_cstr(),
-        "The maximum number of distinct nested mappings in an index.  Default is 1000".as_pg_cstr(),
-        DEFAULT_NESTED_FIELDS_LIMIT,
-        1,
-        std::i32::MAX,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_int_reloption(
-        RELOPT_KIND_ZDB,
-        "total_fields_limit".as_pg_cstr(),
-        "The maximum number of fields in an index. Field and object mappings, as well as field aliases count towards this limit. The default value is 1000.".as_pg_cstr(),
-        DEFAULT_TOTAL_FIELDS_LIMIT,
-        1,
-        std::i32::MAX,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_int_reloption(
-        RELOPT_KIND_ZDB,
-        "max_terms_count".as_pg_cstr(),
-        "The maximum number of terms that can be used in Terms Query.  The default value is 65535."
-            .as_pg_cstr(),
-        DEFAULT_MAX_TERMS_COUNT,
-        1,
-        std::i32::MAX,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_string_reloption(
-        RELOPT_KIND_ZDB,
-        "alias".as_pg_cstr(),
-        "The Elasticsearch Alias to which this index should belong".as_pg_cstr(),
-        std::ptr::null(),
-        None,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_string_reloption(
-        RELOPT_KIND_ZDB,
-        "uuid".as_pg_cstr(),
-        "The Elasticsearch index name, as a UUID".as_pg_cstr(),
-        std::ptr::null(),
-        None,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_string_reloption(
-        RELOPT_KIND_ZDB,
-        "translog_durability".as_pg_cstr(),
-        "Elasticsearch index.translog.durability setting.  Defaults to 'request'".as_pg_cstr(),
-        "request".as_pg_cstr(),
-        Some(validate_translog_durability),
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_int_reloption(
-        RELOPT_KIND_ZDB,
-        "optimize_after".as_pg_cstr(),
-        "After how many deleted docs should ZDB _optimize the ES index during VACUUM?".as_pg_cstr(),
-        DEFAULT_OPTIMIZE_AFTER,
-        0,
-        std::i32::MAX,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_bool_reloption(
-        RELOPT_KIND_ZDB,
-        "llapi".as_pg_cstr(),
-        "Will this index be used by ZomboDB's low-level API?".as_pg_cstr(),
-        false,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_string_reloption(
-        RELOPT_KIND_ZDB,
-        "options".as_pg_cstr(),
-        "ZomboDB Index Linking options".as_pg_cstr(),
-        std::ptr::null(),
-        Some(validate_options),
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_string_reloption(
-        RELOPT_KIND_ZDB,
-        "field_lists".as_pg_cstr(),
-        "Combine fields into named lists during search".as_pg_cstr(),
-        std::ptr::null(),
-        Some(validate_field_lists),
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_bool_reloption(
-        RELOPT_KIND_ZDB,
-        "shadow".as_pg_cstr(),
-        "Is this index a shadow index, and if so, to which one".as_pg_cstr(),
-        false,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_bool_reloption(
-        RELOPT_KIND_ZDB,
-        "nested_object_date_detection".as_pg_cstr(),
-        "Should ES try to automatically detect dates in nested objects".as_pg_cstr(),
-        false,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_bool_reloption(
-        RELOPT_KIND_ZDB,
-        "nested_object_numeric_detection".as_pg_cstr(),
-        "Should ES try to automatically detect numbers in nested objects".as_pg_cstr(),
-        false,
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
-    pg_sys::add_string_reloption(
-        RELOPT_KIND_ZDB,
-        "nested_object_text_mapping".as_pg_cstr(),
-        "As a JSON mapping definition, how should dynamic text values in JSON be mapped?".as_pg_cstr(),
-        r#"{ "type": "keyword", "ignore_above": 10922, "normalizer": "lowercase", "copy_to": "zdb_all" }"#.as_pg_cstr(),
-        Some(validate_text_mapping),
-        #[cfg(feature = "pg13")]
-        {
-            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
-        },
-    );
+    if (1..=MAX_BATCH_SIZE as u64).contains(&bytes) {
+        Some(bytes as i32)
+    } else {
+        None
+    }
 }
 
-#[cfg(any(test, feature = "pg_test"))]
-mod tests {
-    use crate::access_method::options::{
-        validate_translog_durability, validate_url, RefreshInterval, ZDBIndexOptions,
-        DEFAULT_BATCH_SIZE, DEFAULT_BULK_CONCURRENCY, DEFAULT_COMPRESSION_LEVEL,
-        DEFAULT_OPTIMIZE_AFTER, DEFAULT_SHARDS, DEFAULT_TYPE_NAME,
-    };
-    use crate::gucs::ZDB_DEFAULT_REPLICAS;
-    use pgx::pg_sys::AsPgCStr;
-    use pgx::*;
+#[pg_guard]
+extern "C" fn validate_batch_size(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert batch_size to utf8");
+    if parse_batch_size(value).is_none() {
+        panic!(
+            "invalid batch_size setting.  Must be between 1 and {} bytes, such as '16mb' or '512kb': {}",
+            MAX_BATCH_SIZE, value
+        )
+    }
+}
+
+#[pg_guard]
+extern "C" fn validate_floor_segment(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert floor_segment to utf8");
+    if parse_byte_size(value).is_none() {
+        panic!(
+            "invalid floor_segment setting.  Must be a byte size such as '512kb' or '2mb': {}",
+            value
+        )
+    }
+}
+
+lazy_static! {
+    static ref TIMEZONE_OFFSET: regex::Regex =
+        regex::Regex::new(r"^[+-]([01][0-9]|2[0-3])(:?[0-5][0-9])?$").unwrap();
+    static ref TIMEZONE_NAME: regex::Regex = regex::Regex::new(
+        r"^(Africa|America|Antarctica|Arctic|Asia|Atlantic|Australia|Europe|Indian|Pacific|Etc)(/[A-Za-z0-9_+\-]+){1,2}$"
+    )
+    .unwrap();
+}
+
+fn is_valid_timezone(value: &str) -> bool {
+    matches!(value, "UTC" | "GMT" | "Z")
+        || TIMEZONE_OFFSET.is_match(value)
+        || TIMEZONE_NAME.is_match(value)
+}
+
+#[pg_guard]
+extern "C" fn validate_aggregation_timezone(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert aggregation_timezone to utf8");
+    if !is_valid_timezone(value) {
+        panic!(
+            "invalid aggregation_timezone setting.  Must be 'UTC', an offset such as '+01:00', or a region name such as 'America/New_York': {}",
+            value
+        )
+    }
+}
+
+fn validate_routing_partition_size(options: &ZDBIndexOptionsInternal) {
+    // Elasticsearch allows a partition size of 1 regardless of the number of shards
+    if options.routing_partition_size > 1 && options.routing_partition_size >= options.shards {
+        panic!(
+            "routing_partition_size ({}) must be less than shards ({})",
+            options.routing_partition_size, options.shards
+        )
+    }
+}
+
+#[pg_guard]
+extern "C" fn validate_disable_norms_fields(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert disable_norms_fields to utf8");
+    if value.split(',').any(|field| field.trim().is_empty()) {
+        panic!(
+            "invalid disable_norms_fields setting.  Field names cannot be empty: '{}'",
+            value
+        )
+    }
+}
+
+#[pg_guard]
+extern "C" fn validate_tier_preference(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert tier_preference to utf8");
+    for tier in value.split(',') {
+        match tier.trim() {
+            "data_content" | "data_hot" | "data_warm" | "data_cold" | "data_frozen" => {}
+            _ => panic!(
+                "invalid tier_preference setting.  Must be a comma-separated list of 'data_content', 'data_hot', 'data_warm', 'data_cold', or 'data_frozen': {}",
+                value
+            ),
+        }
+    }
+}
+
+#[pg_guard]
+extern "C" fn validate_flush_mode(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert flush_mode to utf8");
+    if value != "transaction" && value != "statement" {
+        panic!(
+            "invalid flush_mode setting.  Must be one of 'transaction' or 'statement': {}",
+            value
+        )
+    }
+}
+
+#[pg_guard]
+extern "C" fn validate_store_type(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert store_type to utf8");
+    if !matches!(value, "fs" | "mmapfs" | "niofs" | "hybridfs") {
+        panic!(
+            "invalid store_type setting.  Must be one of 'fs', 'mmapfs', 'niofs', or 'hybridfs': {}",
+            value
+        )
+    }
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as usize) << 16 | (b[1] as usize) << 8 | b[2] as usize;
+
+        encoded.push(ALPHABET[n >> 18 & 63] as char);
+        encoded.push(ALPHABET[n >> 12 & 63] as char);
+        encoded.push(if chunk.len() > 1 {
+            ALPHABET[n >> 6 & 63] as char
+        } else {
+            '='
+        });
+        encoded.push(if chunk.len() > 2 {
+            ALPHABET[n & 63] as char
+        } else {
+            '='
+        });
+    }
+    encoded
+}
+
+#[pg_guard]
+extern "C" fn validate_search_preference(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we won't send a preference
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert search_preference to utf8");
+
+    // otherwise, ES accepts any custom string as a preference
+    if value.trim().is_empty() {
+        panic!("search_preference must not be empty")
+    }
+}
+
+#[pg_guard]
+extern "C" fn validate_compression_type(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert compression_type to utf8");
+    if value != "none" && value != "deflate" && value != "gzip" {
+        panic!(
+            "invalid compression_type setting.  Must be one of 'none', 'deflate', or 'gzip': {}",
+            value
+        )
+    }
+}
+
+#[pg_guard]
+extern "C" fn validate_op_type(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert op_type to utf8");
+    if value != "index" && value != "create" {
+        panic!(
+            "invalid op_type setting.  Must be one of 'index' or 'create': {}",
+            value
+        )
+    }
+}
+
+#[pg_guard]
+extern "C" fn validate_rank_feature_fields(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert rank_feature_fields to utf8");
+    if value.split(',').any(|field| field.trim().is_empty()) {
+        panic!(
+            "invalid rank_feature_fields setting.  Field names cannot be empty: '{}'",
+            value
+        )
+    }
+}
+
+fn parse_dense_vector_field(pair: &str) -> Option<(String, u32)> {
+    let mut parts = pair.splitn(2, ':');
+    let field = parts.next()?.trim();
+    let dims = parts.next()?.trim().parse::<u32>().ok()?;
+    if field.is_empty() || dims == 0 {
+        None
+    } else {
+        Some((field.to_owned(), dims))
+    }
+}
+
+#[pg_guard]
+extern "C" fn validate_dense_vector_fields(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert dense_vector_fields to utf8");
+    if value
+        .split(',')
+        .any(|pair| parse_dense_vector_field(pair).is_none())
+    {
+        panic!(
+            "invalid dense_vector_fields setting.  Must be a comma-separated list of field:dims pairs where dims is a positive integer: '{}'",
+            value
+        )
+    }
+}
+
+/// Elasticsearch accepts an integer or percentage, either of which may be negative, or
+/// space-separated conditional combinations of them such as `3<90%` or `2<-25% 9<-3`
+fn is_valid_minimum_should_match(value: &str) -> bool {
+    fn is_simple(value: &str) -> bool {
+        let value = value.strip_suffix('%').unwrap_or(value);
+        value.parse::<i32>().is_ok()
+    }
+
+    let mut parts = value.split_whitespace().peekable();
+    parts.peek().is_some()
+        && parts.all(|part| {
+            let mut condition = part.splitn(2, '<');
+            match (condition.next(), condition.next()) {
+                (Some(count), Some(value)) => count.parse::<u32>().is_ok() && is_simple(value),
+                (Some(value), None) => is_simple(value),
+                _ => false,
+            }
+        })
+}
+
+#[pg_guard]
+extern "C" fn validate_default_minimum_should_match(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert default_minimum_should_match to utf8");
+    if !is_valid_minimum_should_match(value) {
+        panic!(
+            "invalid default_minimum_should_match setting.  Must be an integer, a percentage, or a combination such as '3<90%': '{}'",
+            value
+        )
+    }
+}
+
+#[pg_guard]
+extern "C" fn validate_tiebreaker_field(value: *const std::os::raw::c_char) {
+    if value.is_null() {
+        // null is fine -- we'll just use our default
+        return;
+    }
+
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("failed to convert tiebreaker_field to utf8");
+    if value.is_empty() || value.contains(|c: char| c.is_whitespace() || c == ',') {
+        panic!(
+            "invalid tiebreaker_field setting.  Must be a single field name: '{}'",
+            value
+        )
+    }
+}
+
+const NUM_REL_OPTS: usize = 71;
+#[allow(clippy::unneeded_field_pattern)] // b/c of offset_of!()
+#[pg_guard]
+pub unsafe extern "C" fn amoptions(
+    reloptions: pg_sys::Datum,
+    validate: bool,
+) -> *mut pg_sys::bytea {
+    // TODO:  how to make this const?  we can't use offset_of!() macro in const definitions, apparently
+    let tab: [pg_sys::relopt_parse_elt; NUM_REL_OPTS] = [
+        pg_sys::relopt_parse_elt {
+            optname: "url".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, url_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "type_name".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, type_name_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "refresh_interval".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, refresh_interval_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "shards".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, shards) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "replicas".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, replicas) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "bulk_concurrency".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, bulk_concurrency) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "batch_size".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, batch_size_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "compression_level".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, compression_level) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "max_result_window".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, max_result_window) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "nested_fields_limit".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, nested_fields_limit) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "total_fields_limit".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, total_fields_limit) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "max_terms_count".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, max_terms_count) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "alias".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, alias_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "optimize_after".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, optimize_after) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "llapi".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, llapi) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "uuid".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, uuid_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "translog_durability".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, translog_durability_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "options".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, options_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "field_lists".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, field_lists_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "shadow".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, shadow_index) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "nested_object_date_detection".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, nested_object_date_detection) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "nested_object_numeric_detection".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, nested_object_numeric_detection) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "nested_object_text_mapping".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, nested_object_text_mapping_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "recovery_priority".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, recovery_priority) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "check_on_startup".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, check_on_startup_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "default_wait_for_active_shards".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, default_wait_for_active_shards_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "soft_deletes".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, soft_deletes) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "default_query_boost".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, default_query_boost_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "block_metadata".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, block_metadata) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "floor_segment".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, floor_segment_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "trace_requests".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, trace_requests) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "replicas_step".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, replicas_step) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "slowlog_source_length".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, slowlog_source_length) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "default_pipeline".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, default_pipeline_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "store_ctid_field".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, store_ctid_field) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "aggregation_timezone".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, aggregation_timezone_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "stopwords".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, stopwords_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "routing_partition_size".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, routing_partition_size) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "allow_leading_wildcard".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, allow_leading_wildcard) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "disable_norms_fields".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, disable_norms_fields_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "tier_preference".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, tier_preference_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "flush_mode".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, flush_mode_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "final_pipeline".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, final_pipeline_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "max_script_fields".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, max_script_fields) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "mapping_coerce".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, mapping_coerce) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "store_type".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, store_type_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "username".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, username_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "password".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, password_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "search_throttled".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, search_throttled) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "verify_index_on_scan".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, verify_index_on_scan) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "optimize_max_segments".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, optimize_max_segments) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "max_docs".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, max_docs) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "search_preference".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, search_preference_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "compression_type".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, compression_type_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "ignore_malformed".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, ignore_malformed) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "request_timeout".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, request_timeout) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "max_concurrent_writers".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, max_concurrent_writers) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "max_shingle_diff".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, max_shingle_diff) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "synthetic_source".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, synthetic_source) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "op_type".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, op_type_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "updatable_synonyms".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, updatable_synonyms) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "eager_bitset_filters".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, eager_bitset_filters) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "rank_feature_fields".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, rank_feature_fields_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "dense_vector_fields".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, dense_vector_fields_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "auto_search_after".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, auto_search_after) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "minimize_bulk_metadata".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, minimize_bulk_metadata) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "default_minimum_should_match".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, default_minimum_should_match_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "tiebreaker_field".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_STRING,
+            offset: offset_of!(ZDBIndexOptionsInternal, tiebreaker_field_offset) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "store_doc_size".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_BOOL,
+            offset: offset_of!(ZDBIndexOptionsInternal, store_doc_size) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "agg_max_buckets".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, agg_max_buckets) as i32,
+        },
+        pg_sys::relopt_parse_elt {
+            optname: "field_name_length_limit".as_pg_cstr(),
+            opttype: pg_sys::relopt_type_RELOPT_TYPE_INT,
+            offset: offset_of!(ZDBIndexOptionsInternal, field_name_length_limit) as i32,
+        },
+    ];
+
+    let rdopts = build_relopts(reloptions, validate, tab);
+    if validate && !rdopts.is_null() {
+        validate_routing_partition_size(&*(rdopts as *const ZDBIndexOptionsInternal));
+    }
+    rdopts
+}
+
+#[cfg(feature = "pg13")]
+unsafe fn build_relopts(
+    reloptions: pg_sys::Datum,
+    validate: bool,
+    tab: [pg_sys::relopt_parse_elt; NUM_REL_OPTS],
+) -> *mut pg_sys::bytea {
+    let rdopts;
+
+    /* Parse the user-given reloptions */
+    rdopts = pg_sys::build_reloptions(
+        reloptions,
+        validate,
+        RELOPT_KIND_ZDB,
+        std::mem::size_of::<ZDBIndexOptionsInternal>(),
+        tab.as_ptr(),
+        NUM_REL_OPTS as i32,
+    );
+
+    rdopts as *mut pg_sys::bytea
+}
+
+#[cfg(not(feature = "pg13"))]
+unsafe fn build_relopts(
+    reloptions: pg_sys::Datum,
+    validate: bool,
+    tab: [pg_sys::relopt_parse_elt; NUM_REL_OPTS],
+) -> *mut pg_sys::bytea {
+    let mut noptions = 0;
+    let options = pg_sys::parseRelOptions(reloptions, validate, RELOPT_KIND_ZDB, &mut noptions);
+    if noptions == 0 {
+        return std::ptr::null_mut();
+    }
+
+    for relopt in std::slice::from_raw_parts_mut(options, noptions as usize) {
+        relopt.gen.as_mut().unwrap().lockmode = pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE;
+    }
+
+    let rdopts = pg_sys::allocateReloptStruct(
+        std::mem::size_of::<ZDBIndexOptionsInternal>(),
+        options,
+        noptions,
+    );
+    pg_sys::fillRelOptions(
+        rdopts,
+        std::mem::size_of::<ZDBIndexOptionsInternal>(),
+        options,
+        noptions,
+        validate,
+        tab.as_ptr(),
+        tab.len() as i32,
+    );
+    pg_sys::pfree(options as void_mut_ptr);
+
+    rdopts as *mut pg_sys::bytea
+}
+
+pub unsafe fn init() {
+    RELOPT_KIND_ZDB = pg_sys::add_reloption_kind();
+
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "url".as_pg_cstr(),
+        "Server URL and port".as_pg_cstr(),
+        "default".as_pg_cstr(),
+        Some(validate_url),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "type_name".as_pg_cstr(),
+        "What Elasticsearch index type name should ZDB use?  Default is 'doc'".as_pg_cstr(),
+        "doc".as_pg_cstr(),
+        None,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "refresh_interval".as_pg_cstr(),
+        "Frequency in which Elasticsearch indexes are refreshed.  Related to ES' index.refresh_interval setting".as_pg_cstr(),
+        DEFAULT_REFRESH_INTERVAL.as_pg_cstr(),
+        None,
+        #[cfg(feature = "pg13")]
+            { pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "shards".as_pg_cstr(),
+        "The number of shards for the index".as_pg_cstr(),
+        DEFAULT_SHARDS,
+        1,
+        32768,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "replicas".as_pg_cstr(),
+        "The number of replicas for the index".as_pg_cstr(),
+        REPLICAS_FROM_GUC,
+        0,
+        32768,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "bulk_concurrency".as_pg_cstr(),
+        "The maximum number of concurrent _bulk API requests".as_pg_cstr(),
+        *DEFAULT_BULK_CONCURRENCY,
+        1,
+        1024,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "batch_size".as_pg_cstr(),
+        "The size of batch calls to the _bulk API, in bytes or with a unit such as '16mb'"
+            .as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_batch_size),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "compression_level".as_pg_cstr(),
+        "0-9 value to indicate the level of HTTP compression".as_pg_cstr(),
+        DEFAULT_COMPRESSION_LEVEL,
+        0,
+        9,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "max_result_window".as_pg_cstr(),
+        "The number of docs to page in from Elasticsearch at one time.  Default is 10,000"
+            .as_pg_cstr(),
+        DEFAULT_MAX_RESULT_WINDOW,
+        1,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "nested_fields_limit".as_pg_cstr(),
+        "The maximum number of distinct nested mappings in an index.  Default is 1000".as_pg_cstr(),
+        DEFAULT_NESTED_FIELDS_LIMIT,
+        1,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "total_fields_limit".as_pg_cstr(),
+        "The maximum number of fields in an index. Field and object mappings, as well as field aliases count towards this limit. The default value is 1000.".as_pg_cstr(),
+        DEFAULT_TOTAL_FIELDS_LIMIT,
+        1,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "max_terms_count".as_pg_cstr(),
+        "The maximum number of terms that can be used in Terms Query.  The default value is 65535."
+            .as_pg_cstr(),
+        DEFAULT_MAX_TERMS_COUNT,
+        1,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "alias".as_pg_cstr(),
+        "The Elasticsearch Alias to which this index should belong".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_alias),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "uuid".as_pg_cstr(),
+        "The Elasticsearch index name, as a UUID".as_pg_cstr(),
+        std::ptr::null(),
+        None,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "translog_durability".as_pg_cstr(),
+        "Elasticsearch index.translog.durability setting.  Defaults to 'request'".as_pg_cstr(),
+        "request".as_pg_cstr(),
+        Some(validate_translog_durability),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "optimize_after".as_pg_cstr(),
+        "After how many deleted docs should ZDB _optimize the ES index during VACUUM?".as_pg_cstr(),
+        DEFAULT_OPTIMIZE_AFTER,
+        0,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "llapi".as_pg_cstr(),
+        "Will this index be used by ZomboDB's low-level API?".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "options".as_pg_cstr(),
+        "ZomboDB Index Linking options".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_options),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "field_lists".as_pg_cstr(),
+        "Combine fields into named lists during search".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_field_lists),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "shadow".as_pg_cstr(),
+        "Is this index a shadow index, and if so, to which one".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "nested_object_date_detection".as_pg_cstr(),
+        "Should ES try to automatically detect dates in nested objects".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "nested_object_numeric_detection".as_pg_cstr(),
+        "Should ES try to automatically detect numbers in nested objects".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "nested_object_text_mapping".as_pg_cstr(),
+        "As a JSON mapping definition, how should dynamic text values in JSON be mapped?".as_pg_cstr(),
+        r#"{ "type": "keyword", "ignore_above": 10922, "normalizer": "lowercase", "copy_to": "zdb_all" }"#.as_pg_cstr(),
+        Some(validate_text_mapping),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "recovery_priority".as_pg_cstr(),
+        "The order in which Elasticsearch recovers this index after a restart.  Related to ES' index.priority setting".as_pg_cstr(),
+        DEFAULT_RECOVERY_PRIORITY,
+        0,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "check_on_startup".as_pg_cstr(),
+        "Elasticsearch index.shard.check_on_startup setting.  Defaults to 'false'".as_pg_cstr(),
+        "false".as_pg_cstr(),
+        Some(validate_check_on_startup),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "default_wait_for_active_shards".as_pg_cstr(),
+        "Elasticsearch index.write.wait_for_active_shards setting.  Defaults to '1'".as_pg_cstr(),
+        "1".as_pg_cstr(),
+        Some(validate_default_wait_for_active_shards),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "soft_deletes".as_pg_cstr(),
+        "Elasticsearch index.soft_deletes.enabled setting.  Defaults to true".as_pg_cstr(),
+        true,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "default_query_boost".as_pg_cstr(),
+        "A boost applied to every query against this index.  Defaults to 1.0".as_pg_cstr(),
+        "1.0".as_pg_cstr(),
+        Some(validate_default_query_boost),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "block_metadata".as_pg_cstr(),
+        "Elasticsearch index.blocks.metadata setting.  Defaults to false".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "floor_segment".as_pg_cstr(),
+        "Elasticsearch index.merge.policy.floor_segment setting.  Defaults to '2mb'".as_pg_cstr(),
+        "2mb".as_pg_cstr(),
+        Some(validate_floor_segment),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "trace_requests".as_pg_cstr(),
+        "Send a unique X-Request-Id header with each search and _bulk request.  Defaults to false".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "replicas_step".as_pg_cstr(),
+        "Increase the number of replicas one at a time, waiting for green status between each.  Defaults to false".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "slowlog_source_length".as_pg_cstr(),
+        "How many characters of the _source to include in the indexing slowlog.  -1 includes it all, 0 includes none".as_pg_cstr(),
+        DEFAULT_SLOWLOG_SOURCE_LENGTH,
+        -1,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "default_pipeline".as_pg_cstr(),
+        "The Elasticsearch ingest pipeline applied to every document indexed into this index".as_pg_cstr(),
+        std::ptr::null(),
+        None,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "store_ctid_field".as_pg_cstr(),
+        "Also index each row's ctid, as text, in a keyword field named _zdb_ctid.  Defaults to false".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "aggregation_timezone".as_pg_cstr(),
+        "The time_zone used by date_histogram aggregations.  Defaults to 'UTC'".as_pg_cstr(),
+        "UTC".as_pg_cstr(),
+        Some(validate_aggregation_timezone),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "stopwords".as_pg_cstr(),
+        "A comma-separated list of stopwords, or '_none_', applied to text fields".as_pg_cstr(),
+        std::ptr::null(),
+        None,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "routing_partition_size".as_pg_cstr(),
+        "The number of shards a custom routing value can go to".as_pg_cstr(),
+        DEFAULT_ROUTING_PARTITION_SIZE,
+        1,
+        32768,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "allow_leading_wildcard".as_pg_cstr(),
+        "Whether query_string queries may begin with a wildcard.  Defaults to false".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "disable_norms_fields".as_pg_cstr(),
+        "A comma-separated list of text and keyword fields that should not store norms".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_disable_norms_fields),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "tier_preference".as_pg_cstr(),
+        "Elasticsearch index.routing.allocation.include._tier_preference setting".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_tier_preference),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "flush_mode".as_pg_cstr(),
+        "When indexed changes are flushed to Elasticsearch.  One of 'transaction' or 'statement'.  Defaults to 'transaction'".as_pg_cstr(),
+        "transaction".as_pg_cstr(),
+        Some(validate_flush_mode),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "final_pipeline".as_pg_cstr(),
+        "The Elasticsearch ingest pipeline applied to every document after any default or request pipeline".as_pg_cstr(),
+        std::ptr::null(),
+        None,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "max_script_fields".as_pg_cstr(),
+        "The maximum number of script_fields allowed in a query".as_pg_cstr(),
+        DEFAULT_MAX_SCRIPT_FIELDS,
+        1,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "mapping_coerce".as_pg_cstr(),
+        "Elasticsearch index.mapping.coerce setting.  Defaults to true".as_pg_cstr(),
+        true,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "store_type".as_pg_cstr(),
+        "Elasticsearch index.store.type setting.  Defaults to 'fs'".as_pg_cstr(),
+        "fs".as_pg_cstr(),
+        Some(validate_store_type),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "username".as_pg_cstr(),
+        "The username used to authenticate with Elasticsearch".as_pg_cstr(),
+        std::ptr::null(),
+        None,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "password".as_pg_cstr(),
+        "The password used to authenticate with Elasticsearch".as_pg_cstr(),
+        std::ptr::null(),
+        None,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "search_throttled".as_pg_cstr(),
+        "Whether the index may be search throttled (frozen), so searches should pass ignore_throttled=false.  Defaults to false"
+            .as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "verify_index_on_scan".as_pg_cstr(),
+        "Should index scans first verify that the Elasticsearch index exists?  Defaults to false"
+            .as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "optimize_max_segments".as_pg_cstr(),
+        "How many segments should ZDB _optimize the ES index down to?".as_pg_cstr(),
+        DEFAULT_OPTIMIZE_MAX_SEGMENTS,
+        1,
+        1024,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "max_docs".as_pg_cstr(),
+        "The maximum number of documents allowed in the index.  Defaults to 0, which is unlimited"
+            .as_pg_cstr(),
+        0,
+        0,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "search_preference".as_pg_cstr(),
+        "The Elasticsearch preference parameter sent with searches, such as '_local'".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_search_preference),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "compression_type".as_pg_cstr(),
+        "The HTTP(s) request body compression encoding.  One of 'none', 'deflate', or 'gzip'.  Defaults to 'deflate'"
+            .as_pg_cstr(),
+        DEFAULT_COMPRESSION_TYPE.as_pg_cstr(),
+        Some(validate_compression_type),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "ignore_malformed".as_pg_cstr(),
+        "Elasticsearch index.mapping.ignore_malformed setting.  Defaults to false".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "request_timeout".as_pg_cstr(),
+        "The timeout, in seconds, for HTTP requests to Elasticsearch.  Defaults to 0, which is no timeout"
+            .as_pg_cstr(),
+        0,
+        0,
+        86400,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "max_concurrent_writers".as_pg_cstr(),
+        "The maximum number of backends that can concurrently write to the index.  Defaults to 0, which is unlimited"
+            .as_pg_cstr(),
+        0,
+        0,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "max_shingle_diff".as_pg_cstr(),
+        "The maximum allowed difference between max_shingle_size and min_shingle_size".as_pg_cstr(),
+        DEFAULT_MAX_SHINGLE_DIFF,
+        0,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "synthetic_source".as_pg_cstr(),
+        "Should the index use Elasticsearch's synthetic _source mode?  Defaults to false".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "op_type".as_pg_cstr(),
+        "The Elasticsearch op_type for new documents, either 'index' or 'create'.  Defaults to 'index'"
+            .as_pg_cstr(),
+        DEFAULT_OP_TYPE.as_pg_cstr(),
+        Some(validate_op_type),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "updatable_synonyms".as_pg_cstr(),
+        "Should synonym filters be updateable, so they can be reloaded?  Defaults to false".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "eager_bitset_filters".as_pg_cstr(),
+        "Elasticsearch index.load_fixed_bitset_filters_eagerly setting.  Defaults to true".as_pg_cstr(),
+        true,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "rank_feature_fields".as_pg_cstr(),
+        "A comma-separated list of numeric fields to map as rank_feature fields".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_rank_feature_fields),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "dense_vector_fields".as_pg_cstr(),
+        "A comma-separated list of field:dims pairs to map as dense_vector fields".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_dense_vector_fields),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "auto_search_after".as_pg_cstr(),
+        "Should LIMITs larger than max_result_window be paged in with search_after rather than a scroll?  Defaults to true".as_pg_cstr(),
+        true,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "minimize_bulk_metadata".as_pg_cstr(),
+        "Should _bulk action lines omit the _index already named by the request URL?  Defaults to true".as_pg_cstr(),
+        true,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "default_minimum_should_match".as_pg_cstr(),
+        "The minimum_should_match applied to match queries that don't specify their own".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_default_minimum_should_match),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_string_reloption(
+        RELOPT_KIND_ZDB,
+        "tiebreaker_field".as_pg_cstr(),
+        "The field used to break ties in the sort order when paging with search_after.  Defaults to zdb_ctid".as_pg_cstr(),
+        std::ptr::null(),
+        Some(validate_tiebreaker_field),
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_bool_reloption(
+        RELOPT_KIND_ZDB,
+        "store_doc_size".as_pg_cstr(),
+        "Should the size of each document's _source be stored in the _size field?  Requires the mapper-size plugin".as_pg_cstr(),
+        false,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "agg_max_buckets".as_pg_cstr(),
+        "The maximum number of buckets an aggregate request may ask for.  Defaults to 0, which leaves it to the cluster's search.max_buckets"
+            .as_pg_cstr(),
+        0,
+        0,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+    pg_sys::add_int_reloption(
+        RELOPT_KIND_ZDB,
+        "field_name_length_limit".as_pg_cstr(),
+        "The maximum length of a field name in the index's mapping.  The default value is 50000."
+            .as_pg_cstr(),
+        DEFAULT_FIELD_NAME_LENGTH_LIMIT,
+        1,
+        std::i32::MAX,
+        #[cfg(feature = "pg13")]
+        {
+            pg_sys::AccessExclusiveLock as pg_sys::LOCKMODE
+        },
+    );
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use crate::access_method::options::{
+        base64_encode, parse_batch_size, parse_byte_size, parse_urls,
+        validate_aggregation_timezone, validate_alias, validate_batch_size,
+        validate_check_on_startup, validate_compression_type,
+        validate_default_minimum_should_match, validate_default_query_boost,
+        validate_default_wait_for_active_shards, validate_dense_vector_fields,
+        validate_disable_norms_fields, validate_floor_segment, validate_flush_mode,
+        validate_op_type, validate_rank_feature_fields, validate_search_preference,
+        validate_store_type, validate_tiebreaker_field, validate_tier_preference,
+        validate_translog_durability, validate_url, CompressionType, FlushMode, OpType,
+        RefreshInterval, ZDBIndexOptions, DEFAULT_BATCH_SIZE, DEFAULT_BULK_CONCURRENCY,
+        DEFAULT_COMPRESSION_LEVEL, DEFAULT_MAX_SCRIPT_FIELDS, DEFAULT_MAX_SHINGLE_DIFF,
+        DEFAULT_OPTIMIZE_AFTER, DEFAULT_OPTIMIZE_MAX_SEGMENTS, DEFAULT_ROUTING_PARTITION_SIZE,
+        DEFAULT_SHARDS, DEFAULT_TIEBREAKER_FIELD, DEFAULT_TYPE_NAME,
+    };
+    use crate::elasticsearch::Elasticsearch;
+    use crate::gucs::{ZDB_DEFAULT_ELASTICSEARCH_URL, ZDB_DEFAULT_REPLICAS};
+    use pgx::pg_sys::AsPgCStr;
+    use pgx::*;
+
+    #[pg_test]
+    fn test_validate_url() {
+        validate_url("http://localhost:9200/".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_validate_alias() {
+        validate_alias("my_app.index-2021".as_pg_cstr());
+    }
+
+    #[pg_test(error = "alias must be lowercase: MyApp")]
+    fn test_validate_uppercase_alias() {
+        validate_alias("MyApp".as_pg_cstr());
+    }
+
+    #[pg_test(error = "alias must not contain ' ': my app")]
+    fn test_validate_alias_with_space() {
+        validate_alias("my app".as_pg_cstr());
+    }
+
+    #[pg_test(error = "alias must not contain '#': my#app")]
+    fn test_validate_alias_with_forbidden_character() {
+        validate_alias("my#app".as_pg_cstr());
+    }
+
+    #[pg_test(error = "alias must not start with '-', '_', or '+': _myapp")]
+    fn test_validate_alias_leading_underscore() {
+        validate_alias("_myapp".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_validate_default_url() {
+        validate_url("default".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_validate_url_without_trailing_slash() {
+        validate_url("http://localhost:9200".as_pg_cstr());
+    }
+
+    #[pg_test(error = "url must use the http or https scheme: ftp://x/")]
+    fn test_validate_non_http_url() {
+        validate_url("ftp://x/".as_pg_cstr());
+    }
+
+    #[pg_test(error = "relative URL without a base")]
+    fn test_validate_invalid_url() {
+        validate_url("localhost".as_pg_cstr());
+    }
+
+    #[pg_test(
+        error = "no Elasticsearch url in ' , '.  Set the index's url option or zdb.default_elasticsearch_url"
+    )]
+    fn test_parse_urls_without_urls() {
+        parse_urls(" , ");
+    }
+
+    #[pg_test]
+    fn test_validate_multiple_urls() {
+        validate_url("http://es1:9200/,http://es2:9200/".as_pg_cstr());
+    }
+
+    #[pg_test(error = "url must use the http or https scheme: ftp://es2/")]
+    fn test_validate_invalid_second_url() {
+        validate_url("http://es1:9200/,ftp://es2/".as_pg_cstr());
+    }
+
+    #[pg_test(error = "url entry #2 is empty: 'http://es1:9200/,'")]
+    fn test_validate_url_trailing_comma() {
+        validate_url("http://es1:9200/,".as_pg_cstr());
+    }
+
+    #[pg_test(error = "url entry #2 is empty: 'http://es1:9200/,,http://es2:9200/'")]
+    fn test_validate_url_empty_entry() {
+        validate_url("http://es1:9200/,,http://es2:9200/".as_pg_cstr());
+    }
+
+    #[pg_test(
+        error = "invalid translog_durability setting.  Must be one of 'request' or 'async': foo"
+    )]
+    fn test_validate_invalid_translog_durability() {
+        validate_translog_durability("foo".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_validate_batch_size_with_unit() {
+        validate_batch_size("8mb".as_pg_cstr());
+        assert_eq!(parse_batch_size("8mb"), Some(8 * 1024 * 1024));
+        assert_eq!(parse_batch_size("512KB"), Some(512 * 1024));
+    }
+
+    #[pg_test]
+    fn test_validate_batch_size_bytes() {
+        validate_batch_size("1024".as_pg_cstr());
+        assert_eq!(parse_batch_size("1024"), Some(1024));
+    }
+
+    #[pg_test]
+    fn test_parse_byte_size_units() {
+        assert_eq!(parse_byte_size("10b"), Some(10));
+        assert_eq!(parse_byte_size("2kb"), Some(2 * 1024));
+        assert_eq!(parse_byte_size("2mb"), Some(2 * 1024 * 1024));
+        assert_eq!(parse_byte_size("2gb"), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(parse_byte_size("2tb"), None);
+        assert_eq!(parse_byte_size("2pb"), None);
+    }
+
+    #[pg_test(
+        error = "invalid batch_size setting.  Must be between 1 and 1073741822 bytes, such as '16mb' or '512kb': 10pb"
+    )]
+    fn test_validate_invalid_batch_size() {
+        validate_batch_size("10pb".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_valid_translog_durability_request() {
+        validate_translog_durability("request".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_valid_translog_durability_async() {
+        validate_translog_durability("async".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_options() {
+        let uuid = 42424242;
+        Spi::run(&format!(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) 
+                WITH (url='http://localhost:19200/', 
+                      type_name='test_type_name', 
+                      alias='test_alias', 
+                      uuid='{}', 
+                      refresh_interval='5s',
+                      translog_durability='async',
+                      optimize_max_segments=5);",
+            uuid
+        ));
+
+        let index_oid = Spi::get_one::<pg_sys::Oid>("SELECT 'idxtest'::regclass::oid")
+            .expect("failed to get SPI result");
+        let indexrel = PgRelation::from_pg(pg_sys::RelationIdGetRelation(index_oid));
+        let options = ZDBIndexOptions::from_relation(&indexrel);
+        assert_eq!(options.url(), "http://localhost:19200/");
+        assert_eq!(options.type_name(), "test_type_name");
+        assert_eq!(options.alias(), "test_alias");
+        assert_eq!(options.uuid(), &uuid.to_string());
+        assert_eq!(
+            options.refresh_interval(),
+            RefreshInterval::Background("5s".to_owned())
+        );
+        assert_eq!(options.compression_level(), 1);
+        assert_eq!(options.shards(), 5);
+        assert_eq!(options.replicas(), 0);
+        assert_eq!(options.bulk_concurrency(), num_cpus::get() as i32);
+        assert_eq!(options.batch_size(), 8 * 1024 * 1024);
+        assert_eq!(options.optimize_after(), DEFAULT_OPTIMIZE_AFTER);
+        assert_eq!(options.optimize_max_segments(), 5);
+        assert_eq!(options.llapi(), false);
+        assert_eq!(options.translog_durability(), "async");
+        assert_eq!(options.links, None);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_options_defaults() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let heap_oid = Spi::get_one::<pg_sys::Oid>("SELECT 'test'::regclass::oid")
+            .expect("failed to get SPI result");
+        let index_oid = Spi::get_one::<pg_sys::Oid>("SELECT 'idxtest'::regclass::oid")
+            .expect("failed to get SPI result");
+        let heaprel = PgRelation::from_pg(pg_sys::RelationIdGetRelation(heap_oid));
+        let indexrel = PgRelation::from_pg(pg_sys::RelationIdGetRelation(index_oid));
+        let options = ZDBIndexOptions::from_relation(&indexrel);
+        assert_eq!(options.type_name(), DEFAULT_TYPE_NAME);
+        assert_eq!(
+            &options.alias(),
+            &format!("pgx_tests.public.test.idxtest-{}", indexrel.oid())
+        );
+        assert_eq!(
+            &options.uuid(),
+            &format!(
+                "{}.{}.{}.{}",
+                pg_sys::MyDatabaseId,
+                indexrel.namespace_oid(),
+                heaprel.oid(),
+                indexrel.oid()
+            )
+        );
+        assert_eq!(options.refresh_interval(), RefreshInterval::Immediate);
+        assert_eq!(options.compression_level(), DEFAULT_COMPRESSION_LEVEL);
+        assert_eq!(options.shards(), DEFAULT_SHARDS);
+        assert_eq!(options.replicas(), ZDB_DEFAULT_REPLICAS.get());
+        assert_eq!(options.bulk_concurrency(), *DEFAULT_BULK_CONCURRENCY);
+        assert_eq!(options.batch_size(), DEFAULT_BATCH_SIZE);
+        assert_eq!(options.optimize_after(), DEFAULT_OPTIMIZE_AFTER);
+        assert_eq!(options.optimize_max_segments(), DEFAULT_OPTIMIZE_MAX_SEGMENTS);
+        assert_eq!(options.llapi(), false);
+        assert_eq!(options.translog_durability(), "request")
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_name() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.index_name(), options.uuid());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_url() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.url(), "http://localhost:19200/");
+        assert_eq!(options.urls(), vec!["http://localhost:19200/".to_owned()]);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_url_from_default_elasticsearch_url() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*));",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(
+            options.url(),
+            ZDB_DEFAULT_ELASTICSEARCH_URL
+                .get()
+                .expect("zdb.default_elasticsearch_url is not set")
+        );
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_url_default() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='default');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(
+            options.url(),
+            ZDB_DEFAULT_ELASTICSEARCH_URL
+                .get()
+                .expect("zdb.default_elasticsearch_url is not set")
+        );
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_url_without_trailing_slash() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.url(), "http://localhost:19200/");
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_multiple_urls() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/, http://localhost:19201/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.url(), "http://localhost:19200/");
+        assert_eq!(
+            options.urls(),
+            vec![
+                "http://localhost:19200/".to_owned(),
+                "http://localhost:19201/".to_owned()
+            ]
+        );
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_type_name() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.type_name(), "doc");
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_resolved_index_options() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', shards=3, alias='test_alias');",
+        );
+
+        let options = Spi::get_one::<JsonB>("SELECT zdb.resolved_index_options('idxtest');")
+            .expect("failed to get SPI result")
+            .0;
+
+        assert_eq!(options["url"], "http://localhost:19200/");
+        assert_eq!(options["shards"], 3);
+        assert_eq!(options["alias"], "test_alias");
+        assert_eq!(options["type_name"], "doc");
+        assert_eq!(options["refresh_interval"], "immediate");
+        assert_eq!(options["replicas"], ZDB_DEFAULT_REPLICAS.get());
+        assert_eq!(options["bulk_concurrency"], *DEFAULT_BULK_CONCURRENCY);
+        assert_eq!(options["batch_size"], DEFAULT_BATCH_SIZE);
+        assert_eq!(options["compression_level"], DEFAULT_COMPRESSION_LEVEL);
+        assert_eq!(options["optimize_after"], DEFAULT_OPTIMIZE_AFTER);
+        assert_eq!(options["llapi"], false);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_link_options() {
+        Spi::run(
+            "CREATE TABLE test_link_options();  
+        CREATE INDEX idxtest_link_options
+                  ON test_link_options
+               USING zombodb ((test_link_options.*)) WITH (options='id=<schema.table.index>other_id');",
+        );
+
+        let index_relation =
+            PgRelation::open_with_name("idxtest_link_options").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(
+            options.links(),
+            &Some(vec!["id=<schema.table.index>other_id".to_string()])
+        );
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_recovery_priority() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', recovery_priority=42);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.recovery_priority(), 42);
+    }
+
+    #[pg_test(
+        error = "invalid check_on_startup setting.  Must be one of 'false', 'checksum', or 'true': foo"
+    )]
+    fn test_validate_invalid_check_on_startup() {
+        validate_check_on_startup("foo".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_valid_check_on_startup_false() {
+        validate_check_on_startup("false".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_valid_check_on_startup_checksum() {
+        validate_check_on_startup("checksum".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_valid_check_on_startup_true() {
+        validate_check_on_startup("true".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_check_on_startup() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', check_on_startup='checksum');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.check_on_startup(), "checksum");
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_query_fields() {
+        Spi::run(
+            "CREATE TABLE test_query_fields (title text, quantity int);
+        CREATE INDEX idxtest_query_fields
+                  ON test_query_fields
+               USING zombodb ((test_query_fields.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let title_type = Spi::get_one::<&str>(
+            "SELECT es_type FROM zdb.query_fields('idxtest_query_fields') WHERE field = 'title';",
+        )
+        .expect("title field not found");
+        let quantity_type = Spi::get_one::<&str>(
+            "SELECT es_type FROM zdb.query_fields('idxtest_query_fields') WHERE field = 'quantity';",
+        )
+        .expect("quantity field not found");
+
+        assert_eq!(title_type, "text");
+        assert_eq!(quantity_type, "integer");
+    }
+
+    #[pg_test(
+        error = "invalid default_wait_for_active_shards setting.  Must be a non-negative number or 'all': foo"
+    )]
+    fn test_validate_invalid_default_wait_for_active_shards() {
+        validate_default_wait_for_active_shards("foo".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_valid_default_wait_for_active_shards_all() {
+        validate_default_wait_for_active_shards("all".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_valid_default_wait_for_active_shards_number() {
+        validate_default_wait_for_active_shards("2".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_default_wait_for_active_shards() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', default_wait_for_active_shards='all');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.default_wait_for_active_shards(), "all");
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_soft_deletes() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', soft_deletes=false);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.soft_deletes(), false);
+    }
+
+    #[pg_test(error = "invalid default_query_boost setting.  Must be a non-negative number: -1")]
+    fn test_validate_invalid_default_query_boost() {
+        validate_default_query_boost("-1".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_valid_default_query_boost() {
+        validate_default_query_boost("2.5".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_default_query_boost() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', default_query_boost='2.5');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.default_query_boost(), 2.5);
+
+        let query = Spi::get_one::<&str>("SELECT zdb.dump_query('idxtest', 'foo');")
+            .expect("failed to dump query");
+        let query: serde_json::Value = serde_json::from_str(query).unwrap();
+        assert_eq!(query["dis_max"]["boost"], serde_json::json!(2.5));
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_block_metadata() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', block_metadata=true);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.block_metadata(), true);
+
+        // the block isn't applied until CREATE INDEX commits, so aborting it can still delete the index
+        let blocked = Spi::get_one::<&str>(
+            "SELECT zdb.index_settings('idxtest')->zdb.index_name('idxtest')->'settings'->'index'->'blocks'->>'metadata';",
+        );
+        assert_eq!(blocked, None);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_block_metadata_survives_alter() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', block_metadata=true);",
+        );
+
+        let setting = |name: &str| {
+            Spi::get_one::<&str>(&format!(
+                "SELECT zdb.index_settings('idxtest')->zdb.index_name('idxtest')->'settings'->'index'{};",
+                name
+            ))
+            .expect("failed to get SPI result")
+        };
+
+        // ZomboDB lifts the block to apply its settings, then puts it back
+        Spi::run("ALTER INDEX idxtest SET (refresh_interval='5s');");
+        assert_eq!(setting("->>'refresh_interval'"), "5s");
+        assert_eq!(setting("->'blocks'->>'metadata'"), "true");
+
+        Spi::run("ALTER INDEX idxtest SET (block_metadata=false, refresh_interval='10s');");
+        assert_eq!(setting("->>'refresh_interval'"), "10s");
+        assert_eq!(setting("->'blocks'->>'metadata'"), "false");
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_export_options_sql() {
+        Spi::run(
+            "CREATE TABLE test_export_options();
+        CREATE INDEX idxtest_export_options
+                  ON test_export_options
+               USING zombodb ((test_export_options.*)) WITH (url='http://localhost:19200/', shards=3);",
+        );
+
+        let statement = Spi::get_one::<&str>(
+            "SELECT stmt FROM zdb.export_options_sql() stmt WHERE stmt LIKE 'ALTER INDEX idxtest_export_options %';",
+        )
+        .expect("no statement exported for index");
+
+        assert!(statement.contains("shards='3'"));
+    }
+
+    #[pg_test(
+        error = "invalid floor_segment setting.  Must be a byte size such as '512kb' or '2mb': 2 megs"
+    )]
+    fn test_validate_invalid_floor_segment() {
+        validate_floor_segment("2 megs".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_valid_floor_segment() {
+        validate_floor_segment("512kb".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_floor_segment() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', floor_segment='4mb');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.floor_segment(), "4mb");
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_replicas_step() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', replicas_step=true);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.replicas_step(), true);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_slowlog_source_length() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', slowlog_source_length=-1);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.slowlog_source_length(), -1);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_default_pipeline() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', default_pipeline='my_pipeline');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.default_pipeline(), Some("my_pipeline".to_owned()));
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_store_ctid_field() {
+        Spi::run(
+            "CREATE TABLE test_store_ctid AS SELECT * FROM generate_series(1, 10);
+        CREATE INDEX idxtest_store_ctid
+                  ON test_store_ctid
+               USING zombodb ((test_store_ctid.*)) WITH (url='http://localhost:19200/', store_ctid_field=true);",
+        );
+
+        let index_relation =
+            PgRelation::open_with_name("idxtest_store_ctid").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert_eq!(options.store_ctid_field(), true);
+
+        let field_type = Spi::get_one::<&str>(
+            "SELECT zdb.field_mapping('idxtest_store_ctid', '_zdb_ctid')->>'type';",
+        );
+        assert_eq!(field_type, Some("keyword"));
+
+        let count = Spi::get_one::<i64>(
+            "SELECT count(*) FROM test_store_ctid WHERE test_store_ctid ==> dsl.term('_zdb_ctid', '(0,1)');",
+        );
+        assert_eq!(count, Some(1));
+    }
+
+    #[pg_test(
+        error = "invalid aggregation_timezone setting.  Must be 'UTC', an offset such as '+01:00', or a region name such as 'America/New_York': Mars/Olympus_Mons"
+    )]
+    fn test_validate_invalid_aggregation_timezone() {
+        validate_aggregation_timezone("Mars/Olympus_Mons".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_valid_aggregation_timezone() {
+        validate_aggregation_timezone("America/New_York".as_pg_cstr());
+        validate_aggregation_timezone("-05:00".as_pg_cstr());
+        validate_aggregation_timezone("UTC".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_aggregation_timezone() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', aggregation_timezone='America/New_York');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.aggregation_timezone(), "America/New_York");
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_stopwords() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', stopwords='foo, bar,baz');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(
+            options.stopwords(),
+            Some(&vec!["foo".to_owned(), "bar".to_owned(), "baz".to_owned()])
+        );
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_stopwords_none() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', stopwords='_none_');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.stopwords(), Some(&Vec::new()));
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_stopwords_analysis() {
+        Spi::run(
+            "CREATE TABLE test(title text);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', stopwords='foo,bar');",
+        );
+
+        let stopwords = Spi::get_one::<JsonB>(
+            "SELECT zdb.index_settings('idxtest')->zdb.index_name('idxtest')->'settings'->'index'->'analysis'->'filter'->'zdb_stopwords'->'stopwords';",
+        )
+        .expect("no stopwords filter");
+        assert_eq!(stopwords.0, serde_json::json!(["foo", "bar"]));
+
+        let analyzer = Spi::get_one::<&str>(
+            "SELECT zdb.index_mapping('idxtest')->zdb.index_name('idxtest')->'mappings'->'properties'->'title'->>'analyzer';",
+        )
+        .expect("title has no analyzer");
+        assert_eq!(analyzer, "zdb_stopwords_analyzer");
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_routing_partition_size() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', shards=5, routing_partition_size=3);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.routing_partition_size(), 3);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_default_routing_partition_size() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(
+            options.routing_partition_size(),
+            DEFAULT_ROUTING_PARTITION_SIZE
+        );
+    }
+
+    #[pg_test(error = "routing_partition_size (2) must be less than shards (2)")]
+    #[initialize(es = true)]
+    unsafe fn test_index_routing_partition_size_too_large() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', shards=2, routing_partition_size=2);",
+        );
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_routing_partition_size_writes() {
+        Spi::run(
+            "CREATE TABLE test(n int);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', shards=5, routing_partition_size=3);",
+        );
+
+        // every bulk action, including the zdb_aborted_xids upserts, needs to be routed
+        Spi::run("INSERT INTO test SELECT n FROM generate_series(1, 10) n;");
+        Spi::run("UPDATE test SET n = 100 WHERE n = 1;");
+        Spi::run("DELETE FROM test WHERE n = 2;");
+
+        // and so does the visibility clause's lookup of the zdb_aborted_xids doc
+        let count = Spi::get_one::<i64>("SELECT zdb.count('idxtest', dsl.match_all());")
+            .expect("failed to get SPI result");
+        assert_eq!(count, 9);
+
+        let updated = Spi::get_one::<i64>(
+            "SELECT count(*) FROM (SELECT * FROM test WHERE test ==> dsl.limit(10, 'n:100')) x;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(updated, 1);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_allow_leading_wildcard() {
+        Spi::run(
+            "CREATE TABLE test(title text);  
+        INSERT INTO test VALUES ('brown'), ('green');
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', allow_leading_wildcard=true);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.allow_leading_wildcard(), true);
+
+        // ES has no such index setting, so it's applied to each query_string query instead
+        let count =
+            Spi::get_one::<i64>("SELECT zdb.count('idxtest', dsl.query_string('title:*own'));")
+                .expect("failed to get SPI result");
+        assert_eq!(count, 1);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_default_allow_leading_wildcard() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.allow_leading_wildcard(), false);
+    }
+
+    #[pg_test(error = "invalid disable_norms_fields setting.  Field names cannot be empty: 'title,,body'")]
+    fn test_validate_invalid_disable_norms_fields() {
+        validate_disable_norms_fields("title,,body".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_disable_norms_fields() {
+        Spi::run(
+            "CREATE TABLE test(title text, body text, tag varchar);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', disable_norms_fields='title, tag');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert_eq!(
+            options.disable_norms_fields(),
+            &vec!["title".to_owned(), "tag".to_owned()]
+        );
+
+        let norms = |field: &str| {
+            Spi::get_one::<bool>(&format!(
+                "SELECT (zdb.field_mapping('idxtest', '{}')->>'norms')::bool;",
+                field
+            ))
+        };
+        assert_eq!(norms("title"), Some(false));
+        assert_eq!(norms("tag"), Some(false));
+        assert_eq!(norms("body"), None);
+    }
+
+    #[pg_test(
+        error = "invalid tier_preference setting.  Must be a comma-separated list of 'data_content', 'data_hot', 'data_warm', 'data_cold', or 'data_frozen': data_hot,data_lukewarm"
+    )]
+    fn test_validate_invalid_tier_preference() {
+        validate_tier_preference("data_hot,data_lukewarm".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_valid_tier_preference() {
+        validate_tier_preference("data_hot,data_warm".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_tier_preference() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', tier_preference='data_hot,data_warm');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.tier_preference(), Some("data_hot,data_warm".to_owned()));
+    }
+
+    #[pg_test(
+        error = "invalid flush_mode setting.  Must be one of 'transaction' or 'statement': query"
+    )]
+    fn test_validate_invalid_flush_mode() {
+        validate_flush_mode("query".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_flush_mode_statement() {
+        Spi::run(
+            "CREATE TABLE test(id bigint);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', flush_mode='statement');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert_eq!(options.flush_mode(), FlushMode::Statement);
+
+        // zdb.request() doesn't flush pending changes itself, so the row is only in
+        // Elasticsearch if the INSERT statement flushed it when it finished
+        Spi::run("INSERT INTO test VALUES (1);");
+        Spi::run("SELECT zdb.request('idxtest', '_refresh', 'POST');");
+        let count = Spi::get_one::<i64>(
+            "SELECT (zdb.request('idxtest', '_count')::jsonb->>'count')::bigint;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(count, 1);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_final_pipeline() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', final_pipeline='my_final_pipeline');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.final_pipeline(), Some("my_final_pipeline".to_owned()));
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_max_script_fields() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', max_script_fields=64);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.max_script_fields(), 64);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_default_max_script_fields() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.max_script_fields(), DEFAULT_MAX_SCRIPT_FIELDS);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_mapping_coerce() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', mapping_coerce=false);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.mapping_coerce(), false);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_replicas_from_guc() {
+        Spi::run("SET zdb.default_replicas = 2;");
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', shards=1);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.replicas(), 2);
+    }
+
+    #[pg_test(
+        error = "invalid store_type setting.  Must be one of 'fs', 'mmapfs', 'niofs', or 'hybridfs': simplefs"
+    )]
+    fn test_validate_invalid_store_type() {
+        validate_store_type("simplefs".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_valid_store_types() {
+        validate_store_type("fs".as_pg_cstr());
+        validate_store_type("mmapfs".as_pg_cstr());
+        validate_store_type("niofs".as_pg_cstr());
+        validate_store_type("hybridfs".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_store_type() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', store_type='niofs');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.store_type(), "niofs");
+    }
+
+    #[pg_test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"elastic:changeme"), "ZWxhc3RpYzpjaGFuZ2VtZQ==");
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_username_password() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', username='elastic', password='changeme');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.username(), Some("elastic".to_owned()));
+        assert_eq!(options.password(), Some("changeme".to_owned()));
+        assert_eq!(
+            options.basic_auth_header(),
+            Some("Basic ZWxhc3RpYzpjaGFuZ2VtZQ==".to_owned())
+        );
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_username_without_password() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', username='elastic');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.username(), Some("elastic".to_owned()));
+        assert_eq!(options.password(), None);
+        assert_eq!(options.basic_auth_header(), None);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_search_throttled() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', search_throttled=true);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.search_throttled(), true);
+
+        // index.search.throttled is private to Elasticsearch, so ZomboDB must never send it
+        let throttled = Spi::get_one::<&str>(
+            "SELECT zdb.index_settings('idxtest')->zdb.index_name('idxtest')->'settings'->'index'->'search'->>'throttled';",
+        );
+        assert_eq!(throttled, None);
+    }
+
+    #[pg_test(error = "ES index missing; reindex required: idxtest")]
+    #[initialize(es = true)]
+    unsafe fn test_verify_index_on_scan_missing_index() {
+        Spi::run(
+            "CREATE TABLE test AS SELECT 'hello'::text AS title;  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', verify_index_on_scan=true);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        Elasticsearch::new(&index_relation)
+            .delete_index()
+            .execute()
+            .expect("failed to delete ES index");
+
+        Spi::run("SET enable_seqscan TO off");
+        Spi::run("SELECT * FROM test WHERE test ==> 'title:hello'");
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_from_heap() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let heap_relation = PgRelation::open_with_name("test").expect("no such relation");
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_heap(&heap_relation).expect("no zombodb index found");
+
+        assert_eq!(options.oid(), index_relation.oid());
+    }
+
+    #[pg_test]
+    unsafe fn test_from_heap_without_zombodb_index() {
+        Spi::run("CREATE TABLE test(id int);  CREATE INDEX idxtest ON test(id);");
+
+        let heap_relation = PgRelation::open_with_name("test").expect("no such relation");
+        assert_eq!(ZDBIndexOptions::from_heap(&heap_relation), None);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_max_docs() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', max_docs=42);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.max_docs(), 42);
+    }
+
+    #[pg_test(error = "idxtest has reached its max_docs limit of 5")]
+    #[initialize(es = true)]
+    unsafe fn test_max_docs_exceeded() {
+        Spi::run(
+            "CREATE TABLE test AS SELECT * FROM generate_series(1, 3);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', max_docs=5);",
+        );
+
+        Spi::run("INSERT INTO test SELECT * FROM generate_series(4, 10);");
+    }
+
+    #[pg_test(error = "search_preference must not be empty")]
+    fn test_validate_empty_search_preference() {
+        validate_search_preference("".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_search_preference() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', search_preference='_local');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.search_preference(), Some("_local".to_owned()));
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_no_search_preference() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.search_preference(), None);
+    }
+
+    #[pg_test]
+    fn test_validate_compression_type() {
+        validate_compression_type("none".as_pg_cstr());
+        validate_compression_type("deflate".as_pg_cstr());
+        validate_compression_type("gzip".as_pg_cstr());
+    }
+
+    #[pg_test(
+        error = "invalid compression_type setting.  Must be one of 'none', 'deflate', or 'gzip': lz4"
+    )]
+    fn test_validate_invalid_compression_type() {
+        validate_compression_type("lz4".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_default_compression_type() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.compression_type(), CompressionType::Deflate);
+        assert!(options.is_compression_enabled());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_compression_type_none() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', compression_type='none', compression_level=9);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.compression_type(), CompressionType::None);
+        assert!(!options.is_compression_enabled());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_ignore_malformed() {
+        Spi::run(
+            "CREATE TABLE test(data json);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', ignore_malformed=true);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert_eq!(options.ignore_malformed(), true);
+
+        let setting = Spi::get_one::<&str>(
+            "SELECT zdb.index_settings('idxtest')->zdb.index_name('idxtest')->'settings'->'index'->'mapping'->>'ignore_malformed';",
+        )
+        .expect("no ignore_malformed setting");
+        assert_eq!(setting, "true");
+
+        // the second document's "n" is malformed for the dynamically-mapped numeric field
+        // but the document itself is still indexed
+        Spi::run(r#"INSERT INTO test VALUES ('{"n": 5}'), ('{"n": "not a number"}');"#);
+        let count = Spi::get_one::<i64>(
+            "SELECT count(*) FROM test WHERE test ==> dsl.match_all();",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(count, 2);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_bulk_concurrency_clamped() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', bulk_concurrency=64);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        Spi::run("SET zdb.max_bulk_concurrency TO 128;");
+        assert_eq!(options.bulk_concurrency(), 64);
+
+        Spi::run("SET zdb.max_bulk_concurrency TO 2;");
+        assert_eq!(options.bulk_concurrency(), 2);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_request_timeout() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', request_timeout=30);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(
+            options.request_timeout(),
+            Some(std::time::Duration::from_secs(30))
+        );
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_default_request_timeout() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.request_timeout(), None);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_max_concurrent_writers() {
+        Spi::run(
+            "CREATE TABLE test(id bigint);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', max_concurrent_writers=1);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert_eq!(options.max_concurrent_writers(), 1);
+
+        // writing to the index takes its one writer slot, which any other backend would
+        // then wait on until this transaction ends
+        Spi::run("INSERT INTO test VALUES (1);");
+        let slots = Spi::get_one::<i64>(
+            "SELECT count(*) FROM pg_locks WHERE locktype = 'advisory' AND pid = pg_backend_pid() AND objid = 0;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(slots, 1);
+
+        // and writing again before the bulk context is flushed doesn't need another slot
+        Spi::run("INSERT INTO test VALUES (2);");
+        let slots = Spi::get_one::<i64>(
+            "SELECT count(*) FROM pg_locks WHERE locktype = 'advisory' AND pid = pg_backend_pid();",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(slots, 1);
+
+        // searching flushes the bulk context, which gives up the slot
+        Spi::run("SELECT zdb.count('idxtest', dsl.match_all());");
+        let slots = Spi::get_one::<i64>(
+            "SELECT count(*) FROM pg_locks WHERE locktype = 'advisory' AND pid = pg_backend_pid();",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(slots, 0);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_max_concurrent_writers_holds_writer_slot() {
+        Spi::run(
+            "CREATE TABLE test(id bigint);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', max_concurrent_writers=1);",
+        );
+
+        // writer slots are the advisory locks pg_advisory_xact_lock(hashtext(uuid), slot) takes
+        let slot_locks = || {
+            Spi::get_one::<i64>(
+                "SELECT count(*) FROM pg_locks
+                  WHERE locktype = 'advisory'
+                    AND classid = hashtext(zdb.index_name('idxtest'))::oid
+                    AND objid = 0
+                    AND objsubid = 2
+                    AND mode = 'ExclusiveLock'
+                    AND pid = pg_backend_pid()
+                    AND granted;",
+            )
+            .expect("failed to get SPI result")
+        };
+        assert_eq!(slot_locks(), 0);
+
+        // writing to the index takes its only writer slot
+        Spi::run("INSERT INTO test VALUES (1);");
+        assert_eq!(slot_locks(), 1);
+
+        // which is the same lock as the advisory lock on the same key, so other backends wait on
+        // it just as they would on pg_advisory_xact_lock()
+        let taken = Spi::get_one::<bool>(
+            "SELECT pg_try_advisory_xact_lock(hashtext(zdb.index_name('idxtest')), 0);",
+        )
+        .expect("failed to get SPI result");
+        assert!(taken);
+        assert_eq!(slot_locks(), 1);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_max_concurrent_writers_releases_writer_slot() {
+        Spi::run(
+            "CREATE TABLE test(id bigint);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', max_concurrent_writers=1);",
+        );
+
+        let slot_locks = || {
+            Spi::get_one::<i64>(
+                "SELECT count(*) FROM pg_locks
+                  WHERE locktype = 'advisory'
+                    AND classid = hashtext(zdb.index_name('idxtest'))::oid
+                    AND pid = pg_backend_pid();",
+            )
+            .expect("failed to get SPI result")
+        };
+
+        // the slot is only held until the writes are flushed, which searching the index does
+        Spi::run("INSERT INTO test VALUES (1);");
+        assert_eq!(slot_locks(), 1);
+        Spi::run("SELECT zdb.count('idxtest', dsl.match_all());");
+        assert_eq!(slot_locks(), 0);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_unlimited_concurrent_writers() {
+        Spi::run(
+            "CREATE TABLE test(id bigint);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        Spi::run("INSERT INTO test VALUES (1);");
+        let slots = Spi::get_one::<i64>(
+            "SELECT count(*) FROM pg_locks WHERE locktype = 'advisory' AND pid = pg_backend_pid();",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(slots, 0);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_max_shingle_diff() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', max_shingle_diff=10);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.max_shingle_diff(), 10);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_default_max_shingle_diff() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.max_shingle_diff(), DEFAULT_MAX_SHINGLE_DIFF);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_synthetic_source() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', synthetic_source=true);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.synthetic_source(), true);
+    }
+
+    #[pg_test(error = "invalid op_type setting.  Must be one of 'index' or 'create': upsert")]
+    fn test_validate_invalid_op_type() {
+        validate_op_type("upsert".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_op_type() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', op_type='create');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.op_type(), OpType::Create);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_op_type_create_conflict() {
+        Spi::run(
+            "CREATE TABLE test(id bigint);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', op_type='create', flush_mode='statement');",
+        );
+
+        // the first row inserted into the table will be at ctid (0,1), whose document _id is 1
+        Spi::run("SELECT zdb.request('idxtest', '_doc/1', 'PUT', '{}');");
+        Spi::run(
+            "CREATE FUNCTION pg_temp.insert_conflicts() RETURNS boolean LANGUAGE plpgsql AS $$
+            BEGIN
+                INSERT INTO test VALUES (1);
+                RETURN false;
+            EXCEPTION WHEN OTHERS THEN
+                RETURN SQLERRM LIKE '%version_conflict_engine_exception%';
+            END;
+            $$;",
+        );
+
+        let conflicted = Spi::get_one::<bool>("SELECT pg_temp.insert_conflicts();")
+            .expect("failed to get SPI result");
+        assert!(conflicted);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_updatable_synonyms() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', updatable_synonyms=true);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.updatable_synonyms(), true);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_eager_bitset_filters() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', eager_bitset_filters=false);",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.eager_bitset_filters(), false);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_default_eager_bitset_filters() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+
+        assert_eq!(options.eager_bitset_filters(), true);
+    }
+
+    #[pg_test(error = "invalid rank_feature_fields setting.  Field names cannot be empty: 'popularity,'")]
+    fn test_validate_invalid_rank_feature_fields() {
+        validate_rank_feature_fields("popularity,".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_rank_feature_fields() {
+        Spi::run(
+            "CREATE TABLE test(title text, popularity integer, rating real);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', rank_feature_fields='popularity, rating');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert_eq!(
+            options.rank_feature_fields(),
+            &vec!["popularity".to_owned(), "rating".to_owned()]
+        );
+
+        let field_type = |field: &str| {
+            Spi::get_one::<&str>(&format!(
+                "SELECT zdb.field_mapping('idxtest', '{}')->>'type';",
+                field
+            ))
+        };
+        assert_eq!(field_type("popularity"), Some("rank_feature"));
+        assert_eq!(field_type("rating"), Some("rank_feature"));
+        assert_eq!(field_type("title"), Some("text"));
+    }
+
+    #[pg_test(error = "rank_feature_fields column is not numeric: title")]
+    #[initialize(es = true)]
+    unsafe fn test_index_rank_feature_fields_not_numeric() {
+        Spi::run(
+            "CREATE TABLE test(title text);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', rank_feature_fields='title');",
+        );
+    }
+
+    #[pg_test(error = "invalid dense_vector_fields setting.  Must be a comma-separated list of field:dims pairs where dims is a positive integer: 'embedding:0'")]
+    fn test_validate_invalid_dense_vector_fields() {
+        validate_dense_vector_fields("embedding:0".as_pg_cstr());
+    }
 
-    #[pg_test]
-    fn test_validate_url() {
-        validate_url("http://localhost:9200/".as_pg_cstr());
+    #[pg_test(error = "invalid dense_vector_fields setting.  Must be a comma-separated list of field:dims pairs where dims is a positive integer: 'embedding'")]
+    fn test_validate_missing_dense_vector_dims() {
+        validate_dense_vector_fields("embedding".as_pg_cstr());
     }
 
     #[pg_test]
-    fn test_validate_default_url() {
-        validate_url("default".as_pg_cstr());
-    }
+    #[initialize(es = true)]
+    unsafe fn test_index_dense_vector_fields() {
+        Spi::run(
+            "CREATE TABLE test(title text, embedding float8[]);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', dense_vector_fields='embedding:3');",
+        );
 
-    #[pg_test(error = "url must end with a forward slash")]
-    fn test_validate_invalid_url() {
-        validate_url("http://localhost:9200".as_pg_cstr());
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert_eq!(
+            options.dense_vector_fields(),
+            &vec![("embedding".to_owned(), 3)]
+        );
+
+        let mapping = Spi::get_one::<JsonB>("SELECT zdb.field_mapping('idxtest', 'embedding');")
+            .expect("failed to get SPI result");
+        assert_eq!(mapping.0["type"], "dense_vector");
+        assert_eq!(mapping.0["dims"], 3);
     }
 
-    #[pg_test(
-        error = "invalid translog_durability setting.  Must be one of 'request' or 'async': foo"
-    )]
-    fn test_validate_invalid_translog_durability() {
-        validate_translog_durability("foo".as_pg_cstr());
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_default_auto_search_after() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert!(options.auto_search_after());
     }
 
     #[pg_test]
-    fn test_valid_translog_durability_request() {
-        validate_translog_durability("request".as_pg_cstr());
+    #[initialize(es = true)]
+    unsafe fn test_auto_search_after_past_max_result_window() {
+        Spi::run(
+            "CREATE TABLE test AS SELECT id FROM generate_series(1, 100) id;  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', max_result_window=10);",
+        );
+
+        let ids = Spi::get_one::<Vec<i32>>(
+            "SELECT array_agg(t.id ORDER BY s.ord)
+               FROM zdb.search_sorted('idxtest', '', '[{\"id\": \"desc\"}]', 75) WITH ORDINALITY s(tid, ord)
+               JOIN test t ON t.ctid = s.tid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(ids, (26..=100).rev().collect::<Vec<_>>());
     }
 
     #[pg_test]
-    fn test_valid_translog_durability_async() {
-        validate_translog_durability("async".as_pg_cstr());
+    #[initialize(es = true)]
+    unsafe fn test_scroll_past_max_result_window() {
+        Spi::run(
+            "CREATE TABLE test AS SELECT id FROM generate_series(1, 100) id;  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', max_result_window=10, auto_search_after=false);",
+        );
+
+        let count = Spi::get_one::<i64>(
+            "SELECT count(*) FROM zdb.search_sorted('idxtest', '', '[{\"id\": \"desc\"}]', 75);",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(count, 75);
     }
 
     #[pg_test]
     #[initialize(es = true)]
-    unsafe fn test_index_options() {
-        let uuid = 42424242;
-        Spi::run(&format!(
+    unsafe fn test_index_minimize_bulk_metadata() {
+        Spi::run(
             "CREATE TABLE test();  
         CREATE INDEX idxtest 
                   ON test 
-               USING zombodb ((test.*)) 
-                WITH (url='http://localhost:19200/', 
-                      type_name='test_type_name', 
-                      alias='test_alias', 
-                      uuid='{}', 
-                      refresh_interval='5s',
-                      translog_durability='async');",
-            uuid
-        ));
-
-        let index_oid = Spi::get_one::<pg_sys::Oid>("SELECT 'idxtest'::regclass::oid")
-            .expect("failed to get SPI result");
-        let indexrel = PgRelation::from_pg(pg_sys::RelationIdGetRelation(index_oid));
-        let options = ZDBIndexOptions::from_relation(&indexrel);
-        assert_eq!(options.url(), "http://localhost:19200/");
-        assert_eq!(options.type_name(), "test_type_name");
-        assert_eq!(options.alias(), "test_alias");
-        assert_eq!(options.uuid(), &uuid.to_string());
-        assert_eq!(
-            options.refresh_interval(),
-            RefreshInterval::Background("5s".to_owned())
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', minimize_bulk_metadata=false);",
         );
-        assert_eq!(options.compression_level(), 1);
-        assert_eq!(options.shards(), 5);
-        assert_eq!(options.replicas(), 0);
-        assert_eq!(options.bulk_concurrency(), num_cpus::get() as i32);
-        assert_eq!(options.batch_size(), 8 * 1024 * 1024);
-        assert_eq!(options.optimize_after(), DEFAULT_OPTIMIZE_AFTER);
-        assert_eq!(options.llapi(), false);
-        assert_eq!(options.translog_durability(), "async");
-        assert_eq!(options.links, None);
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert!(!options.minimize_bulk_metadata());
     }
 
     #[pg_test]
     #[initialize(es = true)]
-    unsafe fn test_index_options_defaults() {
+    unsafe fn test_index_default_minimize_bulk_metadata() {
         Spi::run(
             "CREATE TABLE test();  
         CREATE INDEX idxtest 
@@ -1193,42 +4992,75 @@ mod tests {
                USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
         );
 
-        let heap_oid = Spi::get_one::<pg_sys::Oid>("SELECT 'test'::regclass::oid")
-            .expect("failed to get SPI result");
-        let index_oid = Spi::get_one::<pg_sys::Oid>("SELECT 'idxtest'::regclass::oid")
-            .expect("failed to get SPI result");
-        let heaprel = PgRelation::from_pg(pg_sys::RelationIdGetRelation(heap_oid));
-        let indexrel = PgRelation::from_pg(pg_sys::RelationIdGetRelation(index_oid));
-        let options = ZDBIndexOptions::from_relation(&indexrel);
-        assert_eq!(options.type_name(), DEFAULT_TYPE_NAME);
-        assert_eq!(
-            &options.alias(),
-            &format!("pgx_tests.public.test.idxtest-{}", indexrel.oid())
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert!(options.minimize_bulk_metadata());
+    }
+
+    #[pg_test]
+    fn test_validate_percentage_default_minimum_should_match() {
+        validate_default_minimum_should_match("75%".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_validate_integer_default_minimum_should_match() {
+        validate_default_minimum_should_match("2".as_pg_cstr());
+    }
+
+    #[pg_test]
+    fn test_validate_combination_default_minimum_should_match() {
+        validate_default_minimum_should_match("2<-25% 9<-3".as_pg_cstr());
+    }
+
+    #[pg_test(error = "invalid default_minimum_should_match setting.  Must be an integer, a percentage, or a combination such as '3<90%': 'most'")]
+    fn test_validate_invalid_default_minimum_should_match() {
+        validate_default_minimum_should_match("most".as_pg_cstr());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_default_minimum_should_match() {
+        Spi::run(
+            "CREATE TABLE test AS SELECT * FROM (VALUES
+                ('apple banana cherry date'),
+                ('apple banana'),
+                ('apple')) t(body);  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', default_minimum_should_match='75%');",
         );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert_eq!(options.default_minimum_should_match(), Some("75%"));
+
+        let count = |query: &str| {
+            Spi::get_one::<i64>(&format!(
+                "SELECT count(*) FROM test WHERE test ==> {};",
+                query
+            ))
+            .expect("failed to get SPI result")
+        };
+        assert_eq!(count("dsl.match('body', 'apple banana cherry date')"), 1);
+
+        // a match query's own minimum_should_match wins
         assert_eq!(
-            &options.uuid(),
-            &format!(
-                "{}.{}.{}.{}",
-                pg_sys::MyDatabaseId,
-                indexrel.namespace_oid(),
-                heaprel.oid(),
-                indexrel.oid()
-            )
+            count("dsl.match('body', 'apple banana cherry date', minimum_should_match => 1)"),
+            3
         );
-        assert_eq!(options.refresh_interval(), RefreshInterval::Immediate);
-        assert_eq!(options.compression_level(), DEFAULT_COMPRESSION_LEVEL);
-        assert_eq!(options.shards(), DEFAULT_SHARDS);
-        assert_eq!(options.replicas(), ZDB_DEFAULT_REPLICAS.get());
-        assert_eq!(options.bulk_concurrency(), *DEFAULT_BULK_CONCURRENCY);
-        assert_eq!(options.batch_size(), DEFAULT_BATCH_SIZE);
-        assert_eq!(options.optimize_after(), DEFAULT_OPTIMIZE_AFTER);
-        assert_eq!(options.llapi(), false);
-        assert_eq!(options.translog_durability(), "request")
+
+        Spi::run("ALTER INDEX idxtest SET (default_minimum_should_match='2');");
+        assert_eq!(count("dsl.match('body', 'apple banana cherry date')"), 2);
+    }
+
+    #[pg_test(error = "invalid tiebreaker_field setting.  Must be a single field name: 'id, name'")]
+    fn test_validate_invalid_tiebreaker_field() {
+        validate_tiebreaker_field("id, name".as_pg_cstr());
     }
 
     #[pg_test]
     #[initialize(es = true)]
-    unsafe fn test_index_name() {
+    unsafe fn test_index_default_tiebreaker_field() {
         Spi::run(
             "CREATE TABLE test();  
         CREATE INDEX idxtest 
@@ -1238,29 +5070,67 @@ mod tests {
 
         let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
         let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert_eq!(options.tiebreaker_field(), DEFAULT_TIEBREAKER_FIELD);
+    }
 
-        assert_eq!(options.index_name(), options.uuid());
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_tiebreaker_field_pagination() {
+        Spi::run(
+            "CREATE TABLE test AS SELECT id, id % 3 AS bucket FROM generate_series(1, 100) id;  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', max_result_window=10, tiebreaker_field='id');",
+        );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert_eq!(options.tiebreaker_field(), "id");
+
+        // only three distinct sort values, so every page boundary falls within a run of ties
+        let ids = Spi::get_one::<Vec<i32>>(
+            "SELECT array_agg(t.id ORDER BY s.ord)
+               FROM zdb.search_sorted('idxtest', '', '[{\"bucket\": \"asc\"}]', 100) WITH ORDINALITY s(tid, ord)
+               JOIN test t ON t.ctid = s.tid;",
+        )
+        .expect("failed to get SPI result");
+
+        let mut expected = (1..=100).collect::<Vec<i32>>();
+        expected.sort_by_key(|id| (id % 3, *id));
+        assert_eq!(ids, expected);
     }
 
     #[pg_test]
     #[initialize(es = true)]
-    unsafe fn test_index_url() {
+    unsafe fn test_index_store_doc_size() {
         Spi::run(
-            "CREATE TABLE test();  
+            "CREATE TABLE test AS SELECT 'hello world' AS title;  
         CREATE INDEX idxtest 
                   ON test 
-               USING zombodb ((test.*)) WITH (url='http://localhost:19200/');",
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', store_doc_size=true);",
         );
 
         let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
         let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert!(options.store_doc_size());
 
-        assert_eq!(options.url(), "http://localhost:19200/");
+        let has_mapper_size = Spi::get_one::<bool>(
+            "SELECT zdb.request('idxtest', '/_cat/plugins?h=component&format=json')::jsonb @> '[{\"component\": \"mapper-size\"}]';",
+        )
+        .expect("failed to get SPI result");
+        if !has_mapper_size {
+            // the index was still created, just without a _size field
+            return;
+        }
+
+        let size = Spi::get_one::<i64>("SELECT zdb.doc_size('idxtest', ctid) FROM test;")
+            .expect("no document size");
+        assert!(size > 0);
     }
 
     #[pg_test]
     #[initialize(es = true)]
-    unsafe fn test_index_type_name() {
+    unsafe fn test_index_default_store_doc_size() {
         Spi::run(
             "CREATE TABLE test();  
         CREATE INDEX idxtest 
@@ -1270,27 +5140,127 @@ mod tests {
 
         let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
         let options = ZDBIndexOptions::from_relation(&index_relation);
-
-        assert_eq!(options.type_name(), "doc");
+        assert!(!options.store_doc_size());
     }
 
     #[pg_test]
     #[initialize(es = true)]
-    unsafe fn test_index_link_options() {
+    unsafe fn test_index_agg_max_buckets() {
         Spi::run(
-            "CREATE TABLE test_link_options();  
-        CREATE INDEX idxtest_link_options
-                  ON test_link_options
-               USING zombodb ((test_link_options.*)) WITH (options='id=<schema.table.index>other_id');",
+            "CREATE TABLE test AS SELECT n FROM generate_series(1, 100) n;  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', agg_max_buckets=50);",
         );
 
-        let index_relation =
-            PgRelation::open_with_name("idxtest_link_options").expect("no such relation");
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
         let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert_eq!(options.agg_max_buckets(), 50);
 
-        assert_eq!(
-            options.links(),
-            &Some(vec!["id=<schema.table.index>other_id".to_string()])
+        let terms = Spi::get_one::<i64>("SELECT count(*) FROM zdb.terms('idxtest', 'n', '', 10);")
+            .expect("failed to get SPI result");
+        assert_eq!(terms, 10);
+    }
+
+    #[pg_test(
+        error = "aggregation could produce up to 1000 buckets, which exceeds this index's agg_max_buckets of 50"
+    )]
+    #[initialize(es = true)]
+    unsafe fn test_agg_max_buckets_rejects_large_terms() {
+        Spi::run(
+            "CREATE TABLE test AS SELECT n FROM generate_series(1, 100) n;  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', agg_max_buckets=50);",
+        );
+
+        Spi::run("SELECT * FROM zdb.terms('idxtest', 'n', '', 1000);");
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_agg_max_buckets_default_size() {
+        Spi::run(
+            "CREATE TABLE test AS SELECT n % 10 AS n FROM generate_series(1, 100) n;  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', agg_max_buckets=50);",
+        );
+
+        // without a size_limit, the number of terms depends on the data and isn't counted
+        let terms = Spi::get_one::<i64>("SELECT count(*) FROM zdb.terms('idxtest', 'n', '');")
+            .expect("failed to get SPI result");
+        assert_eq!(terms, 10);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_agg_max_buckets_ignores_default_size() {
+        Spi::run(
+            "CREATE TABLE test AS SELECT n FROM generate_series(1, 100) n;  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', agg_max_buckets=50);",
+        );
+
+        // it's left to search.max_buckets to limit every term
+        let terms = Spi::get_one::<i64>("SELECT count(*) FROM zdb.terms('idxtest', 'n', '');")
+            .expect("failed to get SPI result");
+        assert_eq!(terms, 100);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_agg_max_buckets_histogram() {
+        Spi::run(
+            "CREATE TABLE test AS SELECT n FROM generate_series(1, 100) n;  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', agg_max_buckets=50);",
+        );
+
+        // 1 through 100 in buckets of 10 is 0-9 through 100-109
+        let buckets =
+            Spi::get_one::<i64>("SELECT count(*) FROM zdb.histogram('idxtest', 'n', '', 10);")
+                .expect("failed to get SPI result");
+        assert_eq!(buckets, 11);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_agg_max_buckets_ignores_histogram() {
+        Spi::run(
+            "CREATE TABLE test AS SELECT n FROM generate_series(1, 100) n;  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', agg_max_buckets=50);",
+        );
+
+        // a histogram's buckets depend on the data, so it's left to search.max_buckets
+        let buckets =
+            Spi::get_one::<i64>("SELECT count(*) FROM zdb.histogram('idxtest', 'n', '', 1);")
+                .expect("failed to get SPI result");
+        assert_eq!(buckets, 100);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    unsafe fn test_index_field_name_length_limit() {
+        Spi::run(
+            "CREATE TABLE test();  
+        CREATE INDEX idxtest 
+                  ON test 
+               USING zombodb ((test.*)) WITH (url='http://localhost:19200/', field_name_length_limit=255);",
         );
+
+        let index_relation = PgRelation::open_with_name("idxtest").expect("no such relation");
+        let options = ZDBIndexOptions::from_relation(&index_relation);
+        assert_eq!(options.field_name_length_limit(), 255);
+
+        let setting = Spi::get_one::<&str>(
+            "SELECT zdb.index_settings('idxtest')->zdb.index_name('idxtest')->'settings'->'index'->'mapping'->'field_name_length'->>'limit';",
+        )
+        .expect("no field_name_length.limit setting");
+        assert_eq!(setting, "255");
     }
 }
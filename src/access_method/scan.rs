@@ -1,3 +1,4 @@
+use crate::access_method::options::ZDBIndexOptions;
 use crate::elasticsearch::search::SearchResponseIntoIter;
 use crate::elasticsearch::Elasticsearch;
 use crate::executor_manager::get_executor_manager;
@@ -64,7 +65,18 @@ pub extern "C" fn amrescan(
         });
     }
 
-    let elasticsearch = Elasticsearch::new(&indexrel);
+    let options = ZDBIndexOptions::from_relation(&indexrel);
+    let verify_index = options.verify_index_on_scan();
+    let elasticsearch = Elasticsearch::from_options(options);
+
+    if verify_index
+        && !elasticsearch
+            .index_exists()
+            .execute()
+            .unwrap_or_else(|e| panic!("{}", e))
+    {
+        panic!("ES index missing; reindex required: {}", indexrel.name());
+    }
 
     let response = elasticsearch
         .open_search(query.prepare(&indexrel, None).0)
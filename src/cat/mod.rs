@@ -1,6 +1,8 @@
 use pgx::*;
+use serde::*;
 
 use crate::elasticsearch::Elasticsearch;
+use crate::utils::lookup_all_zdb_index_oids;
 
 #[pg_extern(immutable, parallel_safe)]
 fn cat_request(index: PgRelation, endpoint: &str) -> JsonB {
@@ -14,3 +16,159 @@ fn cat_request(index: PgRelation, endpoint: &str) -> JsonB {
     .expect("failed to convert _cat response to json");
     JsonB(result)
 }
+
+/// Returns the number of documents in each of the index's primary shards, from `_cat/shards`
+#[pg_extern(volatile, parallel_safe)]
+fn shard_doc_counts(
+    index: PgRelation,
+) -> impl std::iter::Iterator<Item = (name!(shard, i32), name!(doc_count, i64))> {
+    #[derive(Deserialize)]
+    struct CatShard {
+        shard: String,
+        prirep: String,
+        docs: Option<String>,
+    }
+
+    let es = Elasticsearch::new(&index);
+    let response = es
+        .cat(&format!("shards/{}", es.index_name()))
+        .execute()
+        .expect("failed to execute _cat request");
+    let shards = serde_json::from_str::<Vec<CatShard>>(&response)
+        .expect("failed to convert _cat response to json");
+
+    let mut counts = shards
+        .into_iter()
+        .filter(|shard| shard.prirep == "p")
+        .map(|shard| {
+            (
+                shard
+                    .shard
+                    .parse::<i32>()
+                    .expect("shard number is not an integer"),
+                // unassigned shards don't have a doc count
+                shard.docs.map_or(0, |docs| {
+                    docs.parse::<i64>().expect("docs is not an integer")
+                }),
+            )
+        })
+        .collect::<Vec<_>>();
+    counts.sort_unstable();
+    counts.into_iter()
+}
+
+/// Returns the health, document count, and store size of every ZomboDB index in the database,
+/// from `_cat/indices`.  Indexes on an Elasticsearch cluster that can't be reached are reported with
+/// a status of `unreachable` rather than failing the whole query
+#[pg_extern(volatile, parallel_safe)]
+fn health_summary() -> impl std::iter::Iterator<
+    Item = (
+        name!(index_name, String),
+        name!(es_index, String),
+        name!(status, String),
+        name!(docs, Option<i64>),
+        name!(store_bytes, Option<i64>),
+    ),
+> {
+    #[derive(Deserialize)]
+    struct CatIndex {
+        health: Option<String>,
+        #[serde(rename = "docs.count")]
+        docs_count: Option<String>,
+        #[serde(rename = "store.size")]
+        store_size: Option<String>,
+    }
+
+    let parse = |value: Option<String>| value.and_then(|value| value.parse::<i64>().ok());
+
+    let mut summary = Vec::new();
+    for oid in lookup_all_zdb_index_oids().unwrap_or_default() {
+        let index = PgRelation::with_lock(oid, pg_sys::AccessShareLock as pg_sys::LOCKMODE);
+        let es = Elasticsearch::new(&index);
+        let index_name = index.name().to_owned();
+        let es_index = es.index_name().to_owned();
+
+        match es.cat(&format!("indices/{}", es_index)).execute() {
+            Ok(response) => {
+                let cat_index = serde_json::from_str::<Vec<CatIndex>>(&response)
+                    .expect("failed to convert _cat response to json")
+                    .pop()
+                    .expect("_cat/indices returned no indices");
+                summary.push((
+                    index_name,
+                    es_index,
+                    cat_index.health.unwrap_or_else(|| "unknown".to_owned()),
+                    parse(cat_index.docs_count),
+                    parse(cat_index.store_size),
+                ));
+            }
+
+            // the cluster answered, but doesn't have the index
+            Err(e) if e.status() == Some(404) => {
+                summary.push((index_name, es_index, "missing".to_owned(), None, None))
+            }
+
+            // we didn't get an HTTP response at all
+            Err(e) if e.status().is_none() => {
+                summary.push((index_name, es_index, "unreachable".to_owned(), None, None))
+            }
+
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    summary.sort_by(|a, b| a.0.cmp(&b.0));
+    summary.into_iter()
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_shard_doc_counts() {
+        Spi::run("CREATE TABLE test_shard_doc_counts AS SELECT n FROM generate_series(1, 100) n;");
+        Spi::run("CREATE INDEX idxtest_shard_doc_counts ON test_shard_doc_counts USING zombodb ((test_shard_doc_counts.*)) WITH (shards = 3);");
+
+        let shards = Spi::get_one::<i64>(
+            "SELECT count(*) FROM zdb.shard_doc_counts('idxtest_shard_doc_counts');",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(shards, 3);
+
+        let total = Spi::get_one::<i64>(
+            "SELECT sum(doc_count)::bigint FROM zdb.shard_doc_counts('idxtest_shard_doc_counts');",
+        )
+        .expect("failed to get SPI result");
+        let count = Spi::get_one::<i64>(
+            "SELECT (zdb.request('idxtest_shard_doc_counts', '_count')::jsonb->>'count')::bigint;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(total, count);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_health_summary() {
+        Spi::run("CREATE TABLE test_health_a AS SELECT n FROM generate_series(1, 10) n;");
+        Spi::run(
+            "CREATE INDEX idxtest_health_a ON test_health_a USING zombodb ((test_health_a.*));",
+        );
+        Spi::run("CREATE TABLE test_health_b AS SELECT n FROM generate_series(1, 20) n;");
+        Spi::run(
+            "CREATE INDEX idxtest_health_b ON test_health_b USING zombodb ((test_health_b.*));",
+        );
+
+        let rows = Spi::get_one::<i64>(
+            "SELECT count(*)
+               FROM zdb.health_summary()
+              WHERE index_name IN ('idxtest_health_a', 'idxtest_health_b')
+                AND es_index = zdb.index_name(index_name::regclass)
+                AND status IN ('green', 'yellow')
+                AND store_bytes > 0;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(rows, 2);
+    }
+}
@@ -64,9 +64,22 @@ where
         self,
     ) -> std::result::Result<(ReturnType, HashMap<String, serde_json::Value>), ElasticsearchError>
     {
+        let max_buckets = self.elasticsearch.options.agg_max_buckets();
+        if max_buckets > 0 {
+            let buckets = estimate_buckets(&self.json_query["aggs"]);
+            if buckets > max_buckets as u64 {
+                panic!(
+                    "aggregation could produce up to {} buckets, which exceeds this index's agg_max_buckets of {}",
+                    buckets, max_buckets
+                );
+            }
+        }
+
         let mut url = self.elasticsearch.alias_url();
         url.push_str("/_search");
         url.push_str("?size=0");
+        url.push_str(self.elasticsearch.ignore_throttled_param());
+        url.push_str(&self.elasticsearch.preference_param());
 
         let client = Elasticsearch::client().get(&url);
 
@@ -136,3 +149,171 @@ where
         })
     }
 }
+
+/// An upper bound on the number of buckets the named aggregations in `aggs` could produce.  Only
+/// multi-bucket aggregations count:  each contributes the buckets it could create, and its
+/// sub-aggregations are multiplied out across each of those buckets.  Metric aggregations add
+/// nothing, and single-bucket aggregations like `nested` or `filter` only pass through the
+/// buckets of their sub-aggregations.
+///
+/// The estimate is made from the request alone.  Aggregations whose bucket count depends on the
+/// data -- `histogram` and `date_histogram`, `rare_terms`, and `terms`-like aggregations asking
+/// for every term with a `size` of `INT_MAX` -- can't be bounded that way, so neither they nor
+/// their sub-aggregations are counted, and it's left to the cluster's `search.max_buckets`
+fn estimate_buckets(aggs: &Value) -> u64 {
+    let aggs = match aggs.as_object() {
+        Some(aggs) => aggs,
+        None => return 0,
+    };
+
+    aggs.values()
+        .map(|agg| {
+            let agg = match agg.as_object() {
+                Some(agg) => agg,
+                None => return 0,
+            };
+
+            let mut kind = None;
+            let mut sub_aggs = &Value::Null;
+            for (key, body) in agg {
+                match key.as_str() {
+                    "aggs" | "aggregations" => sub_aggs = body,
+                    "meta" => {}
+                    _ => kind = Some((key.as_str(), body)),
+                }
+            }
+
+            let (kind, body) = match kind {
+                Some(kind) => kind,
+                None => return 0,
+            };
+
+            match kind {
+                "filter"
+                | "global"
+                | "missing"
+                | "sampler"
+                | "diversified_sampler"
+                | "children"
+                | "parent"
+                | "nested"
+                | "reverse_nested" => estimate_buckets(sub_aggs),
+                _ => match multi_bucket_count(kind, body) {
+                    Some(buckets) => buckets.saturating_mul(1 + estimate_buckets(sub_aggs)),
+
+                    // a metric or pipeline aggregation
+                    None => 0,
+                },
+            }
+        })
+        .fold(0, u64::saturating_add)
+}
+
+/// The number of buckets a multi-bucket aggregation of type `kind` could create, `Some(0)` if
+/// that depends on the data, or `None` if `kind` isn't a multi-bucket aggregation
+fn multi_bucket_count(kind: &str, body: &Value) -> Option<u64> {
+    let count = match kind {
+        "terms" | "significant_terms" => match body["size"].as_u64().unwrap_or(10) {
+            // the request wants every term, however many the field has
+            size if size >= std::i32::MAX as u64 => 0,
+            size => size,
+        },
+
+        // rare_terms has no size and returns however many terms qualify
+        "rare_terms" | "histogram" | "date_histogram" => 0,
+        "significant_text" | "multi_terms" | "composite" => body["size"].as_u64().unwrap_or(10),
+        "geohash_grid" | "geotile_grid" => body["size"].as_u64().unwrap_or(10000),
+        "auto_date_histogram" | "variable_width_histogram" => {
+            body["buckets"].as_u64().unwrap_or(10)
+        }
+        "range" | "date_range" | "ip_range" | "geo_distance" => body["ranges"]
+            .as_array()
+            .map_or(0, |ranges| ranges.len() as u64),
+        "filters" => {
+            let filters = match &body["filters"] {
+                Value::Object(filters) => filters.len(),
+                Value::Array(filters) => filters.len(),
+                _ => 0,
+            } as u64;
+            let other = body["other_bucket"].as_bool().unwrap_or(false)
+                || body.get("other_bucket_key").is_some();
+            filters + if other { 1 } else { 0 }
+        }
+        "adjacency_matrix" => {
+            // one bucket per filter and one per pair of filters
+            let filters = body["filters"]
+                .as_object()
+                .map_or(0, |filters| filters.len()) as u64;
+            filters + filters * filters.saturating_sub(1) / 2
+        }
+        _ => return None,
+    };
+
+    Some(count)
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use crate::elasticsearch::aggregate_search::estimate_buckets;
+    use pgx::*;
+    use serde_json::json;
+
+    #[pg_test]
+    fn test_estimate_buckets() {
+        let aggs = json! {
+            {
+                "by_color": {
+                    "terms": { "field": "color", "size": 10 },
+                    "aggs": {
+                        "by_size": { "terms": { "field": "size", "size": 5 } },
+                        "avg_price": { "avg": { "field": "price" } }
+                    }
+                },
+                "total": { "sum": { "field": "price" } }
+            }
+        };
+
+        // 10 colors, each with 5 sizes.  the metrics don't make buckets
+        let buckets = estimate_buckets(&aggs);
+        assert_eq!(buckets, 10 * (1 + 5));
+    }
+
+    #[pg_test]
+    fn test_estimate_buckets_default_size() {
+        let aggs = json! {
+            {
+                "the_agg": {
+                    "nested": { "path": "items" },
+                    "aggs": {
+                        "the_agg": {
+                            "terms": { "field": "items.sku", "size": std::i32::MAX, "shard_size": std::i32::MAX }
+                        }
+                    }
+                }
+            }
+        };
+
+        // how many terms there are depends on the data, so they aren't counted
+        assert_eq!(estimate_buckets(&aggs), 0);
+    }
+
+    #[pg_test]
+    fn test_estimate_buckets_histogram() {
+        let aggs = json! {
+            {
+                "prices": {
+                    "histogram": { "field": "price", "interval": 10.0 },
+                    "aggs": {
+                        "by_day": { "date_histogram": { "field": "sold", "calendar_interval": "day" } }
+                    }
+                },
+                "ranges": {
+                    "range": { "field": "price", "ranges": [ { "to": 10 }, { "from": 10 } ] }
+                }
+            }
+        };
+
+        // the histograms' buckets depend on the data, so only the 2 ranges are counted
+        assert_eq!(estimate_buckets(&aggs), 2);
+    }
+}
@@ -171,3 +171,97 @@ $$;
 
 "#
 );
+
+/// ```funcname
+/// adjacency_matrix
+/// ```
+#[pg_extern(immutable, parallel_safe)]
+fn adjacency_matrix_by_filters(
+    index: PgRelation,
+    filters: JsonB,
+    query: Option<default!(&str, NULL)>,
+) -> impl std::iter::Iterator<Item = (name!(key, Option<String>), name!(doc_count, i64))> {
+    #[derive(Deserialize, Serialize)]
+    struct BucketEntry {
+        doc_count: i64,
+        key: serde_json::Value,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct AdjacencyMatrixAggData {
+        buckets: Vec<BucketEntry>,
+    }
+
+    let named_filters = filters
+        .0
+        .as_object()
+        .expect("filters must be a json object of names to queries");
+
+    let mut filters_map = HashMap::new();
+    for (name, filter) in named_filters {
+        let filter = filter
+            .as_str()
+            .unwrap_or_else(|| panic!("filter '{}' is not a query string", name));
+
+        filters_map.insert(
+            name.clone(),
+            ZDBQuery::from_str(filter)
+                .prepare(&index, None)
+                .0
+                .take_query_dsl(),
+        );
+    }
+
+    // without a query, the filters are applied to every document
+    let query = ZDBQuery::from_str(query.unwrap_or_default());
+    let (prepared_query, index) = query.prepare(&index, None);
+    let elasticsearch = Elasticsearch::new(&index);
+    let request = elasticsearch.aggregate::<AdjacencyMatrixAggData>(
+        None,
+        true,
+        prepared_query,
+        json! {
+            {
+                "adjacency_matrix": {
+                    "filters": filters_map,
+                }
+            }
+        },
+    );
+
+    let result = request
+        .execute()
+        .expect("failed to execute aggregate search");
+
+    result
+        .buckets
+        .into_iter()
+        .map(|entry| (json_to_string(entry.key), entry.doc_count))
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_adjacency_matrix_by_filters() {
+        Spi::run(
+            "CREATE TABLE test_adjacency_matrix AS SELECT n FROM generate_series(1, 100) n;
+             CREATE INDEX idxtest_adjacency_matrix ON test_adjacency_matrix USING zombodb ((test_adjacency_matrix.*));",
+        );
+
+        // "low" and "mid" overlap on 41 through 50
+        let intersection = Spi::get_one::<i64>(
+            r#"SELECT doc_count FROM zdb.adjacency_matrix('idxtest_adjacency_matrix', '{"low": "n <= 50", "mid": "n > 40 and n <= 60"}') WHERE key = 'low&mid';"#,
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(intersection, 10);
+
+        let scoped = Spi::get_one::<i64>(
+            r#"SELECT doc_count FROM zdb.adjacency_matrix('idxtest_adjacency_matrix', '{"low": "n <= 50", "mid": "n > 40 and n <= 60"}', 'n <= 45') WHERE key = 'low&mid';"#,
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(scoped, 5);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::elasticsearch::Elasticsearch;
 use crate::zdbquery::ZDBQuery;
 use pgx::*;
+use serde_json::json;
 use std::convert::TryInto;
 
 #[pg_extern(immutable, parallel_safe)]
@@ -24,3 +25,110 @@ fn raw_count(index: PgRelation, query: ZDBQuery) -> i64 {
         .try_into()
         .expect("count request overflowed an i64")
 }
+
+/// Counts each of `queries` in a single Elasticsearch `_msearch` request.  `idx` is the 1-based
+/// position of the query in the `queries` array
+#[pg_extern(immutable, parallel_safe)]
+fn count_batch(
+    index: PgRelation,
+    queries: Array<&str>,
+) -> impl std::iter::Iterator<Item = (name!(idx, i32), name!(count, i64))> {
+    let es = Elasticsearch::new(&index);
+    let queries = queries
+        .iter()
+        .map(|query| {
+            let query = query.expect("queries array cannot contain NULL elements");
+            ZDBQuery::from_str(query).prepare(&index, None).0
+        })
+        .collect::<Vec<_>>();
+
+    if queries.is_empty() {
+        return Vec::new().into_iter();
+    }
+
+    es.count_batch(queries)
+        .execute()
+        .unwrap_or_else(|e| panic!("{}", e))
+        .into_iter()
+        .enumerate()
+        .map(|(idx, count)| {
+            (
+                idx as i32 + 1,
+                count.try_into().expect("count request overflowed an i64"),
+            )
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+/// ```funcname
+/// terms_lookup
+/// ```
+#[pg_extern(immutable, parallel_safe)]
+fn terms_lookup_count(
+    index: PgRelation,
+    field: &str,
+    lookup_index: PgRelation,
+    lookup_path: &str,
+    lookup_id: &str,
+) -> i64 {
+    let es = Elasticsearch::new(&index);
+    let lookup_es = Elasticsearch::new(&lookup_index);
+    let query = ZDBQuery::new_with_query_dsl(json! {
+        {
+            "terms": {
+                field: {
+                    "index": lookup_es.alias_name(),
+                    "id": lookup_id,
+                    "path": lookup_path
+                }
+            }
+        }
+    });
+
+    es.count(query.prepare(&index, None).0)
+        .execute()
+        .expect("failed to execute terms lookup count query")
+        .try_into()
+        .expect("count request overflowed an i64")
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_terms_lookup_count() {
+        Spi::run(
+            "CREATE TABLE test_terms_lookup AS SELECT id::bigint FROM generate_series(1, 10) id;
+             CREATE INDEX idxtest_terms_lookup ON test_terms_lookup USING zombodb ((test_terms_lookup.*));
+             CREATE TABLE test_terms_lookup_ids (ids bigint[]);
+             INSERT INTO test_terms_lookup_ids VALUES (ARRAY[1, 3, 5, 42]);
+             CREATE INDEX idxtest_terms_lookup_ids ON test_terms_lookup_ids USING zombodb ((test_terms_lookup_ids.*));",
+        );
+
+        // the lone row in the lookup table is at ctid (0,1), which is _id "1" in Elasticsearch
+        let count = Spi::get_one::<i64>(
+            "SELECT zdb.terms_lookup('idxtest_terms_lookup', 'id', 'idxtest_terms_lookup_ids', 'ids', '1');",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(count, 3);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_count_batch() {
+        Spi::run(
+            "CREATE TABLE test_count_batch AS SELECT id::bigint FROM generate_series(1, 10) id;
+             CREATE INDEX idxtest_count_batch ON test_count_batch USING zombodb ((test_count_batch.*));",
+        );
+
+        let counts = Spi::get_one::<Vec<i64>>(
+            "SELECT array_agg(count ORDER BY idx)
+               FROM zdb.count_batch('idxtest_count_batch', ARRAY['id:[1 TO 5]', 'id:42', '']);",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(counts, vec![5, 0, 10]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::access_method::options::ZDBIndexOptions;
 use crate::elasticsearch::aggregates::date_histogram::pg_catalog::*;
 use crate::elasticsearch::Elasticsearch;
 use crate::zdbquery::ZDBQuery;
@@ -29,7 +30,7 @@ fn date_histogram(
     query: ZDBQuery,
     calendar_interval: Option<default!(CalendarInterval, NULL)>,
     fixed_interval: Option<default!(&str, NULL)>,
-    time_zone: default!(&str, "+00:00"),
+    time_zone: Option<default!(&str, NULL)>,
     format: default!(&str, "yyyy-MM-dd"),
 ) -> impl std::iter::Iterator<
     Item = (
@@ -61,11 +62,12 @@ fn date_histogram(
         buckets: Vec<BucketEntry>,
     }
 
+    let options = ZDBIndexOptions::from_relation(&index);
     let date_histogram = DateHistogram {
         field,
         calendar_interval,
         fixed_interval,
-        time_zone,
+        time_zone: time_zone.unwrap_or(options.aggregation_timezone()),
         format,
     };
 
@@ -97,3 +99,135 @@ fn date_histogram(
         .into_iter()
         .map(|entry| (entry.key_as_string, entry.key, entry.doc_count))
 }
+
+#[pg_extern(immutable, parallel_safe)]
+fn date_histogram_by_interval(
+    index: PgRelation,
+    field: &str,
+    interval: &str,
+    query: Option<default!(&str, NULL)>,
+) -> impl std::iter::Iterator<
+    Item = (
+        name!(bucket, TimestampWithTimeZone),
+        name!(doc_count, i64),
+    ),
+> {
+    #[derive(Deserialize, Serialize)]
+    struct BucketEntry {
+        doc_count: i64,
+        key: i64,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct DateHistogramAggData {
+        buckets: Vec<BucketEntry>,
+    }
+
+    let interval_type = match interval {
+        "minute" | "1m" | "hour" | "1h" | "day" | "1d" | "week" | "1w" | "month" | "1M"
+        | "quarter" | "1q" | "year" | "1y" => "calendar_interval",
+        _ if is_fixed_interval(interval) => "fixed_interval",
+        _ => panic!(
+            "invalid interval: '{}'.  Must be a calendar interval such as 'day' or '1M', or a fixed interval such as '90m'",
+            interval
+        ),
+    };
+
+    let options = ZDBIndexOptions::from_relation(&index);
+    let time_zone = options.aggregation_timezone().to_owned();
+
+    // without a query, the histogram covers every document
+    let query = ZDBQuery::from_str(query.unwrap_or_default());
+    let (prepared_query, index) = query.prepare(&index, Some(field.into()));
+    let elasticsearch = Elasticsearch::new(&index);
+    let request = elasticsearch.aggregate::<DateHistogramAggData>(
+        Some(field.into()),
+        true,
+        prepared_query,
+        json! {
+            {
+                "date_histogram": {
+                    "field": field,
+                    interval_type: interval,
+                    "time_zone": time_zone
+                }
+            }
+        },
+    );
+
+    let result = request
+        .execute()
+        .expect("failed to execute aggregate search");
+
+    result
+        .buckets
+        .into_iter()
+        .map(|entry| (epoch_millis_to_timestamptz(entry.key), entry.doc_count))
+}
+
+fn is_fixed_interval(interval: &str) -> bool {
+    let units = ["ms", "s", "m", "h", "d"]
+        .iter()
+        .find(|unit| interval.ends_with(*unit));
+
+    match units {
+        Some(unit) => {
+            let amount = &interval[..interval.len() - unit.len()];
+            !amount.is_empty() && amount.chars().all(|c| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+fn epoch_millis_to_timestamptz(millis: i64) -> TimestampWithTimeZone {
+    // Postgres timestamps are microseconds since 2000-01-01
+    const POSTGRES_EPOCH_MILLIS: i64 = 946_684_800_000;
+    let micros = (millis - POSTGRES_EPOCH_MILLIS) * 1000;
+
+    unsafe {
+        TimestampWithTimeZone::from_datum(micros as pg_sys::Datum, false, pg_sys::TIMESTAMPTZOID)
+    }
+    .expect("failed to convert bucket key to a timestamp with time zone")
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_date_histogram_by_interval() {
+        Spi::run(
+            "CREATE TABLE test_date_histogram (ts timestamptz);
+             INSERT INTO test_date_histogram VALUES
+                ('2021-01-01 08:00:00+00'), ('2021-01-01 20:00:00+00'),
+                ('2021-01-02 01:00:00+00'), ('2021-01-02 12:00:00+00'), ('2021-01-02 23:00:00+00'),
+                ('2021-01-03 12:00:00+00');
+             CREATE INDEX idxtest_date_histogram ON test_date_histogram USING zombodb ((test_date_histogram.*));",
+        );
+
+        let counts = Spi::get_one::<Vec<i64>>(
+            "SELECT array_agg(doc_count ORDER BY bucket) FROM zdb.date_histogram_by_interval('idxtest_date_histogram', 'ts', 'day');",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(counts, vec![2, 3, 1]);
+
+        let first = Spi::get_one::<bool>(
+            "SELECT min(bucket) = '2021-01-01 00:00:00+00' FROM zdb.date_histogram_by_interval('idxtest_date_histogram', 'ts', '1d');",
+        )
+        .expect("failed to get SPI result");
+        assert!(first);
+    }
+
+    #[pg_test(
+        error = "invalid interval: 'fortnight'.  Must be a calendar interval such as 'day' or '1M', or a fixed interval such as '90m'"
+    )]
+    #[initialize(es = true)]
+    fn test_date_histogram_invalid_interval() {
+        Spi::run(
+            "CREATE TABLE test_date_histogram_invalid (ts timestamptz);
+             CREATE INDEX idxtest_date_histogram_invalid ON test_date_histogram_invalid USING zombodb ((test_date_histogram_invalid.*));
+             SELECT * FROM zdb.date_histogram_by_interval('idxtest_date_histogram_invalid', 'ts', 'fortnight');",
+        );
+    }
+}
@@ -52,3 +52,92 @@ fn filters(
         .into_iter()
         .map(|entry| (entry.0, entry.1.doc_count))
 }
+
+#[pg_extern(immutable, parallel_safe)]
+fn filters_agg(
+    index: PgRelation,
+    filters: JsonB,
+    query: Option<default!(&str, NULL)>,
+) -> impl std::iter::Iterator<Item = (name!(name, String), name!(doc_count, i64))> {
+    #[derive(Deserialize, Serialize)]
+    struct FilterAggData {
+        buckets: HashMap<String, BucketEntry>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct BucketEntry {
+        doc_count: i64,
+    }
+
+    let named_filters = filters
+        .0
+        .as_object()
+        .expect("filters must be a json object of names to queries");
+
+    let mut filters_map = HashMap::new();
+    for (name, filter) in named_filters {
+        let filter = filter
+            .as_str()
+            .unwrap_or_else(|| panic!("filter '{}' is not a query string", name));
+
+        filters_map.insert(
+            name.clone(),
+            ZDBQuery::from_str(filter)
+                .prepare(&index, None)
+                .0
+                .take_query_dsl(),
+        );
+    }
+
+    // without a query, the filters are applied to every document
+    let query = ZDBQuery::from_str(query.unwrap_or_default());
+    let (prepared_query, index) = query.prepare(&index, None);
+    let elasticsearch = Elasticsearch::new(&index);
+    let request = elasticsearch.aggregate::<FilterAggData>(
+        None,
+        true,
+        prepared_query,
+        json! {
+            {
+                "filters": {
+                    "filters": filters_map,
+                }
+            }
+        },
+    );
+
+    let result = request
+        .execute()
+        .expect("failed to execute aggregate search");
+
+    result
+        .buckets
+        .into_iter()
+        .map(|(name, entry)| (name, entry.doc_count))
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_filters_agg() {
+        Spi::run(
+            "CREATE TABLE test_filters_agg AS SELECT n FROM generate_series(1, 100) n;
+             CREATE INDEX idxtest_filters_agg ON test_filters_agg USING zombodb ((test_filters_agg.*));",
+        );
+
+        let counts = Spi::get_one::<Vec<i64>>(
+            r#"SELECT array_agg(doc_count ORDER BY name) FROM zdb.filters_agg('idxtest_filters_agg', '{"small": "n <= 10", "large": "n > 75"}');"#,
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(counts, vec![25, 10]);
+
+        let scoped = Spi::get_one::<Vec<i64>>(
+            r#"SELECT array_agg(doc_count ORDER BY name) FROM zdb.filters_agg('idxtest_filters_agg', '{"small": "n <= 10", "large": "n > 75"}', 'n <= 80');"#,
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(scoped, vec![5, 10]);
+    }
+}
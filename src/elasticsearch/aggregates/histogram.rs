@@ -49,3 +49,76 @@ fn histogram(
         .into_iter()
         .map(|entry| (entry.key, entry.doc_count))
 }
+
+/// ```funcname
+/// histogram
+/// ```
+#[pg_extern(immutable, parallel_safe)]
+fn histogram_by_interval(
+    index: PgRelation,
+    field: &str,
+    interval: f64,
+    query: Option<default!(&str, NULL)>,
+) -> impl std::iter::Iterator<Item = (name!(bucket, f64), name!(doc_count, i64))> {
+    #[derive(Deserialize, Serialize)]
+    struct BucketEntry {
+        doc_count: i64,
+        key: f64,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct HistogramAggData {
+        buckets: Vec<BucketEntry>,
+    }
+
+    // without a query, the histogram covers every document
+    let query = ZDBQuery::from_str(query.unwrap_or_default());
+    let (prepared_query, index) = query.prepare(&index, Some(field.into()));
+    let elasticsearch = Elasticsearch::new(&index);
+    let request = elasticsearch.aggregate::<HistogramAggData>(
+        Some(field.into()),
+        true,
+        prepared_query,
+        json! {
+            {
+                "histogram": {
+                    "field": field,
+                    "interval": interval
+                }
+            }
+        },
+    );
+
+    let result = request
+        .execute()
+        .expect("failed to execute aggregate search");
+
+    result
+        .buckets
+        .into_iter()
+        .map(|entry| (entry.key, entry.doc_count))
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_histogram_by_interval() {
+        Spi::run("CREATE TABLE test_histogram AS SELECT n FROM generate_series(0, 24) n;");
+        Spi::run("CREATE INDEX idxtest_histogram ON test_histogram USING zombodb ((test_histogram.*));");
+
+        let all = Spi::get_one::<Vec<i64>>(
+            "SELECT array_agg(doc_count ORDER BY bucket) FROM zdb.histogram('idxtest_histogram', 'n', 10.0);",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(all, vec![10, 10, 5]);
+
+        let scoped = Spi::get_one::<Vec<i64>>(
+            "SELECT array_agg(doc_count ORDER BY bucket) FROM zdb.histogram('idxtest_histogram', 'n', 10.0, 'n < 15');",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(scoped, vec![10, 5]);
+    }
+}
@@ -91,6 +91,47 @@ fn cardinality(index: PgRelation, field: &str, query: ZDBQuery) -> Numeric {
     result.value
 }
 
+/// ```funcname
+/// cardinality
+/// ```
+///
+/// Untyped query literals resolve to this form rather than the one above, so it returns the same
+/// `numeric` to keep existing callers working
+#[pg_extern(immutable, parallel_safe)]
+fn cardinality_with_optional_query(
+    index: PgRelation,
+    field: &str,
+    query: Option<default!(&str, NULL)>,
+) -> Numeric {
+    #[derive(Deserialize, Serialize)]
+    struct CardinalityAggData {
+        value: Numeric,
+    }
+
+    // without a query, the distinct count covers every document
+    let query = ZDBQuery::from_str(query.unwrap_or_default());
+    let (prepared_query, index) = query.prepare(&index, Some(field.into()));
+    let elasticsearch = Elasticsearch::new(&index);
+    let request = elasticsearch.aggregate::<CardinalityAggData>(
+        Some(field.into()),
+        true,
+        prepared_query,
+        json! {
+            {
+                "cardinality": {
+                    "field" : field
+                }
+            }
+        },
+    );
+
+    let result = request
+        .execute()
+        .expect("failed to execute aggregate search");
+
+    result.value
+}
+
 #[pg_extern(immutable, parallel_safe)]
 fn max(index: PgRelation, field: &str, query: ZDBQuery) -> Numeric {
     #[derive(Deserialize, Serialize)]
@@ -206,3 +247,36 @@ fn value_count(index: PgRelation, field: &str, query: ZDBQuery) -> Numeric {
 
     result.value
 }
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_cardinality_with_optional_query() {
+        Spi::run(
+            "CREATE TABLE test_cardinality AS SELECT n, n % 100 AS bucket FROM generate_series(1, 1000) n;
+             CREATE INDEX idxtest_cardinality ON test_cardinality USING zombodb ((test_cardinality.*));",
+        );
+
+        // cardinality is approximate, but HyperLogLog++ is within a few percent at this size
+        let all =
+            Spi::get_one::<i64>("SELECT zdb.cardinality('idxtest_cardinality', 'bucket')::bigint;")
+                .expect("failed to get SPI result");
+        assert!((all - 100).abs() <= 5, "cardinality was {}", all);
+
+        let scoped = Spi::get_one::<i64>(
+            "SELECT zdb.cardinality('idxtest_cardinality', 'bucket', 'bucket < 10')::bigint;",
+        )
+        .expect("failed to get SPI result");
+        assert!((scoped - 10).abs() <= 1, "cardinality was {}", scoped);
+
+        // an untyped query literal picks this form, which must still return numeric
+        let return_type = Spi::get_one::<&str>(
+            "SELECT pg_typeof(zdb.cardinality('idxtest_cardinality', 'bucket', 'bucket < 10'))::text;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(return_type, "numeric");
+    }
+}
@@ -14,6 +14,7 @@ mod percentile_ranks;
 mod percentiles;
 mod query;
 mod range;
+mod sampled_terms;
 mod significant_terms;
 mod significant_terms_two_level;
 mod significant_text;
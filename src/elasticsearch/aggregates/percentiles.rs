@@ -58,3 +58,77 @@ fn percentiles(
         .into_iter()
         .map(|entry| (entry.key, entry.value))
 }
+
+#[pg_extern(immutable, parallel_safe)]
+fn percentiles_by_percents(
+    index: PgRelation,
+    field: &str,
+    percents: Vec<f64>,
+    query: Option<default!(&str, NULL)>,
+) -> impl std::iter::Iterator<Item = (name!(percent, f64), name!(value, Option<f64>))> {
+    #[derive(Deserialize, Serialize)]
+    struct Entry {
+        key: f64,
+        value: Option<f64>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct PercentilesAggData {
+        values: Vec<Entry>,
+    }
+
+    // without a query, the percentiles cover every document
+    let query = ZDBQuery::from_str(query.unwrap_or_default());
+    let (prepared_query, index) = query.prepare(&index, Some(field.into()));
+    let elasticsearch = Elasticsearch::new(&index);
+    let request = elasticsearch.aggregate::<PercentilesAggData>(
+        Some(field.into()),
+        true,
+        prepared_query,
+        json! {
+            {
+                "percentiles": {
+                    "field": field,
+                    "percents": percents,
+                    "keyed": false
+                }
+            }
+        },
+    );
+
+    let result = request
+        .execute()
+        .expect("failed to execute aggregate search");
+
+    result
+        .values
+        .into_iter()
+        .map(|entry| (entry.key, entry.value))
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_percentiles_by_percents() {
+        Spi::run(
+            "CREATE TABLE test_percentiles AS SELECT n::float8 FROM generate_series(1, 1001) n;
+             CREATE INDEX idxtest_percentiles ON test_percentiles USING zombodb ((test_percentiles.*));",
+        );
+
+        // percentiles are approximate, but TDigest is very accurate near the median
+        let median = Spi::get_one::<f64>(
+            "SELECT value FROM zdb.percentiles_by_percents('idxtest_percentiles', 'n', ARRAY[50.0]) WHERE percent = 50.0;",
+        )
+        .expect("failed to get SPI result");
+        assert!((median - 501.0).abs() <= 5.0, "median was {}", median);
+
+        let scoped = Spi::get_one::<f64>(
+            "SELECT value FROM zdb.percentiles_by_percents('idxtest_percentiles', 'n', ARRAY[50.0], 'n <= 101') WHERE percent = 50.0;",
+        )
+        .expect("failed to get SPI result");
+        assert!((scoped - 51.0).abs() <= 1.0, "median was {}", scoped);
+    }
+}
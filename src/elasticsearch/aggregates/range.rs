@@ -61,3 +61,79 @@ fn range(
         )
     })
 }
+
+#[pg_extern(immutable, parallel_safe)]
+fn range_agg(
+    index: PgRelation,
+    field: &str,
+    ranges: JsonB,
+    query: Option<default!(&str, NULL)>,
+) -> impl std::iter::Iterator<Item = (name!(key, String), name!(doc_count, i64))> {
+    #[derive(Deserialize, Serialize)]
+    struct RangesAggData {
+        buckets: Vec<BucketEntry>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct BucketEntry {
+        key: serde_json::Value,
+        doc_count: i64,
+    }
+
+    if !ranges.0.is_array() {
+        panic!("ranges must be a json array of {\"from\", \"to\"} objects")
+    }
+
+    // without a query, the ranges cover every document
+    let query = ZDBQuery::from_str(query.unwrap_or_default());
+    let (prepared_query, index) = query.prepare(&index, Some(field.into()));
+    let elasticsearch = Elasticsearch::new(&index);
+    let request = elasticsearch.aggregate::<RangesAggData>(
+        Some(field.into()),
+        true,
+        prepared_query,
+        json! {
+            {
+                "range": {
+                    "field": field,
+                    "ranges": ranges.0
+                }
+            }
+        },
+    );
+
+    let result = request
+        .execute()
+        .expect("failed to execute aggregate search");
+
+    result
+        .buckets
+        .into_iter()
+        .map(|entry| (json_to_string(entry.key).unwrap(), entry.doc_count))
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_range_agg() {
+        Spi::run(
+            "CREATE TABLE test_range_agg AS SELECT n AS price FROM generate_series(1, 100) n;
+             CREATE INDEX idxtest_range_agg ON test_range_agg USING zombodb ((test_range_agg.*));",
+        );
+
+        let counts = Spi::get_one::<Vec<i64>>(
+            r#"SELECT array_agg(doc_count ORDER BY key) FROM zdb.range_agg('idxtest_range_agg', 'price', '[{"to": 25}, {"from": 25, "to": 75}]');"#,
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(counts, vec![24, 50]);
+
+        let scoped = Spi::get_one::<Vec<i64>>(
+            r#"SELECT array_agg(doc_count ORDER BY key) FROM zdb.range_agg('idxtest_range_agg', 'price', '[{"to": 25}, {"from": 25, "to": 75}]', 'price <= 50');"#,
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(scoped, vec![24, 26]);
+    }
+}
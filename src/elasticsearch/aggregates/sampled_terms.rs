@@ -0,0 +1,101 @@
+use crate::elasticsearch::Elasticsearch;
+use crate::utils::json_to_string;
+use crate::zdbquery::ZDBQuery;
+use pgx::*;
+use serde::*;
+use serde_json::*;
+
+#[pg_extern(immutable, parallel_safe)]
+fn sampled_terms(
+    index: PgRelation,
+    field: &str,
+    query: &str,
+    shard_size: default!(i32, 1000),
+    size: default!(i32, 10),
+) -> impl std::iter::Iterator<Item = (name!(term, Option<String>), name!(doc_count, i64))> {
+    #[derive(Deserialize, Serialize)]
+    struct BucketEntry {
+        doc_count: i64,
+        key: serde_json::Value,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct TermsAggData {
+        buckets: Vec<BucketEntry>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct SampledTerms {
+        sampled_terms: TermsAggData,
+    }
+
+    if shard_size < 1 {
+        panic!("shard_size must be at least 1");
+    } else if size < 1 {
+        panic!("size must be at least 1");
+    }
+
+    let (prepared_query, index) = ZDBQuery::from_str(query).prepare(&index, Some(field.into()));
+    let elasticsearch = Elasticsearch::new(&index);
+    let request = elasticsearch.aggregate::<SampledTerms>(
+        Some(field.into()),
+        true,
+        prepared_query,
+        json! {
+            {
+                "sampler": {
+                    "shard_size": shard_size
+                },
+                "aggregations": {
+                    "sampled_terms": {
+                        "terms": {
+                            "field": field,
+                            "size": size
+                        }
+                    }
+                }
+            }
+        },
+    );
+
+    let result = request
+        .execute()
+        .expect("failed to execute aggregate search");
+
+    result
+        .sampled_terms
+        .buckets
+        .into_iter()
+        .map(|entry| (json_to_string(entry.key), entry.doc_count))
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_sampled_terms() {
+        Spi::run(
+            "CREATE TABLE test_sampled_terms AS
+                SELECT 'tag' || (n % 50) AS tag FROM generate_series(1, 10000) n;
+             CREATE INDEX idxtest_sampled_terms ON test_sampled_terms USING zombodb ((test_sampled_terms.*));",
+        );
+
+        let count = Spi::get_one::<i64>(
+            "SELECT count(*) FROM zdb.sampled_terms('idxtest_sampled_terms', 'tag', '', 100, 5);",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(count, 5);
+    }
+
+    #[pg_test(error = "size must be at least 1")]
+    #[initialize(es = true)]
+    fn test_sampled_terms_invalid_size() {
+        Spi::run(
+            "CREATE TABLE test_sampled_terms(tag text);
+             CREATE INDEX idxtest_sampled_terms ON test_sampled_terms USING zombodb ((test_sampled_terms.*));
+             SELECT * FROM zdb.sampled_terms('idxtest_sampled_terms', 'tag', '', 100, 0);",
+        );
+    }
+}
@@ -66,3 +66,82 @@ fn significant_terms(
         )
     })
 }
+
+#[pg_extern(immutable, parallel_safe)]
+fn significant_terms_by_query(
+    index: PgRelation,
+    field: &str,
+    query: &str,
+    size: default!(i32, 10),
+) -> impl std::iter::Iterator<
+    Item = (
+        name!(term, Option<String>),
+        name!(score, f64),
+        name!(doc_count, i64),
+    ),
+> {
+    #[derive(Deserialize, Serialize)]
+    struct BucketEntry {
+        doc_count: i64,
+        key: serde_json::Value,
+        score: f64,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct SignificantTermsAggData {
+        buckets: Vec<BucketEntry>,
+    }
+
+    if size < 1 {
+        panic!("size must be at least 1");
+    }
+
+    let (prepared_query, index) = ZDBQuery::from_str(query).prepare(&index, Some(field.into()));
+    let elasticsearch = Elasticsearch::new(&index);
+    let request = elasticsearch.aggregate::<SignificantTermsAggData>(
+        Some(field.into()),
+        true,
+        prepared_query,
+        json! {
+            {
+                "significant_terms": {
+                    "field": field,
+                    "size": size
+                }
+            }
+        },
+    );
+
+    let result = request
+        .execute()
+        .expect("failed to execute aggregate search");
+
+    result
+        .buckets
+        .into_iter()
+        .map(|entry| (json_to_string(entry.key), entry.score, entry.doc_count))
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_significant_terms_by_query() {
+        // "rare" is in half of the 'x' rows, but almost none of the others
+        Spi::run(
+            "CREATE TABLE test_significant_terms AS
+                SELECT CASE WHEN n <= 20 THEN 'x' ELSE 'y' END AS category,
+                       CASE WHEN n <= 10 OR n = 200 THEN 'rare' ELSE 'common' END AS tag
+                  FROM generate_series(1, 200) n;
+             CREATE INDEX idxtest_significant_terms ON test_significant_terms USING zombodb ((test_significant_terms.*));",
+        );
+
+        let term = Spi::get_one::<&str>(
+            "SELECT term FROM zdb.significant_terms_by_query('idxtest_significant_terms', 'tag', 'category:x') ORDER BY score DESC LIMIT 1;",
+        )
+        .expect("no significant terms");
+        assert_eq!(term, "rare");
+    }
+}
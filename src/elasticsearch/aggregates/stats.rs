@@ -48,3 +48,69 @@ fn stats(
 
     vec![(result.count, result.min, result.max, result.avg, result.sum)].into_iter()
 }
+
+#[pg_extern(immutable, parallel_safe)]
+fn stats_aggregation(index: PgRelation, field: &str, query: Option<default!(&str, NULL)>) -> JsonB {
+    // without a query, the stats cover every document
+    let query = ZDBQuery::from_str(query.unwrap_or_default());
+    let (prepared_query, index) = query.prepare(&index, Some(field.into()));
+    let elasticsearch = Elasticsearch::new(&index);
+    let request = elasticsearch.aggregate::<Value>(
+        Some(field.into()),
+        true,
+        prepared_query,
+        json! {
+            {
+                "stats": {
+                    "field" : field
+                }
+            }
+        },
+    );
+
+    let result = request
+        .execute()
+        .expect("failed to execute aggregate search");
+
+    JsonB(json! {
+        {
+            "count": result["count"],
+            "min": result["min"],
+            "max": result["max"],
+            "avg": result["avg"],
+            "sum": result["sum"]
+        }
+    })
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_stats_aggregation() {
+        Spi::run(
+            "CREATE TABLE test_stats_aggregation AS SELECT n FROM generate_series(1, 100) n;
+             CREATE INDEX idxtest_stats_aggregation ON test_stats_aggregation USING zombodb ((test_stats_aggregation.*));",
+        );
+
+        let stats = Spi::get_one::<JsonB>(
+            "SELECT zdb.stats_aggregation('idxtest_stats_aggregation', 'n');",
+        )
+        .expect("failed to get SPI result")
+        .0;
+        assert_eq!(stats["count"], 100);
+        assert_eq!(stats["sum"], 5050.0);
+        assert_eq!(stats["min"], 1.0);
+        assert_eq!(stats["max"], 100.0);
+
+        let scoped = Spi::get_one::<JsonB>(
+            "SELECT zdb.stats_aggregation('idxtest_stats_aggregation', 'n', 'n <= 10');",
+        )
+        .expect("failed to get SPI result")
+        .0;
+        assert_eq!(scoped["count"], 10);
+        assert_eq!(scoped["sum"], 55.0);
+    }
+}
@@ -153,6 +153,39 @@ pub(crate) fn analyze_with_field(
     elasticsearch_request_return(elasticsearch.analyze_with_field(field, text))
 }
 
+#[pg_extern(immutable, parallel_safe)]
+fn tokenize(
+    index: PgRelation,
+    field: &str,
+    text: &str,
+) -> impl std::iter::Iterator<
+    Item = (
+        name!(token, String),
+        name!(start_offset, i32),
+        name!(end_offset, i32),
+        name!(position, i32),
+        name!(token_type, String),
+    ),
+> {
+    let elasticsearch = Elasticsearch::new(&index);
+
+    elasticsearch
+        .analyze_with_field(field, text)
+        .execute()
+        .expect("failed to execute Analyze search")
+        .tokens
+        .into_iter()
+        .map(|entry| {
+            (
+                entry.token,
+                entry.start_offset as i32,
+                entry.end_offset as i32,
+                entry.position,
+                entry.type_,
+            )
+        })
+}
+
 #[pg_extern(immutable, parallel_safe)]
 fn analyze_custom(
     index: PgRelation,
@@ -307,6 +340,35 @@ mod tests {
         });
     }
 
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_tokenize() {
+        Spi::run("CREATE TABLE test_tokenize AS SELECT 'hello world'::text AS title;");
+        Spi::run("CREATE INDEX idxtest_tokenize ON test_tokenize USING zombodb ((test_tokenize.*));");
+        Spi::connect(|client| {
+            let mut table = client.select(
+                "SELECT * FROM zdb.tokenize('idxtest_tokenize', 'title', 'Hello World');",
+                None,
+                None,
+            );
+
+            let expect = vec![("hello", 0, 5, 0), ("world", 6, 11, 1)];
+            let mut i = 0;
+            while table.next().is_some() {
+                let token = table.get_datum::<&str>(1).unwrap();
+                let start_offset = table.get_datum::<i32>(2).unwrap();
+                let end_offset = table.get_datum::<i32>(3).unwrap();
+                let position = table.get_datum::<i32>(4).unwrap();
+
+                assert_eq!(expect[i], (token, start_offset, end_offset, position));
+                i += 1;
+            }
+            assert_eq!(expect.len(), i);
+
+            Ok(Some(()))
+        });
+    }
+
     fn test_table(mut table: SpiTupleTable, expect: Vec<(&str, &str, i32, i64, i64)>) {
         let mut i = 0;
         while let Some(_) = table.next() {
@@ -1,5 +1,5 @@
-use crate::access_method::options::RefreshInterval;
-use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use crate::access_method::options::{OpType, RefreshInterval};
+use crate::elasticsearch::{generate_request_id, stats, Elasticsearch, ElasticsearchError};
 use crate::executor_manager::get_executor_manager;
 use crate::gucs::ZDB_LOG_LEVEL;
 use crate::json::builder::JsonBuilder;
@@ -70,8 +70,14 @@ pub struct ElasticsearchBulkRequest {
     concurrency: usize,
     batch_size: usize,
     error_receiver: crossbeam_channel::Receiver<BulkRequestError>,
+    indexed_docs: Option<usize>,
+    inserts_since_count: usize,
 }
 
+/// How many inserts we'll queue before asking Elasticsearch for the index's doc count again
+/// when enforcing `max_docs`
+const MAX_DOCS_CHECK_INTERVAL: usize = 10_000;
+
 impl Clone for ElasticsearchBulkRequest {
     fn clone(&self) -> Self {
         ElasticsearchBulkRequest::new(
@@ -107,6 +113,8 @@ impl ElasticsearchBulkRequest {
             concurrency,
             batch_size,
             error_receiver: erx,
+            indexed_docs: None,
+            inserts_since_count: 0,
         }
     }
 
@@ -207,6 +215,7 @@ impl ElasticsearchBulkRequest {
         builder: JsonBuilder<'static>,
     ) -> Result<(), crossbeam_channel::SendError<BulkRequestCommand>> {
         self.handler.check_for_error();
+        self.check_max_docs();
 
         let prior_update = self.handler.prior_update.take();
         self.handler.queue_command(BulkRequestCommand::Insert {
@@ -220,6 +229,55 @@ impl ElasticsearchBulkRequest {
         })
     }
 
+    /// Raises an error if inserting one more document would put the index over its `max_docs`
+    /// limit.  The index's doc count is only retrieved from Elasticsearch every
+    /// `MAX_DOCS_CHECK_INTERVAL` inserts, and we count our own inserts in between
+    fn check_max_docs(&mut self) {
+        let max_docs = self.elasticsearch.options.max_docs();
+        if max_docs <= 0 {
+            // unlimited
+            return;
+        }
+
+        if self.indexed_docs.is_none() || self.inserts_since_count >= MAX_DOCS_CHECK_INTERVAL {
+            let indexed_docs = ElasticsearchBulkRequest::count_documents(&self.elasticsearch)
+                .unwrap_or_else(|e| panic!("failed to count documents for max_docs: {}", e));
+            self.indexed_docs = Some(indexed_docs);
+            self.inserts_since_count = 0;
+        }
+
+        if self.indexed_docs.unwrap() + self.inserts_since_count >= max_docs as usize {
+            panic!(
+                "{} has reached its max_docs limit of {}",
+                self.elasticsearch.index_relation().name(),
+                max_docs
+            );
+        }
+        self.inserts_since_count += 1;
+    }
+
+    fn count_documents(elasticsearch: &Elasticsearch) -> Result<usize, ElasticsearchError> {
+        // only count the documents that represent rows
+        Elasticsearch::execute_json_request(
+            Elasticsearch::client().post(&format!("{}/_count", elasticsearch.base_url())),
+            Some(json! {
+                {
+                    "query": { "exists": { "field": "zdb_ctid" } }
+                }
+            }),
+            |body| {
+                #[derive(Deserialize)]
+                struct Count {
+                    count: usize,
+                }
+
+                let count: Count =
+                    serde_json::from_reader(body).expect("failed to deserialize count response");
+                Ok(count.count)
+            },
+        )
+    }
+
     pub fn update(
         &mut self,
         ctid: pg_sys::ItemPointerData,
@@ -357,6 +415,9 @@ pub(crate) struct Handler {
     error_sender: crossbeam_channel::Sender<BulkRequestError>,
     error_receiver: crossbeam_channel::Receiver<BulkRequestError>,
     current_xid: Option<pg_sys::TransactionId>,
+    trace_requests: bool,
+    request_id_sender: crossbeam_channel::Sender<String>,
+    request_id_receiver: crossbeam_channel::Receiver<String>,
 }
 
 struct BulkReceiver<'a> {
@@ -368,6 +429,10 @@ struct BulkReceiver<'a> {
     docs_out: usize,
     buffer: Vec<u8>,
     batch_size: usize,
+    store_ctid_field: bool,
+    op_type: OpType,
+    action_index: Option<String>,
+    route_by_id: bool,
 }
 
 impl<'a> std::io::Read for BulkReceiver<'a> {
@@ -410,6 +475,27 @@ impl<'a> std::io::Read for BulkReceiver<'a> {
     }
 }
 
+/// The `_bulk` URL already names our index, so action lines only repeat it as `_index` when
+/// the `minimize_bulk_metadata` index option is off.  Partitioned indexes require routing, so
+/// when `route_by_id` is set each document is routed by its own `_id`
+fn action_metadata(action_index: &Option<String>, route_by_id: bool, mut metadata: Value) -> Value {
+    if let Some(index_name) = action_index.as_ref() {
+        metadata["_index"] = json!(index_name);
+    }
+    if route_by_id {
+        metadata["routing"] = json!(routing_value(&metadata["_id"]));
+    }
+    metadata
+}
+
+/// The routing value for a document `_id`, which is either a ctid or "zdb_aborted_xids"
+fn routing_value(id: &Value) -> String {
+    match id {
+        Value::String(id) => id.clone(),
+        id => id.to_string(),
+    }
+}
+
 impl<'a> BulkReceiver<'a> {
     fn serialize_command(&mut self, command: BulkRequestCommand<'a>) {
         self.docs_out += 1;
@@ -437,13 +523,20 @@ impl<'a> BulkReceiver<'a> {
                 serde_json::to_writer(
                     &mut self.buffer,
                     &json! {
-                        {"index": {"_id": ctid } }
+                        {
+                            self.op_type.as_str():
+                                action_metadata(&self.action_index, self.route_by_id, json!({ "_id": ctid }))
+                        }
                     },
                 )
                 .expect("failed to serialize index line");
                 self.buffer.push(b'\n');
 
                 doc.add_u64("zdb_ctid", ctid);
+                if self.store_ctid_field {
+                    // in the same "(block,offset)" form as Postgres' ctid
+                    doc.add_string("_zdb_ctid", format!("({},{})", ctid >> 32, ctid & 0xffff));
+                }
                 doc.add_u32("zdb_cmin", cmin);
                 if cmax as pg_sys::CommandId != pg_sys::InvalidCommandId {
                     doc.add_u32("zdb_cmax", cmax);
@@ -462,10 +555,12 @@ impl<'a> BulkReceiver<'a> {
                     &mut self.buffer,
                     &json! {
                         {
-                            "update": {
-                                "_id": ctid,
-                                "retry_on_conflict": 1
-                            }
+                            "update": action_metadata(&self.action_index, self.route_by_id, json! {
+                                {
+                                    "_id": ctid,
+                                    "retry_on_conflict": 1
+                                }
+                            })
                         }
                     },
                 )
@@ -495,10 +590,12 @@ impl<'a> BulkReceiver<'a> {
                     &mut self.buffer,
                     &json! {
                         {
-                            "update": {
-                                "_id": "zdb_aborted_xids",
-                                "retry_on_conflict": 128
-                            }
+                            "update": action_metadata(&self.action_index, self.route_by_id, json! {
+                                {
+                                    "_id": "zdb_aborted_xids",
+                                    "retry_on_conflict": 128
+                                }
+                            })
                         }
                     },
                 )
@@ -528,10 +625,12 @@ impl<'a> BulkReceiver<'a> {
                     &mut self.buffer,
                     &json! {
                         {
-                            "update": {
-                                "_id": "zdb_aborted_xids",
-                                "retry_on_conflict": 128
-                            }
+                            "update": action_metadata(&self.action_index, self.route_by_id, json! {
+                                {
+                                    "_id": "zdb_aborted_xids",
+                                    "retry_on_conflict": 128
+                                }
+                            })
                         }
                     },
                 )
@@ -558,7 +657,7 @@ impl<'a> BulkReceiver<'a> {
                     &mut self.buffer,
                     &json! {
                         {
-                            "update": { "_id": ctid }
+                            "update": action_metadata(&self.action_index, self.route_by_id, json!({ "_id": ctid }))
                         }
                     },
                 )
@@ -582,7 +681,7 @@ impl<'a> BulkReceiver<'a> {
                     &mut self.buffer,
                     &json! {
                         {
-                            "update": { "_id": ctid }
+                            "update": action_metadata(&self.action_index, self.route_by_id, json!({ "_id": ctid }))
                         }
                     },
                 )
@@ -606,7 +705,11 @@ impl<'a> BulkReceiver<'a> {
                     &mut self.buffer,
                     &json! {
                         {
-                            "update": { "_id": ctid, "retry_on_conflict": 0 }
+                            "update": action_metadata(
+                                &self.action_index,
+                                self.route_by_id,
+                                json!({ "_id": ctid, "retry_on_conflict": 0 })
+                            )
                         }
                     },
                 )
@@ -630,7 +733,11 @@ impl<'a> BulkReceiver<'a> {
                     &mut self.buffer,
                     &json! {
                         {
-                            "update": { "_id": "zdb_aborted_xids", "retry_on_conflict": 128 }
+                            "update": action_metadata(
+                                &self.action_index,
+                                self.route_by_id,
+                                json!({ "_id": "zdb_aborted_xids", "retry_on_conflict": 128 })
+                            )
                         }
                     },
                 )
@@ -666,6 +773,7 @@ impl Handler {
         error_receiver: &crossbeam_channel::Receiver<BulkRequestError>,
     ) -> Self {
         let (tx, rx) = crossbeam_channel::bounded(10 * concurrency);
+        let (request_id_tx, request_id_rx) = crossbeam_channel::unbounded();
 
         Handler {
             terminated: Arc::new(AtomicBool::new(false)),
@@ -676,7 +784,6 @@ impl Handler {
             total_docs: 0,
             active_threads: Arc::new(AtomicUsize::new(0)),
             successful_requests: Arc::new(AtomicUsize::new(0)),
-            elasticsearch,
             batch_size,
             concurrency,
             bulk_sender: Some(tx),
@@ -684,6 +791,10 @@ impl Handler {
             error_sender,
             error_receiver: error_receiver.clone(),
             current_xid: None,
+            trace_requests: elasticsearch.options.trace_requests(),
+            request_id_sender: request_id_tx,
+            request_id_receiver: request_id_rx,
+            elasticsearch,
         }
     }
 
@@ -799,6 +910,17 @@ impl Handler {
         let batch_size = self.batch_size;
         let active_threads = self.active_threads.clone();
         let successful_requests = self.successful_requests.clone();
+        let trace_requests = self.trace_requests;
+        let request_ids = self.request_id_sender.clone();
+        let request_timeout = self.elasticsearch.options.request_timeout();
+        let store_ctid_field = self.elasticsearch.options.store_ctid_field();
+        let op_type = self.elasticsearch.options.op_type();
+        let action_index = if self.elasticsearch.options.minimize_bulk_metadata() {
+            None
+        } else {
+            Some(self.elasticsearch.index_name().to_owned())
+        };
+        let route_by_id = self.elasticsearch.options.routing_partition_size() > 1;
 
         self.active_threads.fetch_add(1, Ordering::SeqCst);
         std::thread::spawn(move || {
@@ -838,16 +960,21 @@ impl Handler {
                         bytes_out: 0,
                         docs_out: 0,
                         buffer: Vec::new(),
+                        store_ctid_field,
+                        op_type,
+                        action_index: action_index.clone(),
+                        route_by_id,
                     };
 
-                    let url = format!(
-                        "{}/_bulk?format=cbor&filter_path={}",
-                        base_url, BULK_FILTER_PATH
-                    );
+                    let request = Handler::bulk_request(&base_url, trace_requests, request_timeout);
+                    let request_id = request.header("X-Request-Id").map(str::to_owned);
+                    if let Some(request_id) = request_id.as_ref() {
+                        request_ids.send(request_id.clone()).ok(); // best attempt to log the id
+                    }
+
+                    stats::increment(&stats::BULK_REQUESTS);
                     if let Err(e) = Elasticsearch::execute_request(
-                        Elasticsearch::client()
-                            .post(&url)
-                            .set("content-type", "application/json"),
+                        request,
                         &mut reader,
                         |body| {
                             #[derive(Serialize, Deserialize, Debug)]
@@ -896,7 +1023,14 @@ impl Handler {
 
                         // send the error back to the main thread
                         terminated.store(true, Ordering::SeqCst);
-                        Handler::send_error(error, e.status(), e.message());
+                        match request_id {
+                            Some(request_id) => Handler::send_error(
+                                error,
+                                e.status(),
+                                &format!("X-Request-Id={}, {}", request_id, e.message()),
+                            ),
+                            None => Handler::send_error(error, e.status(), e.message()),
+                        }
                         break;
                     }
 
@@ -922,6 +1056,29 @@ impl Handler {
         })
     }
 
+    /// Creates a `_bulk` request.  When tracing, each request gets its own `X-Request-Id`.  We're
+    /// not on the Postgres thread, so the caller sends the id back to `check_for_error()` to be logged
+    fn bulk_request(
+        base_url: &str,
+        trace_requests: bool,
+        request_timeout: Option<Duration>,
+    ) -> ureq::Request {
+        let url = format!(
+            "{}/_bulk?format=cbor&filter_path={}",
+            base_url, BULK_FILTER_PATH
+        );
+        let mut request = Elasticsearch::client()
+            .post(&url)
+            .set("content-type", "application/json");
+        if trace_requests {
+            request = request.set("X-Request-Id", &generate_request_id());
+        }
+        if let Some(timeout) = request_timeout {
+            request = request.timeout(timeout);
+        }
+        request
+    }
+
     fn send_error(
         sender: crossbeam_channel::Sender<BulkRequestError>,
         code: Option<u16>,
@@ -962,6 +1119,15 @@ impl Handler {
 
     #[inline]
     pub(crate) fn check_for_error(&self) {
+        // log the ids of any traced _bulk requests our threads have made, as they can't
+        for request_id in self.request_id_receiver.try_iter() {
+            debug1!(
+                "zombodb request id for index {}: {}",
+                self.elasticsearch.index_name(),
+                request_id
+            );
+        }
+
         // do we have an error queued up?
         match self
             .error_receiver
@@ -993,3 +1159,80 @@ fn downcast_err(e: Box<dyn Any + Send>) -> String {
         "Box<Any>".to_string()
     }
 }
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use crate::access_method::options::OpType;
+    use crate::elasticsearch::bulk::{BulkReceiver, BulkRequestCommand, Handler};
+    use pgx::*;
+    use serde_json::Value;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    fn update_action_line(action_index: Option<String>, route_by_id: bool) -> Value {
+        let (_, receiver) = crossbeam_channel::bounded(1);
+        let mut bulk_receiver = BulkReceiver {
+            terminated: Arc::new(AtomicBool::new(false)),
+            first: None,
+            consumed: Default::default(),
+            receiver,
+            bytes_out: 0,
+            docs_out: 0,
+            buffer: Vec::new(),
+            batch_size: 0,
+            store_ctid_field: false,
+            op_type: OpType::Index,
+            action_index,
+            route_by_id,
+        };
+
+        bulk_receiver.serialize_command(BulkRequestCommand::Update {
+            ctid: 42,
+            cmax: 0,
+            xmax: 1,
+        });
+        let body = String::from_utf8(bulk_receiver.buffer).expect("bulk body is not utf8");
+        serde_json::from_str(body.lines().next().expect("no action line"))
+            .expect("action line is not json")
+    }
+
+    #[pg_test]
+    fn test_minimized_bulk_metadata() {
+        let action = update_action_line(None, false);
+        assert_eq!(action["update"]["_id"], 42);
+        assert!(action["update"].get("_index").is_none());
+        assert!(action["update"].get("routing").is_none());
+    }
+
+    #[pg_test]
+    fn test_unminimized_bulk_metadata() {
+        let action = update_action_line(Some("db.schema.table.index-1234".to_owned()), false);
+        assert_eq!(action["update"]["_id"], 42);
+        assert_eq!(action["update"]["_index"], "db.schema.table.index-1234");
+    }
+
+    #[pg_test]
+    fn test_routed_bulk_metadata() {
+        let action = update_action_line(None, true);
+        assert_eq!(action["update"]["_id"], 42);
+        assert_eq!(action["update"]["routing"], "42");
+    }
+
+    #[pg_test]
+    fn test_bulk_request_id_per_request() {
+        let first = Handler::bulk_request("http://localhost:19200/idx", true, None);
+        let second = Handler::bulk_request("http://localhost:19200/idx", true, None);
+
+        let first_id = first
+            .header("X-Request-Id")
+            .expect("no X-Request-Id header");
+        let second_id = second
+            .header("X-Request-Id")
+            .expect("no X-Request-Id header");
+        assert_eq!(first_id.len(), 36);
+        assert_ne!(first_id, second_id);
+
+        let untraced = Handler::bulk_request("http://localhost:19200/idx", false, None);
+        assert_eq!(untraced.header("X-Request-Id"), None);
+    }
+}
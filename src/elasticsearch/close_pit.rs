@@ -0,0 +1,55 @@
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use pgx::*;
+use serde::*;
+use serde_json::*;
+
+pub struct ElasticsearchClosePitRequest {
+    elasticsearch: Elasticsearch,
+    pit_id: String,
+}
+
+impl ElasticsearchClosePitRequest {
+    pub fn new(elasticsearch: &Elasticsearch, pit_id: &str) -> Self {
+        ElasticsearchClosePitRequest {
+            elasticsearch: elasticsearch.clone(),
+            pit_id: pit_id.to_string(),
+        }
+    }
+
+    /// Returns true if Elasticsearch found and freed the point-in-time
+    pub fn execute(self) -> std::result::Result<bool, ElasticsearchError> {
+        match Elasticsearch::execute_json_request(
+            Elasticsearch::client().delete(&format!("{}_pit", self.elasticsearch.url())),
+            Some(json! {
+                {
+                    "id": self.pit_id
+                }
+            }),
+            |body| {
+                #[derive(Deserialize)]
+                struct ClosePitResponse {
+                    succeeded: bool,
+                    num_freed: u64,
+                }
+
+                let response: ClosePitResponse = serde_json::from_reader(body)
+                    .expect("failed to deserialize close point-in-time response");
+                Ok(response.succeeded && response.num_freed > 0)
+            },
+        ) {
+            // ES responds with a 404 when the point-in-time has already expired or been closed
+            Err(e) if e.status() == Some(404) => Ok(false),
+            other => other,
+        }
+    }
+}
+
+/// Closes a point-in-time previously opened with `zdb.open_pit()`, returning false if it had
+/// already expired
+#[pg_extern(volatile, parallel_safe)]
+fn close_pit(index: PgRelation, pit_id: &str) -> bool {
+    Elasticsearch::new(&index)
+        .close_pit(pit_id)
+        .execute()
+        .unwrap_or_else(|e| panic!("{}", e))
+}
@@ -36,6 +36,9 @@ impl ElasticsearchCountRequest {
 
         let mut url = self.elasticsearch.alias_url();
         url.push_str("/_count");
+        if self.elasticsearch.options.search_throttled() {
+            url.push_str("?ignore_throttled=false");
+        }
         Elasticsearch::execute_json_request(
             Elasticsearch::client().post(&url),
             Some(body),
@@ -0,0 +1,89 @@
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use crate::zdbquery::mvcc::apply_visibility_clause;
+use crate::zdbquery::ZDBPreparedQuery;
+use serde::*;
+use serde_json::*;
+
+pub struct ElasticsearchCountBatchRequest {
+    elasticsearch: Elasticsearch,
+    queries: Vec<ZDBPreparedQuery>,
+}
+
+impl ElasticsearchCountBatchRequest {
+    pub fn new(elasticsearch: &Elasticsearch, queries: Vec<ZDBPreparedQuery>) -> Self {
+        ElasticsearchCountBatchRequest {
+            elasticsearch: elasticsearch.clone(),
+            queries,
+        }
+    }
+
+    /// Returns the count of each query, in the same order the queries were given
+    pub fn execute(self) -> std::result::Result<Vec<u64>, ElasticsearchError> {
+        // _msearch wants newline-delimited json:  a header line followed by a body line, per query
+        let header = json! {{}}.to_string();
+        let mut ndjson = String::new();
+        for query in self.queries {
+            let body = json! {
+                {
+                    "query": apply_visibility_clause(&self.elasticsearch, query, false),
+                    "size": 0,
+                    "track_total_hits": true
+                }
+            };
+            ndjson.push_str(&header);
+            ndjson.push('\n');
+            ndjson.push_str(&body.to_string());
+            ndjson.push('\n');
+        }
+
+        let mut url = self.elasticsearch.alias_url();
+        url.push_str("/_msearch?filter_path=responses.hits.total.value,responses.error");
+        if self.elasticsearch.options.search_throttled() {
+            url.push_str("&ignore_throttled=false");
+        }
+        Elasticsearch::execute_request(
+            Elasticsearch::client()
+                .post(&url)
+                .set("content-type", "application/x-ndjson"),
+            ndjson.as_bytes(),
+            |body| {
+                #[derive(Deserialize)]
+                struct Total {
+                    value: u64,
+                }
+
+                #[derive(Deserialize)]
+                struct Hits {
+                    total: Total,
+                }
+
+                #[derive(Deserialize)]
+                struct Response {
+                    hits: Option<Hits>,
+                    error: Option<Value>,
+                }
+
+                #[derive(Deserialize)]
+                struct MultiSearchResponse {
+                    responses: Vec<Response>,
+                }
+
+                let response: MultiSearchResponse = serde_json::from_reader(body)
+                    .expect("failed to deserialize multi search response");
+
+                // a query can fail on its own without failing the whole _msearch request
+                response
+                    .responses
+                    .into_iter()
+                    .map(|response| match (response.hits, response.error) {
+                        (Some(hits), None) => Ok(hits.total.value),
+                        (_, error) => Err(ElasticsearchError(
+                            None,
+                            serde_json::to_string_pretty(&error).unwrap(),
+                        )),
+                    })
+                    .collect()
+            },
+        )
+    }
+}
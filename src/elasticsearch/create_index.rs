@@ -1,5 +1,7 @@
-use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
-use crate::mapping::lookup_analysis_thing;
+use crate::elasticsearch::{version_at_least, Elasticsearch, ElasticsearchError};
+use crate::mapping::{disable_norms, is_numeric_mapping, lookup_analysis_thing};
+use pgx::*;
+use serde::Deserialize;
 use serde_json::*;
 
 pub struct ElasticsearchCreateIndexRequest {
@@ -17,7 +19,8 @@ impl ElasticsearchCreateIndexRequest {
 
     pub fn execute(self) -> std::result::Result<(), ElasticsearchError> {
         Elasticsearch::execute_json_request(
-            Elasticsearch::client().put(&self.elasticsearch.base_url()),
+            self.elasticsearch
+                .timed(Elasticsearch::client().put(&self.elasticsearch.base_url())),
             Some(self.create_request_body()),
             |_| Ok(()),
         )?;
@@ -40,51 +43,115 @@ impl ElasticsearchCreateIndexRequest {
             .unwrap()
             .contains(r#""type":"nested""#);
 
-        let index_block = if has_nested_field {
-            // we can't do an index-level sort
-            json! { {
-              "number_of_shards": self.elasticsearch.options.shards(),
-              "number_of_replicas": 0,
-              "refresh_interval": "-1",
-              "query.default_field": "zdb_all",
-              "translog.durability": "async",
-              "mapping.nested_fields.limit": self.elasticsearch.options.nested_fields_limit(),
-              "mapping.total_fields.limit": self.elasticsearch.options.total_fields_limit(),
-              "max_result_window": self.elasticsearch.options.max_result_window(),
-              "max_terms_count": self.elasticsearch.options.max_terms_count()
-            } }
-        } else {
-            // we can do an index-level sort on zdb_ctid:asc
-            json! { {
-              "number_of_shards": self.elasticsearch.options.shards(),
-              "number_of_replicas": 0,
-              "refresh_interval": "-1",
-              "query.default_field": "zdb_all",
-              "translog.durability": "async",
-              "mapping.nested_fields.limit": self.elasticsearch.options.nested_fields_limit(),
-              "mapping.total_fields.limit": self.elasticsearch.options.total_fields_limit(),
-              "max_result_window": self.elasticsearch.options.max_result_window(),
-              "max_terms_count": self.elasticsearch.options.max_terms_count(),
-              "sort.field": "zdb_ctid",
-              "sort.order": "asc"
-            } }
-        };
+        let mut index_block = json! { {
+          "number_of_shards": self.elasticsearch.options.shards(),
+          "number_of_replicas": 0,
+          "refresh_interval": "-1",
+          "query.default_field": "zdb_all",
+          "translog.durability": "async",
+          "mapping.nested_fields.limit": self.elasticsearch.options.nested_fields_limit(),
+          "mapping.total_fields.limit": self.elasticsearch.options.total_fields_limit(),
+          "mapping.field_name_length.limit": self.elasticsearch.options.field_name_length_limit(),
+          "max_result_window": self.elasticsearch.options.max_result_window(),
+          "max_terms_count": self.elasticsearch.options.max_terms_count(),
+          "max_script_fields": self.elasticsearch.options.max_script_fields(),
+          "max_shingle_diff": self.elasticsearch.options.max_shingle_diff(),
+          "priority": self.elasticsearch.options.recovery_priority(),
+          "routing_partition_size": self.elasticsearch.options.routing_partition_size(),
+          "shard.check_on_startup": self.elasticsearch.options.check_on_startup(),
+          "write.wait_for_active_shards": self.elasticsearch.options.default_wait_for_active_shards(),
+          "soft_deletes.enabled": self.elasticsearch.options.soft_deletes(),
+          "mapping.coerce": self.elasticsearch.options.mapping_coerce(),
+          "mapping.ignore_malformed": self.elasticsearch.options.ignore_malformed(),
+          "store.type": self.elasticsearch.options.store_type(),
+          "load_fixed_bitset_filters_eagerly": self.elasticsearch.options.eager_bitset_filters(),
+          "merge.policy.floor_segment": self.elasticsearch.options.floor_segment(),
+          "indexing.slowlog.source": self.elasticsearch.slowlog_source_setting(),
+          "default_pipeline": self.elasticsearch.options.default_pipeline(),
+          "final_pipeline": self.elasticsearch.options.final_pipeline(),
+          "routing.allocation.include._tier_preference": self.elasticsearch.options.tier_preference()
+        } };
+
+        if !has_nested_field {
+            // we can do an index-level sort on zdb_ctid:asc, but only when
+            // the mapping doesn't contain a nested field
+            let settings = index_block.as_object_mut().unwrap();
+            settings.insert("sort.field".into(), json!("zdb_ctid"));
+            settings.insert("sort.order".into(), json!("asc"));
+        }
 
-        json! {
+        let mut properties = self.mapping.clone();
+        if self.elasticsearch.options.store_ctid_field() {
+            properties
+                .as_object_mut()
+                .expect("mapping properties are not an object")
+                .insert("_zdb_ctid".into(), json!({ "type": "keyword" }));
+        }
+        if let Some(properties) = properties.as_object_mut() {
+            for field in self.elasticsearch.options.disable_norms_fields() {
+                if let Some(definition) = properties.get_mut(field) {
+                    disable_norms(definition);
+                }
+            }
+            for field in self.elasticsearch.options.rank_feature_fields() {
+                match properties.get_mut(field) {
+                    Some(definition) if is_numeric_mapping(definition) => {
+                        *definition = json!({ "type": "rank_feature" })
+                    }
+                    Some(_) => panic!("rank_feature_fields column is not numeric: {}", field),
+                    None => panic!("rank_feature_fields column does not exist: {}", field),
+                }
+            }
+            let dense_vector_fields = self.elasticsearch.options.dense_vector_fields();
+            if !dense_vector_fields.is_empty() {
+                let version = self
+                    .elasticsearch
+                    .cluster_version()
+                    .unwrap_or_else(|e| panic!("failed to get Elasticsearch version: {}", e));
+                if !version_at_least(&version, 8, 0) {
+                    warning!(
+                        "dense_vector fields can only be indexed for kNN search on Elasticsearch 8.0 or later, but the cluster is version {}.  They won't be usable with zdb.knn_search()",
+                        version
+                    );
+                }
+
+                for (field, dims) in dense_vector_fields {
+                    match properties.get_mut(field) {
+                        Some(definition) if is_numeric_mapping(definition) => {
+                            *definition = dense_vector_mapping(*dims, &version)
+                        }
+                        Some(_) => panic!("dense_vector_fields column is not numeric: {}", field),
+                        None => panic!("dense_vector_fields column does not exist: {}", field),
+                    }
+                }
+            }
+        }
+
+        let mut analysis = json! { {
+           "filter": lookup_analysis_thing("filters"),
+           "char_filter" : lookup_analysis_thing("char_filters"),
+           "tokenizer" : lookup_analysis_thing("tokenizers"),
+           "analyzer": lookup_analysis_thing("analyzers"),
+           "normalizer": lookup_analysis_thing("normalizers")
+        } };
+
+        if let Some(stopwords) = self.elasticsearch.options.stopwords() {
+            apply_stopwords(&mut analysis, &mut properties, stopwords);
+        }
+
+        if self.elasticsearch.options.updatable_synonyms() {
+            mark_synonyms_updateable(&mut analysis);
+        }
+
+        let mut body = json! {
             {
                "settings": {
                   "index": index_block,
-                  "analysis": {
-                     "filter": lookup_analysis_thing("filters"),
-                     "char_filter" : lookup_analysis_thing("char_filters"),
-                     "tokenizer" : lookup_analysis_thing("tokenizers"),
-                     "analyzer": lookup_analysis_thing("analyzers"),
-                     "normalizer": lookup_analysis_thing("normalizers")
-                  },
+                  "analysis": analysis,
                  "similarity": lookup_analysis_thing("similarities")
                },
                "mappings": {
-                     "_source": { "enabled": true },
+                     "_source": self.source_mapping(),
                      "date_detection": self.elasticsearch.options.nested_object_date_detection(),
                      "numeric_detection": self.elasticsearch.options.nested_object_numeric_detection(),
                      "dynamic_templates": [
@@ -118,12 +185,190 @@ impl ElasticsearchCreateIndexRequest {
                             }
                           }
                      ],
-                     "properties": self.mapping
+                     "properties": properties
                },
                "aliases": {
                   self.elasticsearch.options.alias(): {}
                }
             }
+        };
+
+        if self.elasticsearch.options.routing_partition_size() > 1 {
+            // Elasticsearch won't create a partitioned index unless routing is required
+            body["mappings"]["_routing"] = json!({ "required": true });
+        }
+
+        if self.elasticsearch.options.store_doc_size() {
+            let has_mapper_size = self
+                .has_plugin("mapper-size")
+                .unwrap_or_else(|e| panic!("failed to get Elasticsearch plugins: {}", e));
+            if has_mapper_size {
+                body["mappings"]["_size"] = json!({ "enabled": true });
+            } else {
+                // Elasticsearch would otherwise refuse to create the index
+                warning!("store_doc_size requires the Elasticsearch mapper-size plugin, which isn't installed.  Document sizes won't be stored");
+            }
         }
+
+        body
+    }
+
+    fn source_mapping(&self) -> Value {
+        if !self.elasticsearch.options.synthetic_source() {
+            return json!({ "enabled": true });
+        }
+
+        let version = self
+            .elasticsearch
+            .cluster_version()
+            .unwrap_or_else(|e| panic!("failed to get Elasticsearch version: {}", e));
+        match synthetic_source_mapping(&version) {
+            Some(mapping) => mapping,
+            None => {
+                warning!(
+                    "synthetic_source requires Elasticsearch 8.4 or later, but the cluster is version {}.  Using a regular _source instead",
+                    version
+                );
+                json!({ "enabled": true })
+            }
+        }
+    }
+
+    fn has_plugin(&self, name: &str) -> std::result::Result<bool, ElasticsearchError> {
+        #[derive(Deserialize)]
+        struct CatPlugin {
+            component: String,
+        }
+
+        Elasticsearch::execute_json_request(
+            Elasticsearch::client().get(&format!(
+                "{}_cat/plugins?h=component&format=json",
+                self.elasticsearch.url()
+            )),
+            None,
+            |body| {
+                let plugins: Vec<CatPlugin> =
+                    serde_json::from_reader(body).expect("failed to parse _cat/plugins response");
+                Ok(plugins.iter().any(|plugin| plugin.component == name))
+            },
+        )
+    }
+}
+
+/// Marks every synonym filter as `updateable`, so that `zdb.reload_analyzers()` can pick up changes
+/// to their synonyms files.  Elasticsearch only allows updateable filters in search analyzers
+fn mark_synonyms_updateable(analysis: &mut Value) {
+    if let Some(filters) = analysis["filter"].as_object_mut() {
+        for definition in filters.values_mut() {
+            if definition["type"] == "synonym" || definition["type"] == "synonym_graph" {
+                definition["updateable"] = json!(true);
+            }
+        }
+    }
+}
+
+/// The `_source` mapping for an index that wants synthetic `_source`, which Elasticsearch
+/// supports as of 8.4.  Returns `None` for older versions
+fn synthetic_source_mapping(version: &str) -> Option<Value> {
+    if version_at_least(version, 8, 4) {
+        Some(json!({ "mode": "synthetic" }))
+    } else {
+        None
+    }
+}
+
+/// The mapping for a `dense_vector_fields` column.  Indexing the vectors for kNN search needs
+/// Elasticsearch 8.0, so older versions only store them
+fn dense_vector_mapping(dims: u32, version: &str) -> Value {
+    if version_at_least(version, 8, 0) {
+        json!({
+            "type": "dense_vector",
+            "dims": dims,
+            "index": true,
+            "similarity": "cosine"
+        })
+    } else {
+        json!({ "type": "dense_vector", "dims": dims })
+    }
+}
+
+/// Adds a "zdb_stopwords" filter and an analyzer that uses it, and then switches every
+/// text field that would otherwise use "zdb_standard" over to that analyzer
+fn apply_stopwords(analysis: &mut Value, properties: &mut Value, stopwords: &[String]) {
+    analysis["filter"]["zdb_stopwords"] = json! { {
+        "type": "stop",
+        "stopwords": stopwords
+    } };
+    analysis["analyzer"]["zdb_stopwords_analyzer"] = json! { {
+        "type": "custom",
+        "tokenizer": "standard",
+        "filter": ["zdb_truncate_to_fit", "lowercase", "zdb_stopwords"]
+    } };
+
+    if let Some(properties) = properties.as_object_mut() {
+        for definition in properties.values_mut() {
+            if definition["type"] == "text" && definition["analyzer"] == "zdb_standard" {
+                definition["analyzer"] = json!("zdb_stopwords_analyzer");
+            }
+        }
+    }
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use crate::elasticsearch::create_index::{
+        dense_vector_mapping, mark_synonyms_updateable, synthetic_source_mapping,
+    };
+    use pgx::*;
+    use serde_json::json;
+
+    #[pg_test]
+    fn test_synthetic_source_mapping() {
+        assert_eq!(
+            synthetic_source_mapping("8.4.0"),
+            Some(json!({ "mode": "synthetic" }))
+        );
+        assert_eq!(
+            synthetic_source_mapping("8.11.1"),
+            Some(json!({ "mode": "synthetic" }))
+        );
+    }
+
+    #[pg_test]
+    fn test_synthetic_source_mapping_unsupported() {
+        assert_eq!(synthetic_source_mapping("8.3.3"), None);
+        assert_eq!(synthetic_source_mapping("7.10.2"), None);
+    }
+
+    #[pg_test]
+    fn test_dense_vector_mapping() {
+        assert_eq!(
+            dense_vector_mapping(3, "8.0.0"),
+            json!({ "type": "dense_vector", "dims": 3, "index": true, "similarity": "cosine" })
+        );
+        assert_eq!(
+            dense_vector_mapping(3, "7.13.2"),
+            json!({ "type": "dense_vector", "dims": 3 })
+        );
+    }
+
+    #[pg_test]
+    fn test_mark_synonyms_updateable() {
+        let mut analysis = json!({
+            "filter": {
+                "my_synonyms": { "type": "synonym", "synonyms_path": "synonyms.txt" },
+                "my_graph_synonyms": { "type": "synonym_graph", "synonyms": ["a, b"] },
+                "my_stemmer": { "type": "stemmer", "language": "english" }
+            }
+        });
+
+        mark_synonyms_updateable(&mut analysis);
+
+        assert_eq!(analysis["filter"]["my_synonyms"]["updateable"], json!(true));
+        assert_eq!(
+            analysis["filter"]["my_graph_synonyms"]["updateable"],
+            json!(true)
+        );
+        assert!(analysis["filter"]["my_stemmer"].get("updateable").is_none());
     }
 }
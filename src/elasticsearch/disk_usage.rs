@@ -0,0 +1,78 @@
+use crate::elasticsearch::{version_at_least, Elasticsearch, ElasticsearchError};
+use pgx::*;
+use serde_json::Value;
+
+pub struct ElasticsearchDiskUsageRequest(Elasticsearch);
+
+impl ElasticsearchDiskUsageRequest {
+    pub fn new(elasticsearch: &Elasticsearch) -> Self {
+        ElasticsearchDiskUsageRequest(elasticsearch.clone())
+    }
+
+    pub fn execute(self) -> std::result::Result<Value, ElasticsearchError> {
+        // the _disk_usage API was added in Elasticsearch 7.15
+        let version = self.0.cluster_version()?;
+        if !version_at_least(&version, 7, 15) {
+            return Err(ElasticsearchError(
+                None,
+                format!(
+                    "zdb.disk_usage() requires Elasticsearch 7.15 or later, but the cluster is version {}",
+                    version
+                ),
+            ));
+        }
+
+        let url = format!("{}/_disk_usage?run_expensive_tasks=true", self.0.base_url());
+
+        Elasticsearch::execute_json_request(Elasticsearch::client().post(&url), None, |body| {
+            let mut response: Value =
+                serde_json::from_reader(body).expect("failed to parse disk usage response");
+
+            // the response is keyed by index name, alongside a "_shards" summary
+            Ok(response
+                .get_mut(self.0.index_name())
+                .map(Value::take)
+                .unwrap_or(Value::Null))
+        })
+    }
+}
+
+/// Returns the per-field storage breakdown for the index, from Elasticsearch's `_disk_usage` API
+#[pg_extern(volatile, parallel_safe)]
+fn disk_usage(index: PgRelation) -> JsonB {
+    JsonB(
+        Elasticsearch::new(&index)
+            .disk_usage()
+            .execute()
+            .unwrap_or_else(|e| panic!("failed to analyze index disk usage: {}", e)),
+    )
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use crate::elasticsearch::{version_at_least, Elasticsearch};
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_disk_usage() {
+        Spi::run("CREATE TABLE test_disk_usage AS SELECT n, 'hello world ' || n AS title FROM generate_series(1, 100) n;");
+        Spi::run("CREATE INDEX idxtest_disk_usage ON test_disk_usage USING zombodb ((test_disk_usage.*));");
+
+        let index = PgRelation::open_with_name("idxtest_disk_usage").expect("no such relation");
+        let version = Elasticsearch::new(&index)
+            .cluster_version()
+            .expect("failed to get Elasticsearch version");
+        if !version_at_least(&version, 7, 15) {
+            // the cluster predates the _disk_usage API
+            return;
+        }
+
+        let usage = Spi::get_one::<JsonB>("SELECT zdb.disk_usage('idxtest_disk_usage');")
+            .expect("failed to get SPI result");
+        let title = &usage.0["fields"]["title"];
+
+        assert!(title.is_object());
+        assert!(title["total_in_bytes"].as_u64().is_some());
+    }
+}
@@ -0,0 +1,145 @@
+use crate::access_method::options::query_fields;
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use crate::zdbquery::{ZDBPreparedQuery, ZDBQuery};
+use pgx::*;
+use serde_json::*;
+use std::collections::BTreeSet;
+
+pub struct ElasticsearchExplainDocumentRequest {
+    elasticsearch: Elasticsearch,
+    id: u64,
+    query: ZDBPreparedQuery,
+}
+
+impl ElasticsearchExplainDocumentRequest {
+    pub fn new(elasticsearch: &Elasticsearch, id: u64, query: ZDBPreparedQuery) -> Self {
+        ElasticsearchExplainDocumentRequest {
+            elasticsearch: elasticsearch.clone(),
+            id,
+            query,
+        }
+    }
+
+    /// Returns ES' explanation of how the document scored against the query, or `None` if the
+    /// document doesn't match it
+    pub fn execute(self) -> std::result::Result<Option<Value>, ElasticsearchError> {
+        let body = json! {
+            {
+                "query": self.query.query_dsl()
+            }
+        };
+
+        let result = Elasticsearch::execute_json_request(
+            Elasticsearch::client().post(&format!(
+                "{}/_explain/{}?{}",
+                self.elasticsearch.base_url(),
+                self.id,
+                self.elasticsearch
+                    .routing_param(&self.id.to_string())
+                    .trim_start_matches('&')
+            )),
+            Some(body),
+            |body| {
+                let mut response: Value =
+                    serde_json::from_reader(body).expect("failed to parse response json");
+
+                if response["matched"].as_bool().unwrap_or(false) {
+                    Ok(Some(response["explanation"].take()))
+                } else {
+                    Ok(None)
+                }
+            },
+        );
+
+        match result {
+            // a 404 means there's no such document, so nothing matched
+            Err(e) if e.is_404() => Ok(None),
+            result => result,
+        }
+    }
+}
+
+/// Returns the names of the fields that caused the document at `ctid` to match `query`
+#[pg_extern(volatile, parallel_safe)]
+fn matched_fields(index: PgRelation, ctid: pg_sys::ItemPointerData, query: &str) -> Vec<String> {
+    let (prepared_query, _) = ZDBQuery::from_str(query).prepare(&index, None);
+    let explanation = match Elasticsearch::new(&index)
+        .explain_document(item_pointer_to_u64(ctid), prepared_query)
+        .execute()
+        .expect("failed to execute explain request")
+    {
+        Some(explanation) => explanation,
+        None => return Vec::new(),
+    };
+
+    let mut descriptions = Vec::new();
+    collect_descriptions(&explanation, &mut descriptions);
+
+    // ES describes each matching clause in terms of the field it searched, such as
+    // "weight(title:apple in 0)" or "ConstantScore(price:[10 TO 20])"
+    let mut matched = BTreeSet::new();
+    for (field, _) in query_fields(index) {
+        let needle = format!("{}:", field);
+        let is_match = descriptions.iter().any(|description| {
+            description.match_indices(&needle).any(|(idx, _)| {
+                idx == 0
+                    || !description[..idx]
+                        .ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '.')
+            })
+        });
+
+        if is_match {
+            matched.insert(field);
+        }
+    }
+
+    matched.into_iter().collect()
+}
+
+fn collect_descriptions<'a>(explanation: &'a Value, descriptions: &mut Vec<&'a str>) {
+    if explanation["value"].as_f64().unwrap_or_default() <= 0.0 {
+        // this part of the query didn't contribute to the match
+        return;
+    }
+
+    if let Some(description) = explanation["description"].as_str() {
+        descriptions.push(description);
+    }
+
+    if let Some(details) = explanation["details"].as_array() {
+        for detail in details {
+            collect_descriptions(detail, descriptions);
+        }
+    }
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_matched_fields() {
+        Spi::run("CREATE TABLE test_matched_fields AS SELECT 'apple'::text AS title, 'cherry'::text AS body;");
+        Spi::run("CREATE INDEX idxtest_matched_fields ON test_matched_fields USING zombodb ((test_matched_fields.*));");
+
+        let fields = Spi::get_one::<Vec<&str>>(
+            "SELECT zdb.matched_fields('idxtest_matched_fields', ctid, 'title:apple OR body:banana') FROM test_matched_fields;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(fields, vec!["title"]);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_matched_fields_no_match() {
+        Spi::run("CREATE TABLE test_matched_fields AS SELECT 'apple'::text AS title, 'cherry'::text AS body;");
+        Spi::run("CREATE INDEX idxtest_matched_fields ON test_matched_fields USING zombodb ((test_matched_fields.*));");
+
+        let fields = Spi::get_one::<Vec<&str>>(
+            "SELECT zdb.matched_fields('idxtest_matched_fields', ctid, 'title:banana') FROM test_matched_fields;",
+        )
+        .expect("failed to get SPI result");
+        assert!(fields.is_empty());
+    }
+}
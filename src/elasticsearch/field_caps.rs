@@ -0,0 +1,68 @@
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use pgx::*;
+use serde_json::Value;
+
+pub struct ElasticsearchFieldCapsRequest {
+    elasticsearch: Elasticsearch,
+    fields: Vec<String>,
+}
+
+impl ElasticsearchFieldCapsRequest {
+    pub fn new(elasticsearch: &Elasticsearch, fields: Vec<String>) -> Self {
+        ElasticsearchFieldCapsRequest {
+            elasticsearch: elasticsearch.clone(),
+            fields,
+        }
+    }
+
+    pub fn execute(self) -> std::result::Result<Value, ElasticsearchError> {
+        let url = format!(
+            "{}/_field_caps?fields={}",
+            self.elasticsearch.base_url(),
+            url::form_urlencoded::byte_serialize(self.fields.join(",").as_bytes())
+                .collect::<String>()
+        );
+
+        Elasticsearch::execute_json_request(Elasticsearch::client().get(&url), None, |body| {
+            let mut response: Value =
+                serde_json::from_reader(body).expect("failed to parse field caps response");
+            Ok(response["fields"].take())
+        })
+    }
+}
+
+/// Returns Elasticsearch's field capabilities, such as whether they're searchable or aggregatable,
+/// for the specified fields, which can include wildcards
+#[pg_extern(volatile, parallel_safe)]
+fn field_caps(index: PgRelation, fields: Vec<Option<String>>) -> JsonB {
+    let fields = fields.into_iter().flatten().collect::<Vec<_>>();
+    if fields.is_empty() {
+        panic!("fields must not be empty");
+    }
+
+    JsonB(
+        Elasticsearch::new(&index)
+            .field_caps(fields)
+            .execute()
+            .expect("failed to get field capabilities"),
+    )
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_field_caps() {
+        Spi::run("CREATE TABLE test_field_caps AS SELECT n, 'hello world' AS title FROM generate_series(1, 10) n;");
+        Spi::run("CREATE INDEX idxtest_field_caps ON test_field_caps USING zombodb ((test_field_caps.*));");
+
+        let searchable = Spi::get_one::<bool>(
+            "SELECT (zdb.field_caps('idxtest_field_caps', ARRAY['title', 'n'])->'title'->'text'->>'searchable')::boolean;",
+        )
+        .expect("failed to get SPI result");
+
+        assert!(searchable);
+    }
+}
@@ -25,10 +25,11 @@ impl<'a, T: serde::de::DeserializeOwned> ElasticsearchGetDocumentRequest<'a, T>
     pub fn execute(self) -> Result<Option<T>, ElasticsearchError> {
         let result = Elasticsearch::execute_json_request(
             Elasticsearch::client().get(&format!(
-                "{}/_doc/{}?realtime={}",
+                "{}/_doc/{}?realtime={}{}",
                 self.elasticsearch.base_url(),
                 self.id,
-                if self.realtime { "true" } else { "false" }
+                if self.realtime { "true" } else { "false" },
+                self.elasticsearch.routing_param(self.id)
             )),
             None,
             |body| {
@@ -0,0 +1,26 @@
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+
+pub struct ElasticsearchIndexExistsRequest(Elasticsearch);
+
+impl ElasticsearchIndexExistsRequest {
+    pub fn new(elasticsearch: &Elasticsearch) -> Self {
+        ElasticsearchIndexExistsRequest(elasticsearch.clone())
+    }
+
+    pub fn execute(self) -> Result<bool, ElasticsearchError> {
+        match Elasticsearch::execute_json_request(
+            Elasticsearch::client().head(&self.0.base_url()),
+            None,
+            |_| Ok(()),
+        ) {
+            // 404 NOT FOUND means the index doesn't exist
+            Err(e) if e.is_404() => Ok(false),
+
+            // other errors need to be reported back to the caller
+            Err(e) => Err(e),
+
+            // it's there
+            Ok(_) => Ok(true),
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use crate::elasticsearch::{version_at_least, Elasticsearch, ElasticsearchError};
+use crate::zdbquery::mvcc::apply_visibility_clause;
+use crate::zdbquery::ZDBQuery;
+use pgx::*;
+use serde::*;
+use serde_json::*;
+
+pub struct ElasticsearchKnnSearchRequest {
+    elasticsearch: Elasticsearch,
+    field: String,
+    query_vector: Vec<f64>,
+    k: u64,
+    num_candidates: u64,
+}
+
+impl ElasticsearchKnnSearchRequest {
+    pub fn new(
+        elasticsearch: &Elasticsearch,
+        field: String,
+        query_vector: Vec<f64>,
+        k: u64,
+        num_candidates: u64,
+    ) -> Self {
+        ElasticsearchKnnSearchRequest {
+            elasticsearch: elasticsearch.clone(),
+            field,
+            query_vector,
+            k,
+            num_candidates,
+        }
+    }
+
+    /// Returns the `(score, zdb_ctid)` of the nearest neighbors, ordered by descending score
+    pub fn execute(
+        self,
+        index: &PgRelation,
+    ) -> std::result::Result<Vec<(f64, u64)>, ElasticsearchError> {
+        // the _search API's top-level "knn" option was added in Elasticsearch 8.4
+        let version = self.elasticsearch.cluster_version()?;
+        if !version_at_least(&version, 8, 4) {
+            return Err(ElasticsearchError(
+                None,
+                format!(
+                    "zdb.knn_search() requires Elasticsearch 8.4 or later, but the cluster is version {}",
+                    version
+                ),
+            ));
+        }
+
+        // only consider documents visible to the current transaction
+        let (match_all, _) =
+            ZDBQuery::new_with_query_dsl(json! {{ "match_all": {} }}).prepare(index, None);
+        let filter = apply_visibility_clause(&self.elasticsearch, match_all, false);
+        let body = json! {
+            {
+                "knn": {
+                    "field": self.field,
+                    "query_vector": self.query_vector,
+                    "k": self.k,
+                    "num_candidates": self.num_candidates,
+                    "filter": filter
+                },
+                "size": self.k,
+                "_source": false,
+                "stored_fields": "_none_",
+                "docvalue_fields": ["zdb_ctid"]
+            }
+        };
+
+        let mut url = self.elasticsearch.alias_url();
+        url.push_str("/_search");
+        if self.elasticsearch.options.search_throttled() {
+            url.push_str("?ignore_throttled=false");
+        }
+        Elasticsearch::execute_json_request(
+            Elasticsearch::client().post(&url),
+            Some(body),
+            |body| {
+                #[derive(Deserialize)]
+                struct Fields {
+                    zdb_ctid: [u64; 1],
+                }
+
+                #[derive(Deserialize)]
+                struct Hit {
+                    #[serde(rename = "_score")]
+                    score: f64,
+                    fields: Fields,
+                }
+
+                #[derive(Deserialize)]
+                struct Hits {
+                    hits: Vec<Hit>,
+                }
+
+                #[derive(Deserialize)]
+                struct KnnSearchResponse {
+                    hits: Hits,
+                }
+
+                let response: KnnSearchResponse = serde_json::from_reader(body)
+                    .expect("failed to deserialize knn search response");
+                Ok(response
+                    .hits
+                    .hits
+                    .into_iter()
+                    .map(|hit| (hit.score, hit.fields.zdb_ctid[0]))
+                    .collect())
+            },
+        )
+    }
+}
+
+/// Finds the `k` documents whose dense_vector `field` is nearest to `query_vector`, using
+/// Elasticsearch's approximate kNN search, ordered by descending score
+#[pg_extern(volatile, parallel_safe)]
+fn knn_search(
+    index: PgRelation,
+    field: &str,
+    query_vector: Vec<Option<f64>>,
+    k: i32,
+    num_candidates: default!(i32, 100),
+) -> impl std::iter::Iterator<Item = (name!(ctid, pg_sys::ItemPointerData), name!(score, f32))> {
+    if k < 1 {
+        panic!("k must be greater than zero");
+    } else if num_candidates < k {
+        panic!("num_candidates must be greater than or equal to k");
+    }
+
+    let query_vector = query_vector
+        .into_iter()
+        .map(|v| v.expect("query_vector cannot contain NULL values"))
+        .collect();
+
+    let neighbors = Elasticsearch::new(&index)
+        .knn_search(
+            field.to_owned(),
+            query_vector,
+            k as u64,
+            num_candidates as u64,
+        )
+        .execute(&index)
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    let mut results = Vec::new();
+    for (score, tid) in neighbors {
+        let mut ipd = pg_sys::ItemPointerData::default();
+        u64_to_item_pointer(tid, &mut ipd);
+
+        results.push((ipd, score as f32));
+    }
+    results.into_iter()
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use crate::elasticsearch::{version_at_least, Elasticsearch};
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_knn_search() {
+        Spi::run(
+            "CREATE TABLE test_knn AS SELECT * FROM (VALUES
+                ('origin', ARRAY[0.0, 0.0, 0.0]::float8[]),
+                ('near', ARRAY[1.0, 1.0, 1.0]::float8[]),
+                ('far', ARRAY[10.0, 10.0, 10.0]::float8[])) t(name, embedding);",
+        );
+
+        // indexed dense_vector fields need a cluster that supports kNN search
+        Spi::run("CREATE INDEX idxtest_knn ON test_knn USING zombodb ((test_knn.*));");
+        {
+            let index = PgRelation::open_with_name("idxtest_knn").expect("no such relation");
+            let version = Elasticsearch::new(&index)
+                .cluster_version()
+                .expect("failed to get Elasticsearch version");
+            if !version_at_least(&version, 8, 4) {
+                return;
+            }
+        }
+        Spi::run("DROP INDEX idxtest_knn;");
+
+        Spi::run("SELECT zdb.define_field_mapping('test_knn', 'embedding', '{\"type\": \"dense_vector\", \"dims\": 3, \"index\": true, \"similarity\": \"l2_norm\"}');");
+        Spi::run("CREATE INDEX idxtest_knn ON test_knn USING zombodb ((test_knn.*));");
+
+        let names = Spi::get_one::<Vec<&str>>(
+            "SELECT array_agg(t.name ORDER BY s.score DESC)
+               FROM zdb.knn_search('idxtest_knn', 'embedding', ARRAY[9.0, 9.0, 9.0], 2) s
+               JOIN test_knn t ON t.ctid = s.ctid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(names, vec!["far", "near"]);
+    }
+
+    #[pg_test(error = "k must be greater than zero")]
+    #[initialize(es = true)]
+    fn test_knn_search_invalid_k() {
+        Spi::run("CREATE TABLE test_knn(embedding float8[]);");
+        Spi::run("CREATE INDEX idxtest_knn ON test_knn USING zombodb ((test_knn.*));");
+        Spi::run("SELECT * FROM zdb.knn_search('idxtest_knn', 'embedding', ARRAY[1.0], 0);");
+    }
+}
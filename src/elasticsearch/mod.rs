@@ -5,18 +5,34 @@ mod aliases;
 pub(crate) mod analyze;
 mod bulk;
 mod cat;
+mod close_pit;
 mod count;
+mod count_batch;
 mod create_index;
 mod delete_index;
+mod disk_usage;
+mod explain_document;
 mod expunge_deletes;
+mod field_caps;
 mod get_document;
 mod get_mapping;
 mod get_settings;
+mod index_exists;
+mod knn_search;
+mod open_pit;
+mod post_filter_search;
 mod profile_query;
 mod put_mapping;
 mod refresh_index;
+mod reindex;
+mod reload_search_analyzers;
+mod script_field_search;
+mod stats;
 mod suggest_term;
+mod update_document;
 mod update_settings;
+mod validate_query;
+mod wait_for_stable;
 
 pub mod aggregate_search;
 pub mod search;
@@ -26,37 +42,48 @@ use crate::elasticsearch::aggregate_search::ElasticsearchAggregateSearchRequest;
 use crate::elasticsearch::aliases::ElasticsearchAliasRequest;
 use crate::elasticsearch::analyze::ElasticsearchAnalyzerRequest;
 use crate::elasticsearch::cat::ElasticsearchCatRequest;
+use crate::elasticsearch::close_pit::ElasticsearchClosePitRequest;
 use crate::elasticsearch::count::ElasticsearchCountRequest;
+use crate::elasticsearch::count_batch::ElasticsearchCountBatchRequest;
 use crate::elasticsearch::delete_index::ElasticsearchDeleteIndexRequest;
+use crate::elasticsearch::disk_usage::ElasticsearchDiskUsageRequest;
+use crate::elasticsearch::explain_document::ElasticsearchExplainDocumentRequest;
 use crate::elasticsearch::expunge_deletes::ElasticsearchExpungeDeletesRequest;
+use crate::elasticsearch::field_caps::ElasticsearchFieldCapsRequest;
 use crate::elasticsearch::get_document::ElasticsearchGetDocumentRequest;
 use crate::elasticsearch::get_mapping::ElasticsearchGetMappingRequest;
 use crate::elasticsearch::get_settings::ElasticsearchGetSettingsRequest;
+use crate::elasticsearch::index_exists::ElasticsearchIndexExistsRequest;
+use crate::elasticsearch::knn_search::ElasticsearchKnnSearchRequest;
+use crate::elasticsearch::open_pit::ElasticsearchOpenPitRequest;
 use crate::elasticsearch::pg_catalog::ArbitraryRequestType;
+use crate::elasticsearch::post_filter_search::ElasticsearchPostFilterSearchRequest;
 use crate::elasticsearch::profile_query::ElasticsearchProfileQueryRequest;
 use crate::elasticsearch::put_mapping::ElasticsearchPutMappingRequest;
 use crate::elasticsearch::refresh_index::ElasticsearchRefreshIndexRequest;
+use crate::elasticsearch::reindex::ElasticsearchReindexRequest;
+use crate::elasticsearch::reload_search_analyzers::ElasticsearchReloadSearchAnalyzersRequest;
+use crate::elasticsearch::script_field_search::ElasticsearchScriptFieldSearchRequest;
 use crate::elasticsearch::search::ElasticsearchSearchRequest;
 use crate::elasticsearch::suggest_term::ElasticsearchSuggestTermRequest;
+use crate::elasticsearch::update_document::ElasticsearchUpdateDocumentRequest;
 use crate::elasticsearch::update_settings::ElasticsearchUpdateSettingsRequest;
+use crate::elasticsearch::validate_query::ElasticsearchValidateQueryRequest;
+use crate::elasticsearch::wait_for_stable::ElasticsearchWaitForStableRequest;
 use crate::executor_manager::get_executor_manager;
 use crate::utils::is_nested_field;
 use crate::zdbquery::ZDBPreparedQuery;
 pub use bulk::*;
 pub use create_index::*;
-use lazy_static::*;
 use pgx::*;
 use serde::de::DeserializeOwned;
+use serde::Deserialize;
 use serde_json::json;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::io::Read;
 
-lazy_static! {
-    static ref NUM_CPUS: usize = num_cpus::get();
-}
-
 pub mod pg_catalog {
     use pgx::*;
     use serde::Serialize;
@@ -142,6 +169,78 @@ impl Elasticsearch {
         &AGENT
     }
 
+    /// Returns a new, unique request id if this index is configured to trace its requests.  The
+    /// id is logged at DEBUG1 so that it can be correlated with Elasticsearch's logs
+    pub fn request_id(&self) -> Option<String> {
+        if !self.options.trace_requests() {
+            return None;
+        }
+
+        let request_id = generate_request_id();
+        debug1!(
+            "zombodb request id for index {}: {}",
+            self.index_name(),
+            request_id
+        );
+        Some(request_id)
+    }
+
+    /// Sets the `X-Request-Id` header on the request, if this index traces its requests
+    pub fn traced(&self, request: ureq::Request) -> ureq::Request {
+        match self.request_id() {
+            Some(request_id) => request.set("X-Request-Id", &request_id),
+            None => request,
+        }
+    }
+
+    /// Applies this index's `request_timeout` to the request, if it has one
+    pub fn timed(&self, request: ureq::Request) -> ureq::Request {
+        match self.options.request_timeout() {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        }
+    }
+
+    /// A query string parameter that lets searches see this index when it's `search_throttled`,
+    /// as Elasticsearch skips throttled indices by default
+    pub fn ignore_throttled_param(&self) -> &'static str {
+        if self.options.search_throttled() {
+            "&ignore_throttled=false"
+        } else {
+            ""
+        }
+    }
+
+    /// A query string parameter with this index's `search_preference`, if it has one
+    pub fn preference_param(&self) -> String {
+        match self.options.search_preference() {
+            Some(preference) => format!(
+                "&preference={}",
+                url::form_urlencoded::byte_serialize(preference.as_bytes()).collect::<String>()
+            ),
+            None => String::new(),
+        }
+    }
+
+    /// A query string parameter routing the document `id` to its shard, which partitioned indexes
+    /// (see the `routing_partition_size` index option) require.  Documents are routed by their own
+    /// `_id`, which is also Elasticsearch's default routing, so the shard is the same either way
+    pub fn routing_param(&self, id: &str) -> String {
+        if self.options.routing_partition_size() > 1 {
+            format!("&routing={}", id)
+        } else {
+            String::new()
+        }
+    }
+
+    /// The value for ES' `index.indexing.slowlog.source` setting, where `true` means the entire _source
+    fn slowlog_source_setting(&self) -> Value {
+        match self.options.slowlog_source_length() {
+            -1 => json!(true),
+            length => json!(length),
+        }
+    }
+
     pub fn arbitrary_request(
         &self,
         method: ArbitraryRequestType,
@@ -213,10 +312,35 @@ impl Elasticsearch {
         ElasticsearchDeleteIndexRequest::new(self)
     }
 
+    pub fn disk_usage(&self) -> ElasticsearchDiskUsageRequest {
+        ElasticsearchDiskUsageRequest::new(self)
+    }
+
+    pub fn field_caps(&self, fields: Vec<String>) -> ElasticsearchFieldCapsRequest {
+        ElasticsearchFieldCapsRequest::new(self, fields)
+    }
+
+    pub fn index_exists(&self) -> ElasticsearchIndexExistsRequest {
+        ElasticsearchIndexExistsRequest::new(self)
+    }
+
     pub fn refresh_index(&self) -> ElasticsearchRefreshIndexRequest {
         ElasticsearchRefreshIndexRequest::new(self)
     }
 
+    pub fn reindex(
+        &self,
+        dest: &Elasticsearch,
+        script: Option<String>,
+    ) -> ElasticsearchReindexRequest {
+        get_executor_manager().wait_for_completion();
+        ElasticsearchReindexRequest::new(self, dest, script)
+    }
+
+    pub fn reload_search_analyzers(&self) -> ElasticsearchReloadSearchAnalyzersRequest {
+        ElasticsearchReloadSearchAnalyzersRequest::new(self)
+    }
+
     pub fn add_alias(&self, alias_name: &str) -> ElasticsearchAliasRequest {
         ElasticsearchAliasRequest::add(self, alias_name)
     }
@@ -245,9 +369,21 @@ impl Elasticsearch {
         ElasticsearchProfileQueryRequest::new(self, query)
     }
 
+    pub fn explain_document(
+        &self,
+        id: u64,
+        query: ZDBPreparedQuery,
+    ) -> ElasticsearchExplainDocumentRequest {
+        ElasticsearchExplainDocumentRequest::new(self, id, query)
+    }
+
+    pub fn validate_query(&self, query: ZDBPreparedQuery) -> ElasticsearchValidateQueryRequest {
+        ElasticsearchValidateQueryRequest::new(self, query)
+    }
+
     pub fn start_bulk(&self) -> ElasticsearchBulkRequest {
-        let concurrency = (self.options.shards() as usize)
-            .min(NUM_CPUS.min(self.options.bulk_concurrency() as usize));
+        let concurrency =
+            (self.options.shards() as usize).min(self.options.bulk_concurrency() as usize);
         ElasticsearchBulkRequest::new(
             self,
             10_000,
@@ -261,6 +397,24 @@ impl Elasticsearch {
         ElasticsearchSearchRequest::new(self, query)
     }
 
+    pub fn open_pit_search(
+        &self,
+        query: ZDBPreparedQuery,
+        pit_id: &str,
+    ) -> ElasticsearchSearchRequest {
+        get_executor_manager().wait_for_completion();
+        ElasticsearchSearchRequest::with_pit(self, query, pit_id)
+    }
+
+    pub fn open_pit(&self, keepalive: &str) -> ElasticsearchOpenPitRequest {
+        get_executor_manager().wait_for_completion();
+        ElasticsearchOpenPitRequest::new(self, keepalive)
+    }
+
+    pub fn close_pit(&self, pit_id: &str) -> ElasticsearchClosePitRequest {
+        ElasticsearchClosePitRequest::new(self, pit_id)
+    }
+
     pub fn arbitrary_aggregate<T: DeserializeOwned>(
         &self,
         field_name: Option<String>,
@@ -363,6 +517,43 @@ impl Elasticsearch {
         ElasticsearchCountRequest::new(self, query, true)
     }
 
+    pub fn post_filter_search(
+        &self,
+        query: ZDBPreparedQuery,
+        post_filter: ZDBPreparedQuery,
+        limit: u64,
+    ) -> ElasticsearchPostFilterSearchRequest {
+        get_executor_manager().wait_for_completion();
+        ElasticsearchPostFilterSearchRequest::new(self, query, post_filter, limit)
+    }
+
+    pub fn script_field_search(
+        &self,
+        query: ZDBPreparedQuery,
+        field_name: String,
+        script: String,
+        limit: u64,
+    ) -> ElasticsearchScriptFieldSearchRequest {
+        get_executor_manager().wait_for_completion();
+        ElasticsearchScriptFieldSearchRequest::new(self, query, field_name, script, limit)
+    }
+
+    pub fn count_batch(&self, queries: Vec<ZDBPreparedQuery>) -> ElasticsearchCountBatchRequest {
+        get_executor_manager().wait_for_completion();
+        ElasticsearchCountBatchRequest::new(self, queries)
+    }
+
+    pub fn knn_search(
+        &self,
+        field: String,
+        query_vector: Vec<f64>,
+        k: u64,
+        num_candidates: u64,
+    ) -> ElasticsearchKnnSearchRequest {
+        get_executor_manager().wait_for_completion();
+        ElasticsearchKnnSearchRequest::new(self, field, query_vector, k, num_candidates)
+    }
+
     pub fn suggest_terms(
         &self,
         query: ZDBPreparedQuery,
@@ -381,6 +572,10 @@ impl Elasticsearch {
         ElasticsearchGetDocumentRequest::<T>::new(self, id, realtime)
     }
 
+    pub fn update_document(&self, id: u64, partial: Value) -> ElasticsearchUpdateDocumentRequest {
+        ElasticsearchUpdateDocumentRequest::new(self, id, partial)
+    }
+
     pub fn get_mapping(&self) -> ElasticsearchGetMappingRequest {
         ElasticsearchGetMappingRequest::new(self)
     }
@@ -389,6 +584,10 @@ impl Elasticsearch {
         ElasticsearchGetSettingsRequest::new(self)
     }
 
+    pub fn wait_for_stable(&self, timeout: &str) -> ElasticsearchWaitForStableRequest {
+        ElasticsearchWaitForStableRequest::new(self, timeout)
+    }
+
     pub fn url(&self) -> &str {
         self.options.url()
     }
@@ -413,6 +612,25 @@ impl Elasticsearch {
         self.options.type_name()
     }
 
+    /// The version number of the Elasticsearch cluster, such as "7.13.2"
+    pub fn cluster_version(&self) -> std::result::Result<String, ElasticsearchError> {
+        #[derive(Deserialize)]
+        struct Version {
+            number: String,
+        }
+
+        #[derive(Deserialize)]
+        struct ClusterInfo {
+            version: Version,
+        }
+
+        Elasticsearch::execute_json_request(Elasticsearch::client().get(self.url()), None, |body| {
+            let info: ClusterInfo =
+                serde_json::from_reader(body).expect("failed to parse cluster info response");
+            Ok(info.version.number)
+        })
+    }
+
     pub fn execute_request<F, R, Reader: std::io::Read>(
         request: ureq::Request,
         post_data: Reader,
@@ -448,6 +666,7 @@ impl Elasticsearch {
     where
         F: FnOnce(&mut (dyn Read + Send)) -> std::result::Result<R, ElasticsearchError>,
     {
+        stats::increment(&stats::REQUESTS);
         match response {
             // the request was processed by ES, but maybe not successfully
             Ok(response) => {
@@ -540,3 +759,104 @@ fn request(
         Err(e) => panic!("{:?}", e),
     }
 }
+
+/// Is the Elasticsearch `version` string at least `major.minor`?
+pub fn version_at_least(version: &str, major: u32, minor: u32) -> bool {
+    let mut parts = version
+        .split('.')
+        .map(|part| part.parse::<u32>().unwrap_or(0));
+
+    (parts.next().unwrap_or(0), parts.next().unwrap_or(0)) >= (major, minor)
+}
+
+/// Generates a random (version 4) UUID string
+fn generate_request_id() -> String {
+    use std::hash::{BuildHasher, Hasher};
+
+    // each RandomState is seeded with different random keys, which gives us the randomness
+    // we need without another dependency
+    let mut bytes = [0u8; 16];
+    for chunk in bytes.chunks_mut(8) {
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u128(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos(),
+        );
+        chunk.copy_from_slice(&hasher.finish().to_be_bytes());
+    }
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use crate::elasticsearch::{version_at_least, Elasticsearch};
+    use pgx::*;
+
+    #[pg_test]
+    fn test_version_at_least() {
+        assert!(version_at_least("7.15.0", 7, 15));
+        assert!(version_at_least("8.4.3", 7, 15));
+        assert!(version_at_least("8.0.0-rc1", 8, 0));
+        assert!(!version_at_least("7.13.2", 7, 15));
+        assert!(!version_at_least("6.8.23", 7, 15));
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_request_id_when_tracing() {
+        Spi::run(
+            "CREATE TABLE test_trace_requests();
+        CREATE INDEX idxtest_trace_requests
+                  ON test_trace_requests
+               USING zombodb ((test_trace_requests.*)) WITH (url='http://localhost:19200/', trace_requests=true);",
+        );
+
+        let index = PgRelation::open_with_name("idxtest_trace_requests").expect("no such relation");
+        let es = Elasticsearch::new(&index);
+
+        // searches are traced request by request, each with its own id
+        let first = es.traced(Elasticsearch::client().post("http://localhost:19200/_search"));
+        let second = es.traced(Elasticsearch::client().post("http://localhost:19200/_search"));
+        let first_id = first
+            .header("X-Request-Id")
+            .expect("no X-Request-Id header");
+        let second_id = second
+            .header("X-Request-Id")
+            .expect("no X-Request-Id header");
+
+        assert_eq!(first_id.len(), 36);
+        assert_ne!(first_id, second_id);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_no_request_id_without_tracing() {
+        Spi::run(
+            "CREATE TABLE test_trace_requests();
+        CREATE INDEX idxtest_trace_requests
+                  ON test_trace_requests
+               USING zombodb ((test_trace_requests.*)) WITH (url='http://localhost:19200/');",
+        );
+
+        let index = PgRelation::open_with_name("idxtest_trace_requests").expect("no such relation");
+        let request = Elasticsearch::new(&index)
+            .traced(Elasticsearch::client().post("http://localhost:19200/_search"));
+        assert_eq!(request.header("X-Request-Id"), None);
+    }
+}
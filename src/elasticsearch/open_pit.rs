@@ -0,0 +1,48 @@
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use pgx::*;
+use serde::*;
+
+pub struct ElasticsearchOpenPitRequest {
+    elasticsearch: Elasticsearch,
+    keepalive: String,
+}
+
+impl ElasticsearchOpenPitRequest {
+    pub fn new(elasticsearch: &Elasticsearch, keepalive: &str) -> Self {
+        ElasticsearchOpenPitRequest {
+            elasticsearch: elasticsearch.clone(),
+            keepalive: keepalive.to_string(),
+        }
+    }
+
+    pub fn execute(self) -> Result<String, ElasticsearchError> {
+        let url = format!(
+            "{}/_pit?keep_alive={}",
+            self.elasticsearch.base_url(),
+            self.keepalive
+        );
+
+        Elasticsearch::execute_json_request(Elasticsearch::client().post(&url), None, |body| {
+            #[derive(Deserialize)]
+            struct OpenPitResponse {
+                id: String,
+            }
+
+            let response: OpenPitResponse = serde_json::from_reader(body)
+                .expect("failed to deserialize open point-in-time response");
+            Ok(response.id)
+        })
+    }
+}
+
+/// Opens an Elasticsearch point-in-time against the index and returns its id.  Searches made with
+/// that id, such as through `zdb.pit_search()`, see the index as it was when the point-in-time
+/// was opened.  Those searches don't extend its lifetime, so `keepalive` needs to be long enough
+/// to cover all of them
+#[pg_extern(volatile, parallel_safe)]
+fn open_pit(index: PgRelation, keepalive: default!(&str, "'1m'")) -> String {
+    Elasticsearch::new(&index)
+        .open_pit(keepalive)
+        .execute()
+        .unwrap_or_else(|e| panic!("{}", e))
+}
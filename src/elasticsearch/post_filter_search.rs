@@ -0,0 +1,137 @@
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use crate::zdbquery::mvcc::apply_visibility_clause;
+use crate::zdbquery::{ZDBPreparedQuery, ZDBQuery};
+use pgx::*;
+use serde::*;
+use serde_json::*;
+
+pub struct ElasticsearchPostFilterSearchRequest {
+    elasticsearch: Elasticsearch,
+    query: ZDBPreparedQuery,
+    post_filter: ZDBPreparedQuery,
+    limit: u64,
+}
+
+impl ElasticsearchPostFilterSearchRequest {
+    pub fn new(
+        elasticsearch: &Elasticsearch,
+        query: ZDBPreparedQuery,
+        post_filter: ZDBPreparedQuery,
+        limit: u64,
+    ) -> Self {
+        ElasticsearchPostFilterSearchRequest {
+            elasticsearch: elasticsearch.clone(),
+            query,
+            post_filter,
+            limit,
+        }
+    }
+
+    /// Returns the `zdb_ctid` of each hit, in descending score order
+    pub fn execute(self) -> std::result::Result<Vec<u64>, ElasticsearchError> {
+        let body = json! {
+            {
+                "query": apply_visibility_clause(&self.elasticsearch, self.query, false),
+                "post_filter": self.post_filter.take_query_dsl(),
+                "size": self.limit,
+                "_source": false,
+                "stored_fields": "_none_",
+                "docvalue_fields": ["zdb_ctid"]
+            }
+        };
+
+        let mut url = self.elasticsearch.alias_url();
+        url.push_str("/_search?filter_path=hits.hits.fields.zdb_ctid");
+        if self.elasticsearch.options.search_throttled() {
+            url.push_str("&ignore_throttled=false");
+        }
+        Elasticsearch::execute_json_request(
+            Elasticsearch::client().post(&url),
+            Some(body),
+            |body| {
+                #[derive(Deserialize)]
+                struct Fields {
+                    zdb_ctid: Option<[u64; 1]>,
+                }
+
+                #[derive(Deserialize)]
+                struct Hit {
+                    fields: Option<Fields>,
+                }
+
+                #[derive(Deserialize, Default)]
+                struct Hits {
+                    hits: Vec<Hit>,
+                }
+
+                #[derive(Deserialize)]
+                struct PostFilterSearchResponse {
+                    // filter_path drops "hits" entirely when nothing matched
+                    #[serde(default)]
+                    hits: Hits,
+                }
+
+                let response: PostFilterSearchResponse = serde_json::from_reader(body)
+                    .expect("failed to deserialize post filter search response");
+                Ok(response
+                    .hits
+                    .hits
+                    .into_iter()
+                    // the "zdb_aborted_xids" document doesn't have a zdb_ctid
+                    .filter_map(|hit| hit.fields.and_then(|fields| fields.zdb_ctid))
+                    .map(|ctid| ctid[0])
+                    .collect())
+            },
+        )
+    }
+}
+
+/// Returns the ctids of up to `limit` documents matching `query` that also match `post_filter`.
+/// Elasticsearch applies the `post_filter` after the query, so it doesn't affect aggregations
+#[pg_extern(volatile, parallel_safe)]
+fn search_post_filter(
+    index: PgRelation,
+    query: &str,
+    post_filter: &str,
+    limit: i32,
+) -> impl std::iter::Iterator<Item = pg_sys::ItemPointerData> {
+    if limit < 0 {
+        panic!("limit must not be negative");
+    }
+
+    let (query, _) = ZDBQuery::from_str(query).prepare(&index, None);
+    let (post_filter, _) = ZDBQuery::from_str(post_filter).prepare(&index, None);
+    let ctids = Elasticsearch::new(&index)
+        .post_filter_search(query, post_filter, limit as u64)
+        .execute()
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    let mut tids = Vec::new();
+    for ctid in ctids {
+        let mut ipd = pg_sys::ItemPointerData::default();
+        u64_to_item_pointer(ctid, &mut ipd);
+
+        tids.push(ipd);
+    }
+    tids.into_iter()
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_search_post_filter() {
+        Spi::run("CREATE TABLE test_post_filter AS SELECT n, n % 2 = 0 AS even FROM generate_series(1, 20) n;");
+        Spi::run("CREATE INDEX idxtest_post_filter ON test_post_filter USING zombodb ((test_post_filter.*));");
+
+        let ns = Spi::get_one::<Vec<i32>>(
+            "SELECT array_agg(t.n ORDER BY t.n)
+               FROM zdb.search_post_filter('idxtest_post_filter', 'n:[1 TO 10]', 'even:true', 100) s(tid)
+               JOIN test_post_filter t ON t.ctid = s.tid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(ns, vec![2, 4, 6, 8, 10]);
+    }
+}
@@ -17,9 +17,23 @@ impl ElasticsearchProfileQueryRequest {
     }
 
     pub fn execute(self) -> std::result::Result<serde_json::Value, ElasticsearchError> {
+        self.execute_with_filter_path("profile", false)
+    }
+
+    /// Like `execute()`, but the response also includes ES' `took` time and the total hit count
+    pub fn execute_with_stats(self) -> std::result::Result<serde_json::Value, ElasticsearchError> {
+        self.execute_with_filter_path("took,hits.total,profile", true)
+    }
+
+    fn execute_with_filter_path(
+        self,
+        filter_path: &str,
+        track_total_hits: bool,
+    ) -> std::result::Result<serde_json::Value, ElasticsearchError> {
         let body = json! {
             {
                 "profile": true,
+                "track_total_hits": track_total_hits,
                 "query": self.query.query_dsl()
             }
         };
@@ -28,7 +42,8 @@ impl ElasticsearchProfileQueryRequest {
         url.push_str(&self.elasticsearch.base_url());
         url.push_str("/_search");
         url.push_str("?size=0");
-        url.push_str("&filter_path=profile");
+        url.push_str("&filter_path=");
+        url.push_str(filter_path);
         Elasticsearch::execute_json_request(
             Elasticsearch::client().post(&url),
             Some(body),
@@ -46,3 +61,43 @@ fn profile_query(index: PgRelation, query: ZDBQuery) -> JsonB {
             .expect("failed to execute profile query request"),
     )
 }
+
+#[pg_extern(volatile, parallel_safe)]
+fn explain_analyze(index: PgRelation, query: &str) -> JsonB {
+    let (prepared_query, _) = ZDBQuery::from_str(query).prepare(&index, None);
+    let start = std::time::Instant::now();
+    let mut response = Elasticsearch::new(&index)
+        .profile_query(prepared_query)
+        .execute_with_stats()
+        .expect("failed to execute profile query request");
+    let round_trip_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    JsonB(json! {
+        {
+            "es_took_ms": response["took"],
+            "round_trip_ms": round_trip_ms,
+            "rows": response["hits"]["total"]["value"],
+            "profile": response["profile"].take()
+        }
+    })
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_explain_analyze() {
+        Spi::run("CREATE TABLE test_explain_analyze AS SELECT * FROM generate_series(1, 100);");
+        Spi::run("CREATE INDEX idxtest_explain_analyze ON test_explain_analyze USING zombodb ((test_explain_analyze.*));");
+        let result = Spi::get_one::<JsonB>(
+            "SELECT zdb.explain_analyze('idxtest_explain_analyze', 'generate_series < 50');",
+        )
+        .expect("failed to get SPI result");
+
+        assert!(result.0["es_took_ms"].is_number());
+        assert!(result.0["profile"].is_object());
+        assert_eq!(result.0["rows"], serde_json::json!(49));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use pgx::*;
 use serde_json::json;
 
 pub struct ElasticsearchPutMappingRequest {
@@ -28,3 +29,91 @@ impl ElasticsearchPutMappingRequest {
         })
     }
 }
+
+/// Applies the field mappings of the `src` index to the `dest` index.  Returns false if
+/// Elasticsearch rejects them because they conflict with `dest`'s existing mapping
+#[pg_extern(volatile, parallel_safe)]
+fn copy_mapping(src: PgRelation, dest: PgRelation) -> bool {
+    let src_columns = table_columns(&src);
+    let dest_columns = table_columns(&dest);
+    if src_columns != dest_columns {
+        warning!(
+            "the tables of {} and {} have different columns.  The mapping may not fit {}",
+            src.name(),
+            dest.name(),
+            dest.name()
+        );
+    }
+
+    let src_es = Elasticsearch::new(&src);
+    let mut mapping = src_es
+        .get_mapping()
+        .execute()
+        .expect("failed to get source index mapping");
+    let properties = mapping
+        .get_mut(src_es.index_name())
+        .and_then(|index| index.get_mut("mappings"))
+        .and_then(|mappings| mappings.get_mut("properties"))
+        .map(|properties| properties.take())
+        .expect("no properties object in source mapping");
+
+    match Elasticsearch::new(&dest).put_mapping(properties).execute() {
+        Ok(()) => true,
+
+        // ES rejects mappings that conflict with the ones already in the index
+        Err(e) if e.status() == Some(400) => {
+            warning!("failed to copy mapping to {}: {}", dest.name(), e.message());
+            false
+        }
+
+        Err(e) => panic!("failed to copy mapping: {}", e),
+    }
+}
+
+/// The name and type of each column of the index's table
+fn table_columns(index: &PgRelation) -> Vec<(String, pg_sys::Oid)> {
+    index
+        .heap_relation()
+        .expect("not an index")
+        .tuple_desc()
+        .iter()
+        .filter(|att| !att.is_dropped())
+        .map(|att| (att.name().to_owned(), att.type_oid().value()))
+        .collect()
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use crate::access_method::options::ZDBIndexOptions;
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_copy_mapping() {
+        Spi::run("CREATE TABLE test_copy_mapping_src(title text, body text);");
+        Spi::run("CREATE INDEX idxtest_copy_mapping_src ON test_copy_mapping_src USING zombodb ((test_copy_mapping_src.*));");
+        Spi::run("CREATE TABLE test_copy_mapping_dest(title text);");
+        Spi::run("CREATE INDEX idxtest_copy_mapping_dest ON test_copy_mapping_dest USING zombodb ((test_copy_mapping_dest.*));");
+
+        let copied = Spi::get_one::<bool>(
+            "SELECT zdb.copy_mapping('idxtest_copy_mapping_src', 'idxtest_copy_mapping_dest');",
+        )
+        .expect("failed to get SPI result");
+        assert!(copied);
+
+        let properties = |index_name: &str| {
+            let index = PgRelation::open_with_name(index_name).expect("no such relation");
+            let options = ZDBIndexOptions::from_relation(&index);
+            let mapping =
+                Spi::get_one::<JsonB>(&format!("SELECT zdb.index_mapping('{}');", index_name))
+                    .expect("failed to get SPI result")
+                    .0;
+            mapping[options.index_name()]["mappings"]["properties"].clone()
+        };
+
+        assert_eq!(
+            properties("idxtest_copy_mapping_dest"),
+            properties("idxtest_copy_mapping_src")
+        );
+    }
+}
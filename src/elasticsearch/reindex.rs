@@ -0,0 +1,148 @@
+use crate::access_method::options::ZDBIndexOptions;
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use pgx::*;
+use serde::*;
+use serde_json::*;
+
+pub struct ElasticsearchReindexRequest {
+    elasticsearch: Elasticsearch,
+    dest: Elasticsearch,
+    script: Option<String>,
+}
+
+impl ElasticsearchReindexRequest {
+    pub fn new(
+        elasticsearch: &Elasticsearch,
+        dest: &Elasticsearch,
+        script: Option<String>,
+    ) -> Self {
+        ElasticsearchReindexRequest {
+            elasticsearch: elasticsearch.clone(),
+            dest: dest.clone(),
+            script,
+        }
+    }
+
+    /// Returns the number of documents copied into the destination index
+    pub fn execute(self) -> std::result::Result<u64, ElasticsearchError> {
+        // the destination index has its own "zdb_aborted_xids" document, which we mustn't overwrite
+        let mut body = json! {
+            {
+                "source": {
+                    "index": self.elasticsearch.index_name(),
+                    "query": {
+                        "bool": {
+                            "must_not": [{ "ids": { "values": ["zdb_aborted_xids"] } }]
+                        }
+                    }
+                },
+                "dest": { "index": self.dest.index_name() }
+            }
+        };
+        if let Some(script) = self.script {
+            body["script"] = json!({ "source": script, "lang": "painless" });
+        }
+
+        let url = format!("{}_reindex?refresh=true", self.elasticsearch.url());
+        Elasticsearch::execute_json_request(
+            Elasticsearch::client().post(&url),
+            Some(body),
+            |body| {
+                #[derive(Deserialize)]
+                struct ReindexResponse {
+                    created: u64,
+                    updated: u64,
+                    failures: Vec<Value>,
+                }
+
+                let response: ReindexResponse =
+                    serde_json::from_reader(body).expect("failed to parse _reindex response");
+
+                // documents that failed to be copied don't fail the whole request
+                if !response.failures.is_empty() {
+                    return Err(ElasticsearchError(
+                        None,
+                        serde_json::to_string_pretty(&response.failures).unwrap(),
+                    ));
+                }
+                Ok(response.created + response.updated)
+            },
+        )
+    }
+}
+
+/// Copies every document in the `src` index to the `dest` index with Elasticsearch's `_reindex`
+/// API, transforming each one with the Painless `script`.  Returns the number of documents copied.
+/// The copied documents don't correspond to rows in `dest`'s table, so `dest` must be a low-level
+/// API index
+#[pg_extern(volatile, parallel_safe)]
+fn reindex_with_script(src: PgRelation, dest: PgRelation, script: &str) -> i64 {
+    let dest_options = ZDBIndexOptions::from_relation(&dest);
+    if script.trim().is_empty() {
+        panic!("script cannot be empty");
+    } else if !dest_options.llapi() {
+        panic!(
+            "{} is not a low-level API index.  Set llapi=true to use it as the destination of zdb.reindex_with_script()",
+            dest.name()
+        );
+    }
+
+    let src = Elasticsearch::new(&src);
+    let dest = Elasticsearch::from_options(dest_options);
+    if src.url() != dest.url() {
+        panic!("src and dest indexes must be on the same Elasticsearch cluster");
+    }
+
+    src.reindex(&dest, Some(script.to_owned()))
+        .execute()
+        .unwrap_or_else(|e| panic!("{}", e)) as i64
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_reindex_with_script() {
+        Spi::run("CREATE TABLE test_reindex_src AS SELECT 'hello world ' || n AS title FROM generate_series(1, 3) n;");
+        Spi::run("CREATE INDEX idxtest_reindex_src ON test_reindex_src USING zombodb ((test_reindex_src.*));");
+        Spi::run("CREATE TABLE test_reindex_dest (headline text);");
+        Spi::run("CREATE INDEX idxtest_reindex_dest ON test_reindex_dest USING zombodb ((test_reindex_dest.*)) WITH (llapi=true);");
+
+        let copied = Spi::get_one::<i64>(
+            "SELECT zdb.reindex_with_script('idxtest_reindex_src', 'idxtest_reindex_dest', 'ctx._source.headline = ctx._source.remove(''title'')');",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(copied, 3);
+
+        let renamed =
+            Spi::get_one::<i64>("SELECT zdb.count('idxtest_reindex_dest', 'headline:hello');")
+                .expect("failed to get SPI result");
+        let original =
+            Spi::get_one::<i64>("SELECT zdb.count('idxtest_reindex_dest', 'title:hello');")
+                .expect("failed to get SPI result");
+        assert_eq!(renamed, 3);
+        assert_eq!(original, 0);
+    }
+
+    #[pg_test(error = "script cannot be empty")]
+    #[initialize(es = true)]
+    fn test_reindex_with_empty_script() {
+        Spi::run("CREATE TABLE test_reindex (title text);");
+        Spi::run("CREATE INDEX idxtest_reindex ON test_reindex USING zombodb ((test_reindex.*));");
+        Spi::run("SELECT zdb.reindex_with_script('idxtest_reindex', 'idxtest_reindex', '  ');");
+    }
+
+    #[pg_test(
+        error = "idxtest_reindex_dest is not a low-level API index.  Set llapi=true to use it as the destination of zdb.reindex_with_script()"
+    )]
+    #[initialize(es = true)]
+    fn test_reindex_with_script_requires_llapi() {
+        Spi::run("CREATE TABLE test_reindex_src (title text);");
+        Spi::run("CREATE INDEX idxtest_reindex_src ON test_reindex_src USING zombodb ((test_reindex_src.*));");
+        Spi::run("CREATE TABLE test_reindex_dest (title text);");
+        Spi::run("CREATE INDEX idxtest_reindex_dest ON test_reindex_dest USING zombodb ((test_reindex_dest.*));");
+        Spi::run("SELECT zdb.reindex_with_script('idxtest_reindex_src', 'idxtest_reindex_dest', 'ctx._source.title = ''x''');");
+    }
+}
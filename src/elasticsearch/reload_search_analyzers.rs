@@ -0,0 +1,50 @@
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use pgx::*;
+use serde_json::Value;
+
+pub struct ElasticsearchReloadSearchAnalyzersRequest(Elasticsearch);
+
+impl ElasticsearchReloadSearchAnalyzersRequest {
+    pub fn new(elasticsearch: &Elasticsearch) -> Self {
+        ElasticsearchReloadSearchAnalyzersRequest(elasticsearch.clone())
+    }
+
+    pub fn execute(self) -> std::result::Result<Value, ElasticsearchError> {
+        Elasticsearch::execute_json_request(
+            Elasticsearch::client()
+                .post(&format!("{}/_reload_search_analyzers", self.0.base_url())),
+            None,
+            |body| Ok(serde_json::from_reader(body).expect("failed to parse json response")),
+        )
+    }
+}
+
+/// Reloads the index's search analyzers, so that changes to the synonyms files used by
+/// `updatable_synonyms` filters take effect
+#[pg_extern(volatile, parallel_safe)]
+fn reload_analyzers(index: PgRelation) -> JsonB {
+    JsonB(
+        Elasticsearch::new(&index)
+            .reload_search_analyzers()
+            .execute()
+            .expect("failed to reload search analyzers"),
+    )
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_reload_analyzers() {
+        Spi::run("CREATE TABLE test_reload_analyzers AS SELECT * FROM generate_series(1, 10);");
+        Spi::run("CREATE INDEX idxtest_reload_analyzers ON test_reload_analyzers USING zombodb ((test_reload_analyzers.*)) WITH (updatable_synonyms = true);");
+
+        let response =
+            Spi::get_one::<JsonB>("SELECT zdb.reload_analyzers('idxtest_reload_analyzers');")
+                .expect("failed to get SPI result");
+
+        assert!(response.0["reload_details"].is_array());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use crate::zdbquery::mvcc::apply_visibility_clause;
+use crate::zdbquery::{ZDBPreparedQuery, ZDBQuery};
+use pgx::*;
+use serde::*;
+use serde_json::*;
+use std::collections::HashMap;
+
+pub struct ElasticsearchScriptFieldSearchRequest {
+    elasticsearch: Elasticsearch,
+    query: ZDBPreparedQuery,
+    field_name: String,
+    script: String,
+    limit: u64,
+}
+
+impl ElasticsearchScriptFieldSearchRequest {
+    pub fn new(
+        elasticsearch: &Elasticsearch,
+        query: ZDBPreparedQuery,
+        field_name: String,
+        script: String,
+        limit: u64,
+    ) -> Self {
+        ElasticsearchScriptFieldSearchRequest {
+            elasticsearch: elasticsearch.clone(),
+            query,
+            field_name,
+            script,
+            limit,
+        }
+    }
+
+    /// Returns the `(zdb_ctid, computed value)` of each hit, in descending score order
+    pub fn execute(self) -> std::result::Result<Vec<(u64, Value)>, ElasticsearchError> {
+        let body = json! {
+            {
+                "query": apply_visibility_clause(&self.elasticsearch, self.query, false),
+                "size": self.limit,
+                "_source": false,
+                "stored_fields": "_none_",
+                "docvalue_fields": ["zdb_ctid"],
+                "script_fields": {
+                    &self.field_name: {
+                        "script": {
+                            "source": self.script,
+                            "lang": "painless"
+                        }
+                    }
+                }
+            }
+        };
+
+        let mut url = self.elasticsearch.alias_url();
+        url.push_str("/_search");
+        if self.elasticsearch.options.search_throttled() {
+            url.push_str("?ignore_throttled=false");
+        }
+        Elasticsearch::execute_json_request(
+            Elasticsearch::client().post(&url),
+            Some(body),
+            |body| {
+                #[derive(Deserialize)]
+                struct Hit {
+                    fields: HashMap<String, Value>,
+                }
+
+                #[derive(Deserialize)]
+                struct Hits {
+                    hits: Vec<Hit>,
+                }
+
+                #[derive(Deserialize)]
+                struct ScriptFieldSearchResponse {
+                    hits: Hits,
+                }
+
+                let response: ScriptFieldSearchResponse = serde_json::from_reader(body)
+                    .expect("failed to deserialize script field search response");
+
+                let mut results = Vec::new();
+                for mut hit in response.hits.hits {
+                    let ctid = match hit.fields.get("zdb_ctid").and_then(|ctid| ctid[0].as_u64()) {
+                        Some(ctid) => ctid,
+
+                        // this is the "zdb_aborted_xids" document
+                        None => continue,
+                    };
+
+                    // script field values are always returned as an array, but most scripts
+                    // compute a single value
+                    let computed = match hit.fields.remove(&self.field_name) {
+                        Some(Value::Array(mut values)) if values.len() == 1 => values.remove(0),
+                        Some(values) => values,
+                        None => Value::Null,
+                    };
+                    results.push((ctid, computed));
+                }
+                Ok(results)
+            },
+        )
+    }
+}
+
+/// Returns up to `limit` documents matching `query`, along with the value the Painless `script`
+/// computes for each one as the script field `field_name`
+#[pg_extern(volatile, parallel_safe)]
+fn search_with_script_field(
+    index: PgRelation,
+    query: &str,
+    field_name: &str,
+    script: &str,
+    limit: i32,
+) -> impl std::iter::Iterator<Item = (name!(ctid, pg_sys::ItemPointerData), name!(computed, JsonB))>
+{
+    if limit < 0 {
+        panic!("limit must not be negative");
+    }
+
+    let (prepared_query, _) = ZDBQuery::from_str(query).prepare(&index, None);
+    let hits = Elasticsearch::new(&index)
+        .script_field_search(
+            prepared_query,
+            field_name.to_owned(),
+            script.to_owned(),
+            limit as u64,
+        )
+        .execute()
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    let mut results = Vec::new();
+    for (tid, computed) in hits {
+        let mut ipd = pg_sys::ItemPointerData::default();
+        u64_to_item_pointer(tid, &mut ipd);
+
+        results.push((ipd, JsonB(computed)));
+    }
+    results.into_iter()
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_search_with_script_field() {
+        Spi::run(
+            "CREATE TABLE test_script_field AS SELECT * FROM (VALUES
+                (1, 2),
+                (3, 4)) t(a, b);",
+        );
+        Spi::run("CREATE INDEX idxtest_script_field ON test_script_field USING zombodb ((test_script_field.*));");
+
+        let sums = Spi::get_one::<Vec<i64>>(
+            "SELECT array_agg((s.computed #>> '{}')::bigint ORDER BY t.a)
+               FROM zdb.search_with_script_field('idxtest_script_field', '', 'total', 'doc[''a''].value * 10 + doc[''b''].value', 10) s
+               JOIN test_script_field t ON t.ctid = s.ctid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(sums, vec![12, 34]);
+    }
+}
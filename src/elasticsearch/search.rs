@@ -1,4 +1,4 @@
-use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use crate::elasticsearch::{stats, Elasticsearch, ElasticsearchError};
 use crate::gucs::ZDB_ACCELERATOR;
 use crate::utils::read_vlong;
 use crate::zdbquery::mvcc::apply_visibility_clause;
@@ -11,13 +11,17 @@ use std::convert::TryInto;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-const SEARCH_FILTER_PATH:&str = "_scroll_id,_shards.*,hits.total,hits.max_score,hits.hits._score,hits.hits.fields.*,hits.hits.highlight.*";
+const SEARCH_FILTER_PATH:&str = "_scroll_id,_shards.*,hits.total,hits.max_score,hits.hits._score,hits.hits.fields.*,hits.hits.highlight.*,hits.hits.matched_queries";
 const SEARCH_FILTER_PATH_NO_SCORE: &str =
-    "_scroll_id,_shards.*,hits.total,hits.hits.fields.*,hits.hits.highlight.*";
+    "_scroll_id,_shards.*,hits.total,hits.hits.fields.*,hits.hits.highlight.*,hits.hits.matched_queries";
+
+/// How long the point-in-time ZomboDB opens for a search_after scan stays open between pages
+const SCAN_PIT_KEEPALIVE: &str = "1m";
 
 pub struct ElasticsearchSearchRequest {
     elasticsearch: Elasticsearch,
     query: ZDBPreparedQuery,
+    pit_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -30,6 +34,11 @@ pub struct Fields {
     pub zdb_ctid: Option<[u64; 1]>,
     pub zdb_xmin: Option<[u64; 1]>,
     pub zdb_xmax: Option<[u64; 1]>,
+
+    /// the `_name`s of the named queries that matched this hit, which Elasticsearch returns
+    /// alongside the fields rather than within them
+    #[serde(skip)]
+    pub matched_queries: Option<Vec<String>>,
 }
 
 impl Default for Fields {
@@ -38,6 +47,7 @@ impl Default for Fields {
             zdb_ctid: None,
             zdb_xmin: None,
             zdb_xmax: None,
+            matched_queries: None,
         }
     }
 }
@@ -48,6 +58,8 @@ pub struct InnerHit {
     score: Option<f64>,
     fields: Option<Fields>,
     highlight: Option<HashMap<String, Vec<String>>>,
+    sort: Option<Vec<Value>>,
+    matched_queries: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -77,9 +89,16 @@ pub struct ElasticsearchSearchResponse {
     track_scores: bool,
     #[serde(skip)]
     should_sort_hits: bool,
+    /// the url and body to re-issue, with `search_after` set, to page in the next set of hits
+    #[serde(skip)]
+    search_after: Option<(String, Value)>,
+    /// did we open the point-in-time being paged through, and so need to close it when done?
+    #[serde(skip)]
+    close_pit: bool,
 
     #[serde(rename = "_scroll_id")]
     scroll_id: Option<String>,
+    pit_id: Option<String>,
     #[serde(rename = "_shards")]
     shards: Option<Shards>,
     hits: Option<Hits>,
@@ -98,7 +117,8 @@ impl InnerHit {
         Option<Fields>,
         Option<HashMap<String, Vec<String>>>,
     )> {
-        let fields = self.fields.unwrap_or_default();
+        let mut fields = self.fields.unwrap_or_default();
+        fields.matched_queries = self.matched_queries;
         let score = self.score.unwrap_or_default();
         let highlight = self.highlight;
         let ctid = fields.zdb_ctid.map_or(0, |v| v[0]);
@@ -118,11 +138,26 @@ impl ElasticsearchSearchRequest {
         ElasticsearchSearchRequest {
             elasticsearch: elasticsearch.clone(),
             query,
+            pit_id: None,
+        }
+    }
+
+    /// Search the point-in-time `pit_id`, as returned by `zdb.open_pit()`, rather than the live index
+    pub fn with_pit(elasticsearch: &Elasticsearch, query: ZDBPreparedQuery, pit_id: &str) -> Self {
+        ElasticsearchSearchRequest {
+            elasticsearch: elasticsearch.clone(),
+            query,
+            pit_id: Some(pit_id.to_string()),
         }
     }
 
     pub fn execute(self) -> std::result::Result<ElasticsearchSearchResponse, ElasticsearchError> {
-        ElasticsearchSearchRequest::initial_search(&self.elasticsearch, self.query, None)
+        ElasticsearchSearchRequest::initial_search(
+            &self.elasticsearch,
+            self.query,
+            self.pit_id.as_deref(),
+            None,
+        )
     }
 
     pub fn execute_with_fields(
@@ -132,6 +167,7 @@ impl ElasticsearchSearchRequest {
         ElasticsearchSearchRequest::initial_search(
             &self.elasticsearch,
             self.query,
+            self.pit_id.as_deref(),
             Some(extra_fields),
         )
     }
@@ -139,16 +175,45 @@ impl ElasticsearchSearchRequest {
     fn initial_search(
         elasticsearch: &Elasticsearch,
         query: ZDBPreparedQuery,
+        pit_id: Option<&str>,
         extra_fields: Option<Vec<&str>>,
     ) -> std::result::Result<ElasticsearchSearchResponse, ElasticsearchError> {
+        // a LIMIT larger than max_result_window can't be returned in one response, so rather than
+        // holding open a scroll context we page through the hits using search_after.  Scroll
+        // contexts can't be opened against a point-in-time, so those always use search_after
+        let max_result_window = elasticsearch.options.max_result_window() as u64;
+        let use_search_after = pit_id.is_some()
+            || (elasticsearch.options.auto_search_after()
+                && matches!(query.limit(), Some(limit) if limit > max_result_window));
+
+        // unlike a scroll, each search_after page sees the index as it is when the page is
+        // requested, so page through a point-in-time of our own.  Clusters too old to support
+        // them page through the live index instead
+        let scan_pit = if use_search_after && pit_id.is_none() {
+            elasticsearch.open_pit(SCAN_PIT_KEEPALIVE).execute().ok()
+        } else {
+            None
+        };
+        let pit_id = pit_id.or_else(|| scan_pit.as_deref());
+
         let mut should_sort_hits = false;
         let mut url = String::new();
-        url.push_str(&elasticsearch.base_url());
-        url.push_str("/_search");
+        if pit_id.is_some() {
+            // a point-in-time already knows its index, and Elasticsearch won't accept one in the url
+            url.push_str(elasticsearch.url());
+            url.push_str("_search");
+        } else {
+            url.push_str(&elasticsearch.base_url());
+            url.push_str("/_search");
+        }
         url.push_str("?search_type=query_then_fetch");
         url.push_str("&_source=false");
-        url.push_str("&scroll=10m");
         url.push_str("&stored_fields=_none_");
+        if pit_id.is_none() {
+            // nor will it accept index options or a preference
+            url.push_str(elasticsearch.ignore_throttled_param());
+            url.push_str(&elasticsearch.preference_param());
+        }
 
         // we always want the zdb_ctid field
         let have_extra_fields = extra_fields.is_some();
@@ -166,6 +231,16 @@ impl ElasticsearchSearchRequest {
             url.push_str(&format!("&filter_path={}", SEARCH_FILTER_PATH_NO_SCORE));
         }
 
+        if use_search_after {
+            url.push_str(",hits.hits.sort");
+            if pit_id.is_some() {
+                // each page can return a new id for the point-in-time, which the next page must use
+                url.push_str(",pit_id");
+            }
+        } else {
+            url.push_str("&scroll=10m");
+        }
+
         // how should we sort the results?
         let mut sort_json = query.sort_json().cloned();
 
@@ -181,13 +256,16 @@ impl ElasticsearchSearchRequest {
                     offset: None,
                     track_scores,
                     should_sort_hits,
+                    search_after: None,
+                    close_pit: false,
                     scroll_id: None,
+                    pit_id: None,
                     shards: None,
                     hits: None,
                     fast_terms: None,
                 });
             }
-            Some(limit) if limit <= elasticsearch.options.max_result_window() as u64 => {
+            Some(limit) if limit <= max_result_window => {
                 url.push_str(&format!("&size={}", limit));
                 // if we don't already have a sort_json, create one to
                 // order by _score desc
@@ -196,15 +274,25 @@ impl ElasticsearchSearchRequest {
                 }
             }
             _ => {
-                url.push_str(&format!(
-                    "&size={}",
-                    elasticsearch.options.max_result_window()
-                ));
+                url.push_str(&format!("&size={}", max_result_window));
             }
         }
 
         let have_user_sort = sort_json.is_some();
 
+        if use_search_after {
+            // search_after needs a total ordering of the hits, so break ties by the index's
+            // tiebreaker_field.  Its default of zdb_ctid also happens to be the index-organized
+            // order when there's no user sort
+            let mut sort = match sort_json.take() {
+                Some(Value::Array(sort)) => sort,
+                Some(sort) => vec![sort],
+                None => vec![],
+            };
+            sort.push(json!({ elasticsearch.options.tiebreaker_field(): "asc" }));
+            sort_json = Some(json!(sort));
+        }
+
         // if we made it this far and never set a sort, we'll hard-code
         // sorting in the index _doc order so that we return rows in heap
         // order (assuming the index was created with index.sort.field=zdb_ctid),
@@ -256,6 +344,9 @@ impl ElasticsearchSearchRequest {
 
             #[serde(skip_serializing_if = "Option::is_none")]
             highlight: Option<HashMap<&'static str, HashMap<String, Value>>>,
+
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pit: Option<Value>,
         }
 
         let limit = query.limit();
@@ -286,7 +377,8 @@ impl ElasticsearchSearchRequest {
             && min_score.is_none()
             && have_extra_fields == false
             && track_scores == false
-            && have_user_sort == false;
+            && have_user_sort == false
+            && pit_id.is_none();
 
         let body = Body {
             track_scores,
@@ -294,6 +386,21 @@ impl ElasticsearchSearchRequest {
             sort: sort_json,
             query: query_dsl,
             highlight,
+            pit: pit_id.map(|pit_id| {
+                if scan_pit.is_some() {
+                    // keep our own point-in-time open for as long as we keep paging through it
+                    json!({ "id": pit_id, "keep_alive": SCAN_PIT_KEEPALIVE })
+                } else {
+                    json!({ "id": pit_id })
+                }
+            }),
+        };
+
+        let body = json! { body };
+        let search_after = if use_search_after {
+            Some((url.clone(), body.clone()))
+        } else {
+            None
         };
 
         ElasticsearchSearchRequest::get_hits(
@@ -303,8 +410,19 @@ impl ElasticsearchSearchRequest {
             offset,
             elasticsearch,
             should_sort_hits,
-            json! { body },
+            body,
         )
+        .map(|mut response| {
+            response.search_after = search_after.map(|(url, mut body)| {
+                if let Some(pit_id) = response.pit_id.take() {
+                    body["pit"]["id"] = json!(pit_id);
+                }
+                (url, body)
+            });
+            response.close_pit = scan_pit.is_some();
+            response
+        })
+        .map_err(explain_too_many_clauses)
     }
 
     fn scroll(
@@ -348,13 +466,14 @@ impl ElasticsearchSearchRequest {
         should_sort_hits: bool,
         body: serde_json::Value,
     ) -> std::result::Result<ElasticsearchSearchResponse, ElasticsearchError> {
+        stats::increment(&stats::SEARCH_REQUESTS);
         if fast_terms {
             let mut url = String::new();
             url.push_str(&elasticsearch.base_url());
             url.push_str("/_fastterms");
 
             Elasticsearch::execute_json_request(
-                Elasticsearch::client().post(&url),
+                elasticsearch.traced(Elasticsearch::client().post(&url)),
                 Some(body),
                 |mut body| {
                     use byteorder::*;
@@ -380,7 +499,10 @@ impl ElasticsearchSearchRequest {
                         offset: None,
                         track_scores: false,
                         should_sort_hits: false,
+                        search_after: None,
+                        close_pit: false,
                         scroll_id: None,
+                        pit_id: None,
                         shards: None,
                         hits: None,
                         fast_terms: Some(fast_terms),
@@ -390,7 +512,7 @@ impl ElasticsearchSearchRequest {
         } else {
             url.push_str("&format=cbor");
             Elasticsearch::execute_json_request(
-                Elasticsearch::client().post(&url),
+                elasticsearch.traced(Elasticsearch::client().post(&url)),
                 Some(body),
                 |body| {
                     let mut response: ElasticsearchSearchResponse =
@@ -446,12 +568,15 @@ impl Scroller {
     fn new(
         orig_elasticsearch: Elasticsearch,
         orig_scroll_id: Option<String>,
+        search_after: Option<(String, Value)>,
+        close_pit: bool,
         mut initial_hits: Vec<InnerHit>,
         track_scores: bool,
         should_sort_hits: bool,
     ) -> Self {
         let (sender, receiver) = std::sync::mpsc::channel();
         let terminate_arc = Arc::new(AtomicBool::new(false));
+        let mut last_sort = initial_hits.last().and_then(|hit| hit.sort.clone());
 
         // spawn a thread to continually get the next scroll chunk from Elasticsearch
         // until there's no more to get
@@ -459,6 +584,51 @@ impl Scroller {
         let elasticsearch = orig_elasticsearch;
         let terminate = terminate_arc.clone();
         std::thread::spawn(move || {
+            // when paging with search_after, each request picks up after the last hit of the
+            // previous one, and we're done once Elasticsearch returns no more hits
+            if let Some((url, mut body)) = search_after {
+                while let Some(sort) = last_sort.take() {
+                    if terminate.load(Ordering::SeqCst) {
+                        break;
+                    }
+
+                    body["search_after"] = json!(sort);
+                    match ElasticsearchSearchRequest::get_hits(
+                        false,
+                        url.clone(),
+                        None,
+                        None,
+                        &elasticsearch,
+                        should_sort_hits,
+                        body.clone(),
+                    ) {
+                        Ok(mut response) => {
+                            if let Some(pit_id) = response.pit_id.take() {
+                                body["pit"]["id"] = json!(pit_id);
+                            }
+
+                            match response.hits.and_then(|hits| hits.hits) {
+                                Some(inner_hits) if !inner_hits.is_empty() => {
+                                    last_sort = inner_hits.last().and_then(|hit| hit.sort.clone());
+                                    if sender.send(inner_hits).is_err() {
+                                        break;
+                                    }
+                                }
+                                _ => break,
+                            }
+                        }
+                        Err(_) => break,
+                    }
+                }
+
+                if close_pit {
+                    if let Some(pit_id) = body["pit"]["id"].as_str() {
+                        // if this fails, the point-in-time expires on its own soon enough
+                        let _ = elasticsearch.close_pit(pit_id).execute();
+                    }
+                }
+            }
+
             while let Some(sid) = scroll_id {
                 if terminate.load(Ordering::SeqCst) {
                     break;
@@ -670,6 +840,8 @@ impl IntoIterator for ElasticsearchSearchResponse {
             let mut scroller = Scroller::new(
                 self.elasticsearch.expect("no elasticsearch"),
                 self.scroll_id,
+                self.search_after,
+                self.close_pit,
                 self.hits.unwrap().hits.unwrap_or_default(),
                 self.track_scores,
                 self.should_sort_hits,
@@ -694,10 +866,57 @@ impl IntoIterator for ElasticsearchSearchResponse {
     }
 }
 
+/// Elasticsearch's own "too_many_clauses" error doesn't say what to do about it, so replace it
+/// with one that does
+fn explain_too_many_clauses(e: ElasticsearchError) -> ElasticsearchError {
+    let message = e.message();
+    if message.contains("too_many_clauses")
+        || message.contains("too_many_nested_clauses")
+        || message.contains("maxClauseCount")
+    {
+        ElasticsearchError(
+            e.status(),
+            format!(
+                "query exceeds Elasticsearch's `indices.query.bool.max_clause_count` setting.  \
+                 Consider searching large lists of values with a single terms query, such as \
+                 dsl.terms(), or raising max_clause_count on the Elasticsearch cluster: {}",
+                message
+            ),
+        )
+    } else {
+        e
+    }
+}
+
 #[cfg(any(test, feature = "pg_test"))]
 mod tests {
+    use crate::elasticsearch::search::explain_too_many_clauses;
+    use crate::elasticsearch::ElasticsearchError;
     use pgx::*;
 
+    #[pg_test]
+    fn test_explain_too_many_clauses() {
+        let error = ElasticsearchError(
+            Some(400),
+            r#"{"error":{"root_cause":[{"type":"too_many_clauses","reason":"too_many_clauses: maxClauseCount is set to 1024"}]},"status":400}"#.into(),
+        );
+        let explained = explain_too_many_clauses(error);
+
+        assert_eq!(explained.status(), Some(400));
+        assert!(explained
+            .message()
+            .starts_with("query exceeds Elasticsearch's `indices.query.bool.max_clause_count` setting."));
+        assert!(explained.message().contains("dsl.terms()"));
+    }
+
+    #[pg_test]
+    fn test_explain_too_many_clauses_other_error() {
+        let error = ElasticsearchError(Some(404), "no such index".into());
+        let explained = explain_too_many_clauses(error);
+
+        assert_eq!(explained.message(), "no such index");
+    }
+
     #[pg_test]
     #[initialize(es = true)]
     fn test_limit_none() {
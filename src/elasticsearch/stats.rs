@@ -0,0 +1,53 @@
+use pgx::*;
+use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// process-local counters of the requests this backend has made to Elasticsearch.  These are
+// atomic because _bulk requests are made from background threads
+pub(crate) static REQUESTS: AtomicU64 = AtomicU64::new(0);
+pub(crate) static SEARCH_REQUESTS: AtomicU64 = AtomicU64::new(0);
+pub(crate) static BULK_REQUESTS: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn increment(counter: &AtomicU64) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+#[pg_extern(volatile, parallel_safe)]
+fn request_stats() -> JsonB {
+    JsonB(json! {
+        {
+            "requests": REQUESTS.load(Ordering::Relaxed),
+            "search_requests": SEARCH_REQUESTS.load(Ordering::Relaxed),
+            "bulk_requests": BULK_REQUESTS.load(Ordering::Relaxed)
+        }
+    })
+}
+
+#[pg_extern(volatile, parallel_safe)]
+fn reset_stats() {
+    REQUESTS.store(0, Ordering::Relaxed);
+    SEARCH_REQUESTS.store(0, Ordering::Relaxed);
+    BULK_REQUESTS.store(0, Ordering::Relaxed);
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_reset_stats() {
+        Spi::run("CREATE TABLE test_reset_stats AS SELECT * FROM generate_series(1, 10);");
+        Spi::run("CREATE INDEX idxtest_reset_stats ON test_reset_stats USING zombodb ((test_reset_stats.*));");
+        Spi::run("SELECT zdb.query_tids('idxtest_reset_stats', 'generate_series:5');");
+
+        let before = Spi::get_one::<i64>("SELECT (zdb.request_stats()->>'search_requests')::bigint;")
+            .expect("failed to get SPI result");
+        assert!(before > 0);
+
+        Spi::run("SELECT zdb.reset_stats();");
+        let after = Spi::get_one::<i64>("SELECT (zdb.request_stats()->>'search_requests')::bigint;")
+            .expect("failed to get SPI result");
+        assert_eq!(after, 0);
+    }
+}
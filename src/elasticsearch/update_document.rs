@@ -0,0 +1,108 @@
+use crate::access_method::options::ZDBIndexOptions;
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use pgx::*;
+use serde_json::*;
+
+pub struct ElasticsearchUpdateDocumentRequest {
+    elasticsearch: Elasticsearch,
+    id: u64,
+    partial: Value,
+}
+
+impl ElasticsearchUpdateDocumentRequest {
+    pub fn new(elasticsearch: &Elasticsearch, id: u64, partial: Value) -> Self {
+        ElasticsearchUpdateDocumentRequest {
+            elasticsearch: elasticsearch.clone(),
+            id,
+            partial,
+        }
+    }
+
+    /// Returns `false` if there's no document with our id
+    pub fn execute(self) -> std::result::Result<bool, ElasticsearchError> {
+        let result = Elasticsearch::execute_json_request(
+            self.elasticsearch
+                .traced(Elasticsearch::client().post(&format!(
+                    "{}/_update/{}?refresh=true{}",
+                    self.elasticsearch.base_url(),
+                    self.id,
+                    self.elasticsearch.routing_param(&self.id.to_string())
+                ))),
+            Some(json! {
+                {
+                    "doc": self.partial
+                }
+            }),
+            |_| Ok(()),
+        );
+
+        match result {
+            // a 404 just means the document doesn't exist
+            Err(e) if e.is_404() => Ok(false),
+            Err(e) => Err(e),
+            Ok(()) => Ok(true),
+        }
+    }
+}
+
+/// Merges `partial` into the `_source` of the document for the row at `ctid`, without
+/// reindexing the whole row.  Only available for indexes created with `llapi=true`
+#[pg_extern(volatile, parallel_safe)]
+fn update_document(index: PgRelation, ctid: pg_sys::ItemPointerData, partial: JsonB) -> bool {
+    let options = ZDBIndexOptions::from_relation(&index);
+    if !options.llapi() {
+        panic!(
+            "{} is not a low-level API index.  Set llapi=true to use zdb.update_document()",
+            index.name()
+        );
+    } else if !partial.0.is_object() {
+        panic!("partial document must be a json object");
+    }
+
+    Elasticsearch::from_options(options)
+        .update_document(item_pointer_to_u64(ctid), partial.0)
+        .execute()
+        .expect("failed to execute update document request")
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+    use serde_json::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_update_document() {
+        Spi::run("CREATE TABLE test_update_document AS SELECT 1 AS id, 'before'::text AS title, 'unchanged'::text AS body;");
+        Spi::run("CREATE INDEX idxtest_update_document ON test_update_document USING zombodb ((test_update_document.*)) WITH (llapi=true);");
+
+        let updated = Spi::get_one::<bool>(
+            r#"SELECT zdb.update_document('idxtest_update_document', ctid, '{"title": "after"}') FROM test_update_document;"#,
+        )
+        .expect("failed to get SPI result");
+        assert!(updated);
+
+        let response = Spi::get_one::<&str>(
+            "SELECT zdb.request('idxtest_update_document', '_doc/' || (((ctid::text::point)[0]::bigint << 32) | (ctid::text::point)[1]::bigint))
+               FROM test_update_document;",
+        )
+        .expect("failed to get SPI result");
+        let document = serde_json::from_str::<Value>(response).expect("response is not json");
+
+        assert_eq!(document["_source"]["title"], json!("after"));
+        assert_eq!(document["_source"]["body"], json!("unchanged"));
+        assert_eq!(document["_source"]["id"], json!(1));
+    }
+
+    #[pg_test(
+        error = "idxtest_update_document is not a low-level API index.  Set llapi=true to use zdb.update_document()"
+    )]
+    #[initialize(es = true)]
+    fn test_update_document_requires_llapi() {
+        Spi::run("CREATE TABLE test_update_document AS SELECT 1 AS id, 'before'::text AS title;");
+        Spi::run("CREATE INDEX idxtest_update_document ON test_update_document USING zombodb ((test_update_document.*));");
+        Spi::run(
+            r#"SELECT zdb.update_document('idxtest_update_document', ctid, '{"title": "after"}') FROM test_update_document;"#,
+        );
+    }
+}
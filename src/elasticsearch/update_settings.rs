@@ -1,4 +1,5 @@
 use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use crate::gucs::ZDB_REPLICAS_STEP_TIMEOUT;
 use serde_json::*;
 
 pub struct ElasticsearchUpdateSettingsRequest(Elasticsearch);
@@ -9,22 +10,168 @@ impl ElasticsearchUpdateSettingsRequest {
     }
 
     pub fn execute(self) -> std::result::Result<(), ElasticsearchError> {
-        Elasticsearch::execute_json_request(
-            Elasticsearch::client().put(&format!("{}/_settings", self.0.base_url())),
-            Some(json! {
+        if !self.0.options.block_metadata() {
+            return self.put_index_settings();
+        }
+
+        // ES rejects settings changes while the index is blocking metadata changes, so
+        // lift the block first.  It's re-applied by `apply_metadata_block()` once the caller
+        // has made the rest of its changes, or right away if these fail
+        self.lift_metadata_block()?;
+        self.put_index_settings().map_err(|e| {
+            let _ = self.apply_metadata_block();
+            e
+        })
+    }
+
+    fn put_index_settings(&self) -> std::result::Result<(), ElasticsearchError> {
+        let target_replicas = self.0.options.replicas();
+        let current_replicas = if self.0.options.replicas_step() {
+            self.current_replicas()?
+        } else {
+            target_replicas
+        };
+
+        self.put_settings(json! {
+            {
+                "index": {
+                    "max_result_window": self.0.options.max_result_window(),
+                    "mapping.nested_fields.limit": self.0.options.nested_fields_limit(),
+                    "mapping.total_fields.limit": self.0.options.total_fields_limit(),
+                    "mapping.field_name_length.limit": self.0.options.field_name_length_limit(),
+                    "refresh_interval": self.0.options.refresh_interval().as_str(),
+                    "number_of_replicas": current_replicas.min(target_replicas),
+                    "translog.durability": self.0.options.translog_durability(),
+                    "max_terms_count": self.0.options.max_terms_count(),
+                    "max_script_fields": self.0.options.max_script_fields(),
+                    "max_shingle_diff": self.0.options.max_shingle_diff(),
+                    "priority": self.0.options.recovery_priority(),
+                    "write.wait_for_active_shards": self.0.options.default_wait_for_active_shards(),
+                    "merge.policy.floor_segment": self.0.options.floor_segment(),
+                    "indexing.slowlog.source": self.0.slowlog_source_setting(),
+                    "default_pipeline": self.0.options.default_pipeline(),
+                    "final_pipeline": self.0.options.final_pipeline(),
+                    "routing.allocation.include._tier_preference": self.0.options.tier_preference()
+                }
+            }
+        })?;
+
+        // add any new replicas one at a time so that the cluster only needs to
+        // recover one copy of the index at once
+        for replicas in replica_steps(current_replicas, target_replicas) {
+            self.put_settings(json! {
                 {
                     "index": {
-                        "max_result_window": self.0.options.max_result_window(),
-                        "mapping.nested_fields.limit": self.0.options.nested_fields_limit(),
-                        "mapping.total_fields.limit": self.0.options.total_fields_limit(),
-                        "refresh_interval": self.0.options.refresh_interval().as_str(),
-                        "number_of_replicas": self.0.options.replicas(),
-                        "translog.durability": self.0.options.translog_durability(),
-                        "max_terms_count": self.0.options.max_terms_count()
+                        "number_of_replicas": replicas
                     }
                 }
-            }),
+            })?;
+            self.wait_for_recovery(replicas)?;
+        }
+
+        Ok(())
+    }
+
+    fn current_replicas(&self) -> std::result::Result<i32, ElasticsearchError> {
+        let settings = self.0.get_settings().execute()?;
+        Ok(settings[self.0.index_name()]["settings"]["index"]["number_of_replicas"]
+            .as_str()
+            .and_then(|replicas| replicas.parse().ok())
+            .unwrap_or(0))
+    }
+
+    /// Waits for the replicas just added to finish recovering.  Replicas that can't be allocated,
+    /// such as on a single-node cluster, stay unassigned rather than initializing, so this doesn't
+    /// wait for the index to turn green
+    fn wait_for_recovery(&self, replicas: i32) -> std::result::Result<(), ElasticsearchError> {
+        let timeout = ZDB_REPLICAS_STEP_TIMEOUT.get();
+        if self.0.wait_for_stable(&format!("{}s", timeout)).execute()? {
+            Ok(())
+        } else {
+            Err(ElasticsearchError(
+                None,
+                format!(
+                    "timed out after {}s waiting for replica {} of {} to recover.  Raise zdb.replicas_step_timeout, or run ALTER INDEX again to continue",
+                    timeout,
+                    replicas,
+                    self.0.index_name()
+                ),
+            ))
+        }
+    }
+
+    /// Blocks metadata changes to the index, if it's configured to do so.  This needs to happen
+    /// after ZomboDB has finished making its own settings, mapping, and alias changes
+    pub fn apply_metadata_block(&self) -> std::result::Result<(), ElasticsearchError> {
+        if !self.0.options.block_metadata() {
+            return Ok(());
+        }
+
+        self.put_settings(json! {
+            {
+                "index": {
+                    "blocks.metadata": true
+                }
+            }
+        })
+    }
+
+    /// Allows metadata changes to the index again, whether or not it's configured to block them
+    pub fn lift_metadata_block(&self) -> std::result::Result<(), ElasticsearchError> {
+        self.put_settings(json! {
+            {
+                "index": {
+                    "blocks.metadata": false
+                }
+            }
+        })
+    }
+
+    fn put_settings(&self, settings: Value) -> std::result::Result<(), ElasticsearchError> {
+        Elasticsearch::execute_json_request(
+            Elasticsearch::client().put(&format!("{}/_settings", self.0.base_url())),
+            Some(settings),
             |_| Ok(()),
         )
     }
 }
+
+/// The replica counts to step through when going from `current` to `target` replicas.  Removing
+/// replicas needs no recovery, so there are no steps
+fn replica_steps(current: i32, target: i32) -> std::ops::RangeInclusive<i32> {
+    current + 1..=target
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use crate::elasticsearch::update_settings::replica_steps;
+    use pgx::*;
+
+    #[pg_test]
+    fn test_replica_steps() {
+        assert_eq!(replica_steps(0, 3).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(replica_steps(2, 3).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(replica_steps(3, 3).count(), 0);
+        assert_eq!(replica_steps(3, 1).count(), 0);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_replicas_step_on_single_node() {
+        Spi::run(
+            "CREATE TABLE test AS SELECT n FROM generate_series(1, 10) n;
+             CREATE INDEX idxtest ON test USING zombodb ((test.*)) WITH (url='http://localhost:19200/', replicas_step=true);
+             SET zdb.replicas_step_timeout TO 30;",
+        );
+
+        // the test cluster has a single node, so the new replicas can never be assigned and the
+        // index never turns green.  Each step still completes once nothing is initializing
+        Spi::run("ALTER INDEX idxtest SET (replicas=2);");
+
+        let replicas = Spi::get_one::<&str>(
+            "SELECT zdb.index_settings('idxtest')->zdb.index_name('idxtest')->'settings'->'index'->>'number_of_replicas';",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(replicas, "2");
+    }
+}
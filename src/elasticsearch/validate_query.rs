@@ -0,0 +1,132 @@
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use crate::zdbquery::{ZDBPreparedQuery, ZDBQuery};
+use crate::zql::ast::{Expr, IndexLink};
+use pgx::*;
+use serde_json::*;
+use std::collections::HashSet;
+
+pub struct ElasticsearchValidateQueryRequest {
+    elasticsearch: Elasticsearch,
+    query: ZDBPreparedQuery,
+}
+
+impl ElasticsearchValidateQueryRequest {
+    pub fn new(elasticsearch: &Elasticsearch, query: ZDBPreparedQuery) -> Self {
+        ElasticsearchValidateQueryRequest {
+            elasticsearch: elasticsearch.clone(),
+            query,
+        }
+    }
+
+    /// Returns `None` if ES considers the query valid, otherwise the reason it's not
+    pub fn execute(self) -> std::result::Result<Option<String>, ElasticsearchError> {
+        let body = json! {
+            {
+                "query": self.query.query_dsl()
+            }
+        };
+
+        let url = format!(
+            "{}/_validate/query?explain=true",
+            self.elasticsearch.base_url()
+        );
+        Elasticsearch::execute_json_request(
+            Elasticsearch::client().post(&url),
+            Some(body),
+            |body| {
+                let response: Value =
+                    serde_json::from_reader(body).expect("failed to parse response json");
+
+                if response["valid"].as_bool().unwrap_or(false) {
+                    return Ok(None);
+                }
+
+                // the reason is either at the top-level or in the per-shard explanations
+                let error = response["error"]
+                    .as_str()
+                    .or_else(|| {
+                        response["explanations"]
+                            .as_array()
+                            .and_then(|explanations| {
+                                explanations
+                                    .iter()
+                                    .find_map(|explanation| explanation["error"].as_str())
+                            })
+                    })
+                    .unwrap_or("invalid query");
+                Ok(Some(error.to_owned()))
+            },
+        )
+    }
+}
+
+#[pg_extern(volatile, parallel_safe)]
+fn query_validate(index: PgRelation, query: &str) -> JsonB {
+    let zdbquery = ZDBQuery::from_str(query);
+
+    // a query that isn't json is ZomboDB query syntax, which we need to be able to parse
+    // ourselves before ES will ever see it
+    if serde_json::from_str::<Value>(query).is_err() {
+        let mut used_fields = HashSet::new();
+        if let Err(e) = Expr::from_str(
+            &index,
+            "zdb_all",
+            query,
+            &IndexLink::from_zdb(&index),
+            &None,
+            &mut used_fields,
+        ) {
+            return JsonB(json! {
+                {
+                    "valid": false,
+                    "error": e.to_string()
+                }
+            });
+        }
+    }
+
+    let error = Elasticsearch::new(&index)
+        .validate_query(zdbquery.prepare(&index, None).0)
+        .execute()
+        .expect("failed to execute validate query request");
+
+    JsonB(json! {
+        {
+            "valid": error.is_none(),
+            "error": error
+        }
+    })
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_query_validate_valid() {
+        Spi::run("CREATE TABLE test_query_validate AS SELECT * FROM generate_series(1, 10);");
+        Spi::run("CREATE INDEX idxtest_query_validate ON test_query_validate USING zombodb ((test_query_validate.*));");
+        let result = Spi::get_one::<JsonB>(
+            "SELECT zdb.query_validate('idxtest_query_validate', 'generate_series:5');",
+        )
+        .expect("failed to get SPI result");
+
+        assert_eq!(result.0["valid"], serde_json::json!(true));
+        assert!(result.0["error"].is_null());
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_query_validate_malformed() {
+        Spi::run("CREATE TABLE test_query_validate AS SELECT * FROM generate_series(1, 10);");
+        Spi::run("CREATE INDEX idxtest_query_validate ON test_query_validate USING zombodb ((test_query_validate.*));");
+        let result = Spi::get_one::<JsonB>(
+            "SELECT zdb.query_validate('idxtest_query_validate', 'generate_series:(5');",
+        )
+        .expect("failed to get SPI result");
+
+        assert_eq!(result.0["valid"], serde_json::json!(false));
+        assert!(result.0["error"].is_string());
+    }
+}
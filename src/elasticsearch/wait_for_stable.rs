@@ -0,0 +1,92 @@
+use crate::elasticsearch::{Elasticsearch, ElasticsearchError};
+use pgx::*;
+use serde::*;
+
+pub struct ElasticsearchWaitForStableRequest {
+    elasticsearch: Elasticsearch,
+    timeout: String,
+}
+
+#[derive(Deserialize)]
+struct ClusterHealthResponse {
+    timed_out: bool,
+}
+
+impl ElasticsearchWaitForStableRequest {
+    pub fn new(elasticsearch: &Elasticsearch, timeout: &str) -> Self {
+        ElasticsearchWaitForStableRequest {
+            elasticsearch: elasticsearch.clone(),
+            timeout: timeout.to_owned(),
+        }
+    }
+
+    pub fn execute(self) -> std::result::Result<bool, ElasticsearchError> {
+        let url = format!(
+            "{}_cluster/health/{}?wait_for_no_initializing_shards=true&wait_for_no_relocating_shards=true&timeout={}",
+            self.elasticsearch.url(),
+            self.elasticsearch.index_name(),
+            self.timeout
+        );
+
+        match Elasticsearch::execute_json_request(Elasticsearch::client().get(&url), None, |body| {
+            let response: ClusterHealthResponse =
+                serde_json::from_reader(body).expect("failed to parse cluster health response");
+            Ok(!response.timed_out)
+        }) {
+            // ES responds with a 408 when the health check times out
+            Err(e) if e.status() == Some(408) => Ok(false),
+            other => other,
+        }
+    }
+}
+
+fn validate_timeout(timeout: &str) {
+    let units = timeout.trim_start_matches(|c: char| c.is_ascii_digit());
+    let is_valid = units.len() < timeout.len()
+        && ["d", "h", "m", "s", "ms", "micros", "nanos"].contains(&units);
+
+    if !is_valid {
+        panic!(
+            "invalid timeout.  Must be a number followed by one of 'd', 'h', 'm', 's', 'ms', 'micros', or 'nanos': {}",
+            timeout
+        )
+    }
+}
+
+#[pg_extern(volatile, parallel_safe)]
+fn wait_for_stable(index: PgRelation, timeout: default!(&str, "60s")) -> bool {
+    validate_timeout(timeout);
+
+    Elasticsearch::new(&index)
+        .wait_for_stable(timeout)
+        .execute()
+        .expect("failed to wait for stable shards")
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_wait_for_stable() {
+        Spi::run("CREATE TABLE test_wait_for_stable AS SELECT * FROM generate_series(1, 10);");
+        Spi::run("CREATE INDEX idxtest_wait_for_stable ON test_wait_for_stable USING zombodb ((test_wait_for_stable.*));");
+        let stable = Spi::get_one::<bool>(
+            "SELECT zdb.wait_for_stable('idxtest_wait_for_stable', '30s');",
+        )
+        .expect("failed to get SPI result");
+
+        assert!(stable);
+    }
+
+    #[pg_test(
+        error = "invalid timeout.  Must be a number followed by one of 'd', 'h', 'm', 's', 'ms', 'micros', or 'nanos': soon"
+    )]
+    #[initialize(es = true)]
+    fn test_wait_for_stable_invalid_timeout() {
+        Spi::run("CREATE TABLE test_wait_for_stable AS SELECT * FROM generate_series(1, 10);");
+        Spi::run("CREATE INDEX idxtest_wait_for_stable ON test_wait_for_stable USING zombodb ((test_wait_for_stable.*));");
+        Spi::run("SELECT zdb.wait_for_stable('idxtest_wait_for_stable', 'soon');");
+    }
+}
@@ -1,11 +1,12 @@
 use crate::access_method::options::ZDBIndexOptions;
 use crate::elasticsearch::Elasticsearch;
-use crate::mapping::{categorize_tupdesc, generate_default_mapping};
+use crate::mapping::{categorize_tupdesc, disable_norms, generate_default_mapping};
 use crate::utils::{is_zdb_index, lookup_zdb_index_tupdesc};
 use pgx::{
     pg_sys, register_xact_callback, warning, IntoDatum, PgBuiltInOids, PgRelation,
     PgXactCallbackEvent, Spi,
 };
+use serde_json::json;
 
 pub fn get_index_options_for_relation(relation: &PgRelation) -> Vec<ZDBIndexOptions> {
     let mut options = Vec::new();
@@ -70,8 +71,45 @@ pub fn alter_indices(prev_options: Option<Vec<ZDBIndexOptions>>) {
                 panic!("The 'shadow' index property cannot be changed");
             }
 
+            if new_options.block_metadata() && old_options.alias() != new_options.alias() {
+                panic!("The 'alias' index property cannot be changed while 'block_metadata' is enabled");
+            }
+
+            if !old_options.block_metadata() && new_options.block_metadata() {
+                warning!("block_metadata is enabled.  Elasticsearch will reject DROP INDEX, and any changes made outside of ZomboDB, until it is disabled via ALTER INDEX");
+            }
+
             let es = Elasticsearch::new(&index);
 
+            // if the transaction aborts, put the metadata block back the way it was
+            if old_options.block_metadata() || new_options.block_metadata() {
+                let old_options = old_options.clone();
+                register_xact_callback(PgXactCallbackEvent::Abort, move || {
+                    let block_metadata = old_options.block_metadata();
+                    let request = Elasticsearch::from_options(old_options).update_settings();
+                    let result = if block_metadata {
+                        request.apply_metadata_block()
+                    } else {
+                        request.lift_metadata_block()
+                    };
+                    if let Err(e) = result {
+                        // we can't panic here b/c we're already in the ABORT stage
+                        warning!(
+                            "failed to restore index metadata block on transaction abort: {:?}",
+                            e
+                        )
+                    }
+                });
+            }
+
+            // the block is only lifted by `execute()` while it's enabled, so lift it for good
+            // when it's being disabled
+            if old_options.block_metadata() && !new_options.block_metadata() {
+                es.update_settings()
+                    .lift_metadata_block()
+                    .expect("failed to lift index metadata block");
+            }
+
             // change the index settings
             es.update_settings()
                 .execute()
@@ -83,12 +121,25 @@ pub fn alter_indices(prev_options: Option<Vec<ZDBIndexOptions>>) {
             let heap_relation = index.heap_relation().expect("no heap relation for index!");
             let mut mapping = generate_default_mapping(&heap_relation);
             let _ = categorize_tupdesc(&tupdesc, &heap_relation, Some(&mut mapping));
+            if new_options.store_ctid_field() {
+                mapping.insert("_zdb_ctid".into(), json!({ "type": "keyword" }));
+            }
+            for field in new_options.disable_norms_fields() {
+                if let Some(definition) = mapping.get_mut(field) {
+                    disable_norms(definition);
+                }
+            }
             es.put_mapping(
                 serde_json::to_value(&mapping).expect("failed to serialize mapping to json"),
             )
             .execute()
             .expect("failed to update index mapping");
 
+            // now that our settings and mapping are in place, block further changes
+            es.update_settings()
+                .apply_metadata_block()
+                .expect("failed to block index metadata");
+
             // if the user changed the alias
             if old_options.alias() != new_options.alias() {
                 // add the index to its new alias
@@ -97,10 +148,10 @@ pub fn alter_indices(prev_options: Option<Vec<ZDBIndexOptions>>) {
                     .expect("failed to add index to new alias");
 
                 // register a pre-commit callback which will remove the index from its old alias
+                let old_alias = old_options.alias().to_owned();
+                let es = Elasticsearch::from_options(old_options.clone());
                 register_xact_callback(PgXactCallbackEvent::PreCommit, move || {
-                    let alias = old_options.alias().to_owned();
-                    let es = Elasticsearch::from_options(old_options);
-                    es.remove_alias(&alias)
+                    es.remove_alias(&old_alias)
                         .execute()
                         .expect("failed to remove index from old alias");
                 });
@@ -108,15 +159,40 @@ pub fn alter_indices(prev_options: Option<Vec<ZDBIndexOptions>>) {
                 // register an abort callback which will remove the index from the new alias
                 // and restore its settings.  There's nothing we can do about the mapping change, unfortunately
                 register_xact_callback(PgXactCallbackEvent::Abort, move || {
+                    // we can't panic here b/c we're already in the ABORT stage
                     let alias = new_options.alias().to_owned();
-                    let es = Elasticsearch::from_options(new_options);
-                    es.update_settings()
-                        .execute()
-                        .expect("failed to restore index settings");
+                    let block_metadata = old_options.block_metadata();
+                    let es = Elasticsearch::from_options(old_options);
 
-                    es.remove_alias(&alias)
-                        .execute()
-                        .expect("failed to remove index from new alias");
+                    // ES rejects alias changes while the index blocks metadata changes, and the
+                    // abort callback above may have already put the old block back
+                    if block_metadata {
+                        if let Err(e) = es.update_settings().lift_metadata_block() {
+                            warning!(
+                                "failed to lift index metadata block on transaction abort: {:?}",
+                                e
+                            )
+                        }
+                    }
+                    if let Err(e) = es.remove_alias(&alias).execute() {
+                        warning!(
+                            "failed to remove index from new alias on transaction abort: {:?}",
+                            e
+                        )
+                    }
+
+                    if let Err(e) = es.update_settings().execute() {
+                        warning!(
+                            "failed to restore index settings on transaction abort: {:?}",
+                            e
+                        )
+                    }
+                    if let Err(e) = es.update_settings().apply_metadata_block() {
+                        warning!(
+                            "failed to restore index metadata block on transaction abort: {:?}",
+                            e
+                        )
+                    }
                 });
             }
         }
@@ -24,8 +24,15 @@ impl PgHooks for ZDBHooks {
         query_desc: PgBox<pg_sys::QueryDesc>,
         prev_hook: fn(PgBox<pg_sys::QueryDesc>) -> HookResult<()>,
     ) -> HookResult<()> {
+        let is_modification = matches!(
+            query_desc.operation,
+            pg_sys::CmdType_CMD_INSERT | pg_sys::CmdType_CMD_UPDATE | pg_sys::CmdType_CMD_DELETE
+        );
         let result = prev_hook(query_desc);
         get_executor_manager().pop_query();
+        if is_modification {
+            get_executor_manager().flush_statement_bulk_requests();
+        }
         result
     }
 
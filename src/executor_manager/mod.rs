@@ -1,3 +1,4 @@
+use crate::access_method::options::{FlushMode, ZDBIndexOptions};
 use crate::elasticsearch::{Elasticsearch, ElasticsearchBulkRequest};
 use crate::mapping::{categorize_tupdesc, CategorizedAttribute};
 use crate::utils::{find_zdb_index, lookup_all_zdb_index_oids, lookup_zdb_index_tupdesc};
@@ -20,6 +21,92 @@ pub struct BulkContext {
     pub attributes: Vec<CategorizedAttribute<'static>>,
     pub tupdesc: &'static PgTupleDesc<'static>,
     pub is_shadow: bool,
+    pub flush_mode: FlushMode,
+    writer_slot: Option<WriterSlot>,
+}
+
+/// One of an index's `max_concurrent_writers` slots, held by a bulk context from its first write
+/// until it's flushed.  Slots are advisory locks, the same ones `pg_advisory_xact_lock(key, slot)`
+/// takes, where `key` is a hash of the index's uuid.  They're acquired through Postgres' lock
+/// manager so that waiting for one honors `lock_timeout` and is visible to the deadlock detector
+struct WriterSlot(pg_sys::LOCKTAG);
+
+impl WriterSlot {
+    /// Waits until this backend holds one of the index's writer slots, or returns `None` if the
+    /// index doesn't limit its writers
+    fn acquire(elasticsearch: &Elasticsearch) -> Option<WriterSlot> {
+        let max_concurrent_writers = elasticsearch.options.max_concurrent_writers();
+        if max_concurrent_writers <= 0 {
+            // unlimited
+            return None;
+        }
+
+        let key = Spi::get_one_with_args::<i32>(
+            "SELECT hashtext($1);",
+            vec![(
+                PgBuiltInOids::TEXTOID.oid(),
+                elasticsearch.options.uuid().into_datum(),
+            )],
+        )
+        .expect("failed to hash index uuid");
+
+        // take the first free slot, if there is one
+        for slot in 0..max_concurrent_writers {
+            let tag = WriterSlot::locktag(key, slot);
+            if WriterSlot::lock(&tag, true) {
+                return Some(WriterSlot(tag));
+            }
+        }
+
+        // otherwise wait on one of them, spreading waiting backends across the slots
+        let tag = WriterSlot::locktag(key, unsafe { pg_sys::MyProcPid } % max_concurrent_writers);
+        WriterSlot::lock(&tag, false);
+        Some(WriterSlot(tag))
+    }
+
+    /// Gives up the slot so another backend can flush to the index
+    fn release(self) {
+        WriterSlot::with_top_transaction_owner(|| unsafe {
+            pg_sys::LockRelease(&self.0, pg_sys::ExclusiveLock as pg_sys::LOCKMODE, false)
+        });
+    }
+
+    fn lock(tag: &pg_sys::LOCKTAG, dont_wait: bool) -> bool {
+        let result = WriterSlot::with_top_transaction_owner(|| unsafe {
+            pg_sys::LockAcquire(
+                tag,
+                pg_sys::ExclusiveLock as pg_sys::LOCKMODE,
+                false,
+                dont_wait,
+            )
+        });
+        result != pg_sys::LockAcquireResult_LOCKACQUIRE_NOT_AVAIL
+    }
+
+    /// Bulk contexts outlive subtransactions, so their slots are owned by the top-level
+    /// transaction rather than whichever subtransaction happened to first write to the index.
+    /// Either way, Postgres releases any slot still held when the transaction ends
+    fn with_top_transaction_owner<R, F: FnOnce() -> R>(f: F) -> R {
+        unsafe {
+            let owner = pg_sys::CurrentResourceOwner;
+            pg_sys::CurrentResourceOwner = pg_sys::TopTransactionResourceOwner;
+            let result = f();
+            pg_sys::CurrentResourceOwner = owner;
+            result
+        }
+    }
+
+    fn locktag(key: i32, slot: i32) -> pg_sys::LOCKTAG {
+        // this is Postgres' SET_LOCKTAG_INT32() macro
+        pg_sys::LOCKTAG {
+            locktag_field1: unsafe { pg_sys::MyDatabaseId },
+            locktag_field2: key as u32,
+            locktag_field3: slot as u32,
+            locktag_field4: 2,
+            locktag_type: pg_sys::LockTagType_LOCKTAG_ADVISORY as u8,
+            locktag_lockmethodid: pg_sys::USER_LOCKMETHOD as u8,
+        }
+    }
 }
 
 pub struct QueryState {
@@ -269,6 +356,15 @@ impl ExecutorManager {
 
     /// for use by code that needs to execute a search within a transaction
     pub fn wait_for_completion(&mut self) {
+        self.flush_bulk_requests(|_| true);
+    }
+
+    /// for use at the end of each statement, for indexes with `flush_mode='statement'`
+    pub fn flush_statement_bulk_requests(&mut self) {
+        self.flush_bulk_requests(|bulk| bulk.flush_mode == FlushMode::Statement);
+    }
+
+    fn flush_bulk_requests<F: Fn(&BulkContext) -> bool>(&mut self, should_flush: F) {
         if let Some(bulk_requests) = self.bulk_requests.take() {
             let mut replacement_requests = HashMap::with_capacity(bulk_requests.capacity());
 
@@ -277,14 +373,25 @@ impl ExecutorManager {
                 .filter(|(_, bulk)| !bulk.is_shadow)
             // shadow indexes don't change anything
             {
+                if !should_flush(&bulk) {
+                    replacement_requests.insert(key, bulk);
+                    continue;
+                }
+
                 let elasticsearch = bulk.elasticsearch;
                 let attributes = bulk.attributes;
                 let tupdesc = bulk.tupdesc;
+                let flush_mode = bulk.flush_mode;
 
                 if let Err(e) = bulk.bulk.finish() {
                     panic!("{:?}", e)
                 }
 
+                // the replacement context will take a writer slot again if it's written to
+                if let Some(writer_slot) = bulk.writer_slot {
+                    writer_slot.release();
+                }
+
                 let bulk = elasticsearch.start_bulk();
                 replacement_requests.insert(
                     key,
@@ -294,6 +401,8 @@ impl ExecutorManager {
                         attributes,
                         tupdesc,
                         is_shadow: false,
+                        flush_mode,
+                        writer_slot: None,
                     },
                 );
             }
@@ -319,6 +428,10 @@ impl ExecutorManager {
                 if let Err(e) = bulk.bulk.finish() {
                     panic!("{:?}", e);
                 }
+
+                if let Some(writer_slot) = bulk.writer_slot {
+                    writer_slot.release();
+                }
             }
         }
     }
@@ -362,7 +475,7 @@ impl ExecutorManager {
 
         let bulk_map = self.bulk_requests.as_mut().unwrap();
         let xids = &self.xids;
-        bulk_map.entry(relid).or_insert_with(move || {
+        let bulk = bulk_map.entry(relid).or_insert_with(move || {
             let indexrel = unsafe { PgRelation::open(relid) };
             let elasticsearch = Elasticsearch::new(&indexrel);
             let attributes = categorize_tupdesc(tupdesc, &indexrel.heap_relation().unwrap(), None);
@@ -393,6 +506,8 @@ impl ExecutorManager {
             }
 
             let is_shadow = elasticsearch.is_shadow_index();
+            let flush_mode = ZDBIndexOptions::from_relation(&indexrel).flush_mode();
+
             let mut bulk = elasticsearch.start_bulk();
 
             // only non-shadow indexes are written to
@@ -413,7 +528,17 @@ impl ExecutorManager {
                 attributes,
                 tupdesc,
                 is_shadow,
+                flush_mode,
+                writer_slot: None,
             }
-        })
+        });
+
+        // limit how many backends can flush to this index at once.  A flushed context gives up
+        // its slot, so it needs to take one again before it's written to
+        if !bulk.is_shadow && bulk.writer_slot.is_none() {
+            bulk.writer_slot = WriterSlot::acquire(&bulk.elasticsearch);
+        }
+
+        bulk
     }
 }
@@ -38,6 +38,17 @@ pub static ZDB_DEFAULT_REPLICAS: GucSetting<i32> = GucSetting::new(0);
 pub static ZDB_DEFAULT_ELASTICSEARCH_URL: GucSetting<Option<&'static str>> = GucSetting::new(None);
 pub static ZDB_LOG_LEVEL: GucSetting<ZDBLogLevel> = GucSetting::new(ZDBLogLevel::Debug);
 pub static ZDB_ACCELERATOR: GucSetting<bool> = GucSetting::new(false);
+pub static ZDB_MAX_BULK_CONCURRENCY: GucSetting<i32> = GucSetting::new(0);
+pub static ZDB_REPLICAS_STEP_TIMEOUT: GucSetting<i32> = GucSetting::new(600);
+
+/// The upper bound for any index's `bulk_concurrency`.  A setting of zero means the number
+/// of CPUs on this machine
+pub fn max_bulk_concurrency() -> i32 {
+    match ZDB_MAX_BULK_CONCURRENCY.get() {
+        0 => num_cpus::get() as i32,
+        max => max,
+    }
+}
 
 pub fn init() {
     GucRegistry::define_bool_guc("zdb.ignore_visibility",
@@ -59,7 +70,7 @@ pub fn init() {
         &ZDB_DEFAULT_REPLICAS,
         0,
         32768,
-        GucContext::Sighup);
+        GucContext::Userset);
 
     GucRegistry::define_string_guc(
         "zdb.default_elasticsearch_url",
@@ -83,13 +94,33 @@ pub fn init() {
         &ZDB_ACCELERATOR,
         GucContext::Userset,
     );
+
+    GucRegistry::define_int_guc(
+        "zdb.max_bulk_concurrency",
+        "The maximum number of concurrent _bulk API requests any index may use",
+        "Caps each index's bulk_concurrency setting.  Defaults to 0, which means the number of CPUs on the Postgres server.  Raise this when Elasticsearch runs on separate hardware that can absorb more concurrent requests.",
+        &ZDB_MAX_BULK_CONCURRENCY,
+        0,
+        1024,
+        GucContext::Suset,
+    );
+
+    GucRegistry::define_int_guc(
+        "zdb.replicas_step_timeout",
+        "The number of seconds to wait for each new replica to recover when an index has replicas_step enabled",
+        "ALTER INDEX raises an error if a replica hasn't recovered within this many seconds.  Running ALTER INDEX again continues from the replicas that have been added so far.",
+        &ZDB_REPLICAS_STEP_TIMEOUT,
+        1,
+        86400,
+        GucContext::Userset,
+    );
 }
 
 #[cfg(any(test, feature = "pg_test"))]
 mod tests {
     use crate::gucs::{
-        ZDBLogLevel, ZDB_DEFAULT_ELASTICSEARCH_URL, ZDB_DEFAULT_REPLICAS, ZDB_DEFAULT_ROW_ESTIMATE,
-        ZDB_IGNORE_VISIBILITY, ZDB_LOG_LEVEL,
+        max_bulk_concurrency, ZDBLogLevel, ZDB_DEFAULT_ELASTICSEARCH_URL, ZDB_DEFAULT_REPLICAS,
+        ZDB_DEFAULT_ROW_ESTIMATE, ZDB_IGNORE_VISIBILITY, ZDB_LOG_LEVEL, ZDB_REPLICAS_STEP_TIMEOUT,
     };
     use pgx::*;
 
@@ -105,6 +136,8 @@ mod tests {
     #[pg_test]
     fn test_default_replicas() {
         assert_eq!(ZDB_DEFAULT_REPLICAS.get(), 0);
+        Spi::run("SET zdb.default_replicas TO 2");
+        assert_eq!(ZDB_DEFAULT_REPLICAS.get(), 2);
     }
 
     #[pg_test]
@@ -127,4 +160,18 @@ mod tests {
         Spi::run("SET zdb.log_level to 'info'");
         assert_eq!(ZDB_LOG_LEVEL.get(), ZDBLogLevel::Info);
     }
+
+    #[pg_test]
+    fn test_max_bulk_concurrency() {
+        assert_eq!(max_bulk_concurrency(), num_cpus::get() as i32);
+        Spi::run("SET zdb.max_bulk_concurrency TO 128");
+        assert_eq!(max_bulk_concurrency(), 128);
+    }
+
+    #[pg_test]
+    fn test_replicas_step_timeout() {
+        assert_eq!(ZDB_REPLICAS_STEP_TIMEOUT.get(), 600);
+        Spi::run("SET zdb.replicas_step_timeout TO 30");
+        assert_eq!(ZDB_REPLICAS_STEP_TIMEOUT.get(), 30);
+    }
 }
@@ -580,6 +580,28 @@ pub fn lookup_analysis_thing(table_name: &str) -> Value {
     }
 }
 
+pub fn is_numeric_mapping(definition: &Value) -> bool {
+    matches!(
+        definition["type"].as_str(),
+        Some("byte")
+            | Some("short")
+            | Some("integer")
+            | Some("long")
+            | Some("unsigned_long")
+            | Some("half_float")
+            | Some("float")
+            | Some("double")
+            | Some("scaled_float")
+    )
+}
+
+/// Only text and keyword fields have norms, so other field types are left alone
+pub fn disable_norms(definition: &mut Value) {
+    if definition["type"] == "text" || definition["type"] == "keyword" {
+        definition["norms"] = json!(false);
+    }
+}
+
 fn lookup_type_mapping(typoid: PgOid) -> Option<(Option<serde_json::Value>, Option<pg_sys::Oid>)> {
     let mut json = None;
     let mut regproc = None;
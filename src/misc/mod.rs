@@ -1,6 +1,13 @@
+use crate::access_method::options::ZDBIndexOptions;
 use crate::elasticsearch::Elasticsearch;
-use crate::zdbquery::ZDBQuery;
+use crate::gucs::ZDB_DEFAULT_ELASTICSEARCH_URL;
+use crate::query_dsl::nested::pg_catalog::ScoreMode;
+use crate::utils::lookup_all_zdb_index_oids;
+use crate::zdbquery::{ZDBQuery, ZDBQueryClause};
 use pgx::*;
+use serde::*;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
 
 #[pg_extern(immutable, parallel_safe)]
 fn query_tids(index: PgRelation, query: ZDBQuery) -> Vec<pg_sys::ItemPointerData> {
@@ -18,3 +25,858 @@ fn query_tids(index: PgRelation, query: ZDBQuery) -> Vec<pg_sys::ItemPointerData
     }
     tids
 }
+
+/// Like `zdb.query_tids()`, but searches the point-in-time `pit_id` opened by `zdb.open_pit()`, so
+/// the results are consistent with the index as it was when the point-in-time was opened
+#[pg_extern(volatile, parallel_safe)]
+fn pit_search(
+    index: PgRelation,
+    pit_id: &str,
+    query: ZDBQuery,
+) -> impl std::iter::Iterator<Item = pg_sys::ItemPointerData> {
+    let es = Elasticsearch::new(&index);
+    let scroll = es
+        .open_pit_search(query.prepare(&index, None).0, pit_id)
+        .execute()
+        .unwrap_or_else(|e| panic!("{}", e));
+    let mut tids = Vec::new();
+    for (_, tid, _, _) in scroll.into_iter() {
+        let mut ipd = pg_sys::ItemPointerData::default();
+        u64_to_item_pointer(tid, &mut ipd);
+
+        tids.push(ipd);
+    }
+    tids.into_iter()
+}
+
+/// Returns the ctids of the first `limit` documents matching `query`, in the order defined by the
+/// Elasticsearch `sort` array
+#[pg_extern(volatile, parallel_safe)]
+fn search_sorted(
+    index: PgRelation,
+    query: &str,
+    sort: JsonB,
+    limit: i32,
+) -> impl std::iter::Iterator<Item = pg_sys::ItemPointerData> {
+    if !sort.0.is_array() {
+        panic!("sort must be a json array");
+    } else if limit < 0 {
+        panic!("limit must not be negative");
+    }
+
+    let query = ZDBQuery::from_str(query)
+        .set_sort_json(Some(sort.0))
+        .set_limit(Some(limit as u64));
+
+    query_tids(index, query).into_iter()
+}
+
+/// Returns the ctids of up to `limit` documents matching `query` whose score is at least `min_score`
+#[pg_extern(volatile, parallel_safe)]
+fn query_min_score(
+    index: PgRelation,
+    query: &str,
+    min_score: f32,
+    limit: i32,
+) -> impl std::iter::Iterator<Item = pg_sys::ItemPointerData> {
+    if limit < 0 {
+        panic!("limit must not be negative");
+    }
+
+    let query = ZDBQuery::from_str(query)
+        .set_min_score(Some(min_score as f64))
+        .set_limit(Some(limit as u64));
+
+    query_tids(index, query).into_iter()
+}
+
+/// Returns the ctids of up to `limit` documents matching an Elasticsearch bool query assembled from
+/// the ZomboDB query strings in `must`, `should`, and `must_not`.  As with any bool query, when
+/// there are no `must` clauses at least one of the `should` clauses has to match
+#[pg_extern(volatile, parallel_safe)]
+fn bool_query(
+    index: PgRelation,
+    must: Array<&str>,
+    should: Array<&str>,
+    must_not: Array<&str>,
+    limit: i32,
+) -> impl std::iter::Iterator<Item = pg_sys::ItemPointerData> {
+    if limit < 0 {
+        panic!("limit must not be negative");
+    }
+
+    fn clauses(queries: Array<&str>, name: &str) -> Option<Vec<ZDBQueryClause>> {
+        let clauses = queries
+            .iter()
+            .map(|query| {
+                ZDBQueryClause::zdb(query.unwrap_or_else(|| {
+                    panic!("{} array cannot contain NULL elements", name)
+                }))
+            })
+            .collect::<Vec<_>>();
+
+        if clauses.is_empty() {
+            None
+        } else {
+            Some(clauses)
+        }
+    }
+
+    let query = ZDBQuery::new_with_query_clause(ZDBQueryClause::bool(
+        clauses(must, "must"),
+        clauses(should, "should"),
+        clauses(must_not, "must_not"),
+        None,
+    ))
+    .set_limit(Some(limit as u64));
+
+    query_tids(index, query).into_iter()
+}
+
+// Returns the ctids of documents matching `query` whose geo_point `field` falls within the
+// bounding box described by its top-left and bottom-right corners
+/// ```funcname
+/// geo_bounding_box
+/// ```
+#[pg_extern(volatile, parallel_safe)]
+fn geo_bounding_box_tids(
+    index: PgRelation,
+    field: &str,
+    tl_lat: f64,
+    tl_lon: f64,
+    br_lat: f64,
+    br_lon: f64,
+    query: Option<default!(&str, NULL)>,
+) -> impl std::iter::Iterator<Item = pg_sys::ItemPointerData> {
+    // without a query, every document is considered
+    let (prepared_query, _) = ZDBQuery::from_str(query.unwrap_or_default()).prepare(&index, None);
+    let query = ZDBQuery::new_with_query_dsl(json! {
+        {
+            "bool": {
+                "must": [prepared_query.take_query_dsl()],
+                "filter": [{
+                    "geo_bounding_box": {
+                        field: {
+                            "top_left": { "lat": tl_lat, "lon": tl_lon },
+                            "bottom_right": { "lat": br_lat, "lon": br_lon }
+                        }
+                    }
+                }]
+            }
+        }
+    });
+
+    query_tids(index, query).into_iter()
+}
+
+// Returns the ctids of documents matching `query`, ordered by their score after boosting it by
+// the value of the rank_feature `field`
+/// ```funcname
+/// rank_feature
+/// ```
+#[pg_extern(volatile, parallel_safe)]
+fn rank_feature_tids(
+    index: PgRelation,
+    field: &str,
+    query: &str,
+    boost: default!(f32, 1.0),
+) -> impl std::iter::Iterator<Item = pg_sys::ItemPointerData> {
+    let (prepared_query, _) = ZDBQuery::from_str(query).prepare(&index, None);
+    let query = ZDBQuery::new_with_query_dsl(json! {
+        {
+            "bool": {
+                "must": [prepared_query.take_query_dsl()],
+                "should": [{
+                    "rank_feature": {
+                        "field": field,
+                        "boost": boost,
+                        "saturation": {}
+                    }
+                }]
+            }
+        }
+    })
+    .set_want_score(true)
+    .set_sort_json(Some(json!([{"_score": "desc"}])));
+
+    query_tids(index, query).into_iter()
+}
+
+// Returns the ctids of documents whose `field` contains at least `minimum` of the given `values`
+/// ```funcname
+/// terms_set
+/// ```
+#[pg_extern(volatile, parallel_safe)]
+fn terms_set_tids(
+    index: PgRelation,
+    field: &str,
+    values: Vec<Option<String>>,
+    minimum: i32,
+) -> impl std::iter::Iterator<Item = pg_sys::ItemPointerData> {
+    if minimum < 1 {
+        panic!("minimum must be at least 1");
+    }
+
+    let values = values.into_iter().flatten().collect::<Vec<_>>();
+    let query = ZDBQuery::new_with_query_dsl(json! {
+        {
+            "terms_set": {
+                field: {
+                    "terms": values,
+                    "minimum_should_match_script": {
+                        "source": "params.minimum",
+                        "params": { "minimum": minimum }
+                    }
+                }
+            }
+        }
+    });
+
+    query_tids(index, query).into_iter()
+}
+
+// Returns the ctids of documents with at least one nested object under `path` that matches
+// `query` on its own, so that criteria on different fields of the object can't be satisfied by
+// two different objects
+/// ```funcname
+/// nested_query
+/// ```
+#[pg_extern(volatile, parallel_safe)]
+fn nested_query_tids(
+    index: PgRelation,
+    path: &str,
+    query: &str,
+) -> impl std::iter::Iterator<Item = pg_sys::ItemPointerData> {
+    let query = ZDBQuery::new_with_query_clause(ZDBQueryClause::nested(
+        path.into(),
+        ZDBQuery::from_str(query).query_dsl(),
+        ScoreMode::avg,
+        None,
+    ));
+
+    query_tids(index, query).into_iter()
+}
+
+/// Reports the full URL of every Elasticsearch index that was created by ZomboDB for this database
+/// but no longer has a corresponding Postgres index.  Nothing is deleted.
+#[pg_extern(volatile, parallel_safe)]
+fn recover_orphaned_indexes() -> impl std::iter::Iterator<Item = String> {
+    #[derive(Deserialize)]
+    struct IndexAliases {
+        aliases: HashMap<String, serde_json::Value>,
+    }
+
+    let mut urls = HashSet::new();
+    let mut live_index_names = HashSet::new();
+    for oid in lookup_all_zdb_index_oids().unwrap_or_default() {
+        let index = PgRelation::with_lock(oid, pg_sys::AccessShareLock as pg_sys::LOCKMODE);
+        let options = ZDBIndexOptions::from_relation(&index);
+        urls.insert(options.url().to_owned());
+        live_index_names.insert(options.index_name().to_owned());
+    }
+    if let Some(url) = ZDB_DEFAULT_ELASTICSEARCH_URL.get() {
+        urls.insert(if url.ends_with('/') {
+            url
+        } else {
+            format!("{}/", url)
+        });
+    }
+
+    // ZomboDB names its indexes as "database_oid.namespace_oid.table_oid.index_oid"
+    let prefix = format!("{}.", unsafe { pg_sys::MyDatabaseId });
+    let is_zdb_index_name = |name: &str| {
+        name.starts_with(&prefix)
+            && name[prefix.len()..].split('.').count() == 3
+            && name[prefix.len()..]
+                .split('.')
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+    };
+
+    // another Postgres cluster's database can have the same oid, so the index also needs to be in
+    // ZomboDB's default alias for this database, "database_name.schema.table.index-index_oid"
+    let alias_prefix = format!(
+        "{}.",
+        unsafe { std::ffi::CStr::from_ptr(pg_sys::get_database_name(pg_sys::MyDatabaseId)) }
+            .to_str()
+            .unwrap()
+    );
+    let has_zdb_alias = |name: &str, aliases: &HashMap<String, serde_json::Value>| {
+        let alias_suffix = format!("-{}", name.rsplit('.').next().unwrap_or_default());
+        aliases
+            .keys()
+            .any(|alias| alias.starts_with(&alias_prefix) && alias.ends_with(&alias_suffix))
+    };
+
+    let mut orphans = Vec::new();
+    for url in urls {
+        let indices: HashMap<String, IndexAliases> = Elasticsearch::execute_json_request(
+            Elasticsearch::client().get(&format!("{}_alias", url)),
+            None,
+            |body| Ok(serde_json::from_reader(body).expect("failed to parse _alias response")),
+        )
+        .unwrap_or_else(|e| panic!("{}", e));
+
+        for (index_name, index) in indices {
+            if is_zdb_index_name(&index_name)
+                && has_zdb_alias(&index_name, &index.aliases)
+                && !live_index_names.contains(&index_name)
+            {
+                orphans.push(format!("{}{}", url, index_name));
+            }
+        }
+    }
+
+    orphans.sort();
+    orphans.into_iter()
+}
+
+/// Finds up to `max_results` documents whose `field` is most similar to `like_text`, using
+/// Elasticsearch's `more_like_this` query, ordered by descending score
+#[pg_extern(volatile, parallel_safe)]
+fn more_like_this(
+    index: PgRelation,
+    field: &str,
+    like_text: &str,
+    max_results: default!(i32, 10),
+) -> impl std::iter::Iterator<Item = (name!(ctid, pg_sys::ItemPointerData), name!(score, f32))> {
+    if max_results < 1 {
+        panic!("max_results must be greater than zero");
+    }
+
+    let query = ZDBQuery::new_with_query_dsl(json! {
+        {
+            "more_like_this": {
+                "fields": [field],
+                "like": like_text,
+                "min_term_freq": 1,
+                "min_doc_freq": 1
+            }
+        }
+    })
+    .set_want_score(true)
+    .set_limit(Some(max_results as u64));
+
+    let es = Elasticsearch::new(&index);
+    let scroll = es
+        .open_search(query.prepare(&index, None).0)
+        .execute()
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    let mut results = Vec::new();
+    for (score, tid, _, _) in scroll.into_iter() {
+        let mut ipd = pg_sys::ItemPointerData::default();
+        u64_to_item_pointer(tid, &mut ipd);
+
+        results.push((ipd, score as f32));
+    }
+    results.into_iter()
+}
+
+/// Runs `query` with highlighting enabled on `highlight_field`, returning each matching document's
+/// score, its highlighted fragments, and the `_name`s of any named queries that matched it
+#[pg_extern(volatile, parallel_safe)]
+fn search_rich(
+    index: PgRelation,
+    query: &str,
+    highlight_field: &str,
+) -> impl std::iter::Iterator<
+    Item = (
+        name!(ctid, pg_sys::ItemPointerData),
+        name!(score, f32),
+        name!(fragments, Vec<String>),
+        name!(matched, Vec<String>),
+    ),
+> {
+    let mut query = ZDBQuery::from_str(query)
+        .set_want_score(true)
+        .set_sort_json(Some(json!([{"_score": "desc"}])));
+    query
+        .highlights()
+        .insert(highlight_field.to_owned(), json!({}));
+
+    let es = Elasticsearch::new(&index);
+    let scroll = es
+        .open_search(query.prepare(&index, None).0)
+        .execute()
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    let mut results = Vec::new();
+    for (score, tid, fields, highlights) in scroll.into_iter() {
+        let mut ipd = pg_sys::ItemPointerData::default();
+        u64_to_item_pointer(tid, &mut ipd);
+
+        let fragments = highlights
+            .and_then(|mut highlights| highlights.remove(highlight_field))
+            .unwrap_or_default();
+        let matched = fields
+            .and_then(|fields| fields.matched_queries)
+            .unwrap_or_default();
+        results.push((ipd, score as f32, fragments, matched));
+    }
+    results.into_iter()
+}
+
+/// Returns the size, in bytes, of the `_source` of the document with the given ctid, as stored in
+/// the `_size` field by the Elasticsearch mapper-size plugin when the `store_doc_size` index option
+/// is on.  Returns NULL if there's no such document
+#[pg_extern(volatile, parallel_safe)]
+fn doc_size(index: PgRelation, ctid: pg_sys::ItemPointerData) -> Option<i64> {
+    #[derive(Deserialize)]
+    struct Fields {
+        #[serde(rename = "_size")]
+        size: Option<[i64; 1]>,
+    }
+
+    #[derive(Deserialize)]
+    struct Hit {
+        fields: Option<Fields>,
+    }
+
+    #[derive(Deserialize)]
+    struct Hits {
+        hits: Vec<Hit>,
+    }
+
+    #[derive(Deserialize)]
+    struct DocSizeResponse {
+        hits: Hits,
+    }
+
+    if !ZDBIndexOptions::from_relation(&index).store_doc_size() {
+        panic!("index does not have the store_doc_size option enabled");
+    }
+
+    let es = Elasticsearch::new(&index);
+
+    let response: DocSizeResponse = Elasticsearch::execute_json_request(
+        Elasticsearch::client().post(&format!("{}/_search", es.alias_url())),
+        Some(json! {
+            {
+                "query": { "ids": { "values": [item_pointer_to_u64(ctid)] } },
+                "_source": false,
+                "docvalue_fields": ["_size"]
+            }
+        }),
+        |body| Ok(serde_json::from_reader(body).expect("failed to parse _search response")),
+    )
+    .unwrap_or_else(|e| panic!("{}", e));
+
+    response
+        .hits
+        .hits
+        .into_iter()
+        .next()
+        .and_then(|hit| hit.fields)
+        .and_then(|fields| fields.size)
+        .map(|size| size[0])
+}
+
+/// Executes each query `iterations` times as an Elasticsearch `_count` request and reports
+/// the round-trip latencies, in milliseconds, for each one
+#[pg_extern(volatile, parallel_safe)]
+fn benchmark(index: PgRelation, queries: Array<&str>, iterations: default!(i32, 10)) -> JsonB {
+    if iterations < 1 {
+        panic!("iterations must be greater than zero");
+    }
+
+    let es = Elasticsearch::new(&index);
+    let mut results = Vec::new();
+    for query in queries.iter() {
+        let query = query.expect("queries array cannot contain NULL elements");
+        let mut timings = Vec::with_capacity(iterations as usize);
+
+        for _ in 0..iterations {
+            let (prepared_query, _) = ZDBQuery::from_str(query).prepare(&index, None);
+            let start = std::time::Instant::now();
+            es.count(prepared_query)
+                .execute()
+                .unwrap_or_else(|e| panic!("{}", e));
+            timings.push(start.elapsed().as_secs_f64() * 1000.0);
+        }
+
+        timings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        results.push(json! {
+            {
+                "query": query,
+                "iterations": iterations,
+                "p50_ms": percentile(&timings, 0.50),
+                "p95_ms": percentile(&timings, 0.95),
+                "max_ms": timings.last()
+            }
+        });
+    }
+
+    JsonB(json!(results))
+}
+
+/// nearest-rank percentile of an already-sorted, non-empty slice
+fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    let rank = (pct * sorted.len() as f64).ceil() as usize;
+    sorted[rank.max(1) - 1]
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+mod tests {
+    use pgx::*;
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_recover_orphaned_indexes() {
+        Spi::run("CREATE TABLE test_orphans AS SELECT * FROM generate_series(1, 10);");
+        Spi::run("CREATE INDEX idxtest_orphans ON test_orphans USING zombodb ((test_orphans.*));");
+
+        let create = |name: &str, alias: &str| {
+            Spi::run(&format!(
+                "SELECT zdb.request('idxtest_orphans', '/{}', 'PUT', '{{\"aliases\": {{\"{}\": {{}}}}}}');",
+                name, alias
+            ))
+        };
+        let reported = |name: &str| {
+            Spi::get_one::<bool>(&format!(
+                "SELECT EXISTS (SELECT 1 FROM zdb.recover_orphaned_indexes() url WHERE url LIKE '%/{}');",
+                name
+            ))
+        };
+        let database_name =
+            Spi::get_one::<&str>("SELECT current_database()::text;").expect("no database name");
+
+        // named like one of ours, and in the default alias ZomboDB gives it
+        let orphan_name = format!("{}.1.2.3", unsafe { pg_sys::MyDatabaseId });
+        create(
+            &orphan_name,
+            &format!("{}.public.test_orphans.idxtest_gone-3", database_name),
+        );
+
+        // named like one of ours, but created by a database with the same oid in another cluster
+        let foreign_name = format!("{}.1.2.4", unsafe { pg_sys::MyDatabaseId });
+        create(
+            &foreign_name,
+            &format!("not_{}.public.test_orphans.idxtest_other-4", database_name),
+        );
+
+        let orphan_reported = reported(&orphan_name);
+        let foreign_reported = reported(&foreign_name);
+        let live_reported = Spi::get_one::<bool>(
+            "SELECT EXISTS (SELECT 1 FROM zdb.recover_orphaned_indexes() url WHERE url LIKE '%/' || zdb.index_name('idxtest_orphans'));",
+        );
+
+        for name in &[&orphan_name, &foreign_name] {
+            Spi::run(&format!(
+                "SELECT zdb.request('idxtest_orphans', '/{}', 'DELETE');",
+                name
+            ));
+        }
+
+        assert_eq!(orphan_reported, Some(true));
+        assert_eq!(foreign_reported, Some(false));
+        assert_eq!(live_reported, Some(false));
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_search_sorted() {
+        Spi::run("CREATE TABLE test_search_sorted AS SELECT n FROM generate_series(1, 10) n;");
+        Spi::run("CREATE INDEX idxtest_search_sorted ON test_search_sorted USING zombodb ((test_search_sorted.*));");
+        let values = Spi::get_one::<Vec<i32>>(
+            r#"SELECT array_agg(t.n ORDER BY s.ord)
+                 FROM zdb.search_sorted('idxtest_search_sorted', 'n > 2', '[{"n": "desc"}]', 3) WITH ORDINALITY s(tid, ord)
+                 JOIN test_search_sorted t ON t.ctid = s.tid;"#,
+        )
+        .expect("failed to get SPI result");
+
+        assert_eq!(values, vec![10, 9, 8]);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_query_min_score() {
+        Spi::run(
+            "CREATE TABLE test_query_min_score AS SELECT * FROM (VALUES
+                ('strong', 'apple apple apple'),
+                ('weak', 'apple banana cherry grape lemon mango orange peach pear plum')) t(name, title);",
+        );
+        Spi::run("CREATE INDEX idxtest_query_min_score ON test_query_min_score USING zombodb ((test_query_min_score.*));");
+
+        // a threshold halfway between the two documents' scores
+        let threshold = Spi::get_one::<f32>(
+            "SELECT ((max(zdb.score(ctid)) + min(zdb.score(ctid))) / 2)::real
+               FROM test_query_min_score
+              WHERE test_query_min_score ==> 'title:apple';",
+        )
+        .expect("failed to get SPI result");
+
+        let names = Spi::get_one::<Vec<&str>>(&format!(
+            "SELECT array_agg(t.name)
+               FROM zdb.query_min_score('idxtest_query_min_score', 'title:apple', {}, 10) s(tid)
+               JOIN test_query_min_score t ON t.ctid = s.tid;",
+            threshold
+        ))
+        .expect("failed to get SPI result");
+
+        assert_eq!(names, vec!["strong"]);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_geo_bounding_box() {
+        // points are (longitude, latitude)
+        Spi::run(
+            "CREATE TABLE test_geo_bounding_box AS SELECT * FROM (VALUES
+                ('inside', point(-73.98, 40.75)),
+                ('also inside', point(-73.95, 40.70)),
+                ('outside', point(-0.12, 51.50))) t(name, location);",
+        );
+        Spi::run("CREATE INDEX idxtest_geo_bounding_box ON test_geo_bounding_box USING zombodb ((test_geo_bounding_box.*));");
+
+        let names = Spi::get_one::<Vec<&str>>(
+            "SELECT array_agg(t.name ORDER BY t.name)
+               FROM zdb.geo_bounding_box('idxtest_geo_bounding_box', 'location', 41.0, -74.5, 40.5, -73.5) s(tid)
+               JOIN test_geo_bounding_box t ON t.ctid = s.tid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(names, vec!["also inside", "inside"]);
+
+        let scoped = Spi::get_one::<Vec<&str>>(
+            "SELECT array_agg(t.name ORDER BY t.name)
+               FROM zdb.geo_bounding_box('idxtest_geo_bounding_box', 'location', 41.0, -74.5, 40.5, -73.5, 'name:also') s(tid)
+               JOIN test_geo_bounding_box t ON t.ctid = s.tid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(scoped, vec!["also inside"]);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_rank_feature() {
+        Spi::run(
+            "CREATE TABLE test_rank_feature AS SELECT * FROM (VALUES
+                ('low', 1),
+                ('high', 100),
+                ('medium', 10)) t(name, popularity);",
+        );
+        Spi::run("SELECT zdb.define_field_mapping('test_rank_feature', 'popularity', '{\"type\": \"rank_feature\"}');");
+        Spi::run("CREATE INDEX idxtest_rank_feature ON test_rank_feature USING zombodb ((test_rank_feature.*));");
+
+        let names = Spi::get_one::<Vec<&str>>(
+            "SELECT array_agg(t.name ORDER BY s.ord)
+               FROM zdb.rank_feature('idxtest_rank_feature', 'popularity', '') WITH ORDINALITY s(tid, ord)
+               JOIN test_rank_feature t ON t.ctid = s.tid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(names, vec!["high", "medium", "low"]);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_terms_set() {
+        Spi::run(
+            "CREATE TABLE test_terms_set AS SELECT * FROM (VALUES
+                ('all', ARRAY['red', 'green', 'blue']),
+                ('two', ARRAY['red', 'blue']),
+                ('one', ARRAY['green'])) t(name, colors);",
+        );
+        Spi::run("CREATE INDEX idxtest_terms_set ON test_terms_set USING zombodb ((test_terms_set.*));");
+
+        let names = Spi::get_one::<Vec<&str>>(
+            "SELECT array_agg(t.name ORDER BY t.name)
+               FROM zdb.terms_set('idxtest_terms_set', 'colors', ARRAY['red', 'green', 'blue'], 2) s(tid)
+               JOIN test_terms_set t ON t.ctid = s.tid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(names, vec!["all", "two"]);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_nested_query() {
+        Spi::run(
+            r#"CREATE TABLE test_nested_query AS SELECT * FROM (VALUES
+                ('correlated', '[{"name": "apple", "color": "yellow"}]'::jsonb),
+                ('split', '[{"name": "apple", "color": "red"}, {"name": "banana", "color": "yellow"}]'::jsonb)) t(name, data);"#,
+        );
+        Spi::run("CREATE INDEX idxtest_nested_query ON test_nested_query USING zombodb ((test_nested_query.*));");
+
+        let names = Spi::get_one::<Vec<&str>>(
+            "SELECT array_agg(t.name ORDER BY t.name)
+               FROM zdb.nested_query('idxtest_nested_query', 'data', 'data.name:apple AND data.color:yellow') s(tid)
+               JOIN test_nested_query t ON t.ctid = s.tid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(names, vec!["correlated"]);
+
+        let names = Spi::get_one::<Vec<&str>>(
+            "SELECT array_agg(t.name ORDER BY t.name)
+               FROM zdb.nested_query('idxtest_nested_query', 'data', 'data.color:yellow') s(tid)
+               JOIN test_nested_query t ON t.ctid = s.tid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(names, vec!["correlated", "split"]);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_more_like_this() {
+        Spi::run(
+            "CREATE TABLE test_mlt AS SELECT * FROM (VALUES
+                ('the quick brown fox jumps over the fence'),
+                ('lazy dogs sleep all day long'),
+                ('a quick brown fox is a fast fox'),
+                ('the red fox naps')) t(body);",
+        );
+        Spi::run("CREATE INDEX idxtest_mlt ON test_mlt USING zombodb ((test_mlt.*));");
+        let body = Spi::get_one::<&str>(
+            "SELECT body FROM test_mlt WHERE ctid = (
+                SELECT ctid FROM zdb.more_like_this('idxtest_mlt', 'body', 'quick brown fox')
+                 ORDER BY score DESC LIMIT 1);",
+        )
+        .expect("no similar document found");
+
+        assert!(body.contains("quick brown fox"));
+
+        let matched = |body: &str| {
+            Spi::get_one::<bool>(&format!(
+                "SELECT EXISTS (SELECT 1 FROM test_mlt WHERE body = '{}' AND ctid IN (
+                    SELECT ctid FROM zdb.more_like_this('idxtest_mlt', 'body', 'quick brown fox')));",
+                body
+            ))
+            .expect("failed to get SPI result")
+        };
+
+        // sharing only "fox" with the like text is enough to match, but sharing nothing isn't
+        assert!(matched("the red fox naps"));
+        assert!(!matched("lazy dogs sleep all day long"));
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_search_rich() {
+        Spi::run(
+            "CREATE TABLE test_search_rich AS SELECT * FROM (VALUES
+                ('the quick brown fox'),
+                ('lazy dogs sleep all day long')) t(body);",
+        );
+        Spi::run("CREATE INDEX idxtest_search_rich ON test_search_rich USING zombodb ((test_search_rich.*));");
+
+        let (score, fragments, matched) = Spi::get_three::<f32, Vec<String>, Vec<String>>(
+            r#"SELECT score, fragments, matched
+                 FROM zdb.search_rich('idxtest_search_rich', '{
+                        "bool": {
+                            "should": [
+                                {"match": {"body": {"query": "fox", "_name": "animal"}}},
+                                {"match": {"body": {"query": "quick", "_name": "speed"}}}
+                            ]
+                        }
+                      }', 'body');"#,
+        );
+
+        assert!(score.expect("no score") > 0.0);
+        assert_eq!(
+            fragments.expect("no fragments"),
+            vec!["the <em>quick</em> brown <em>fox</em>".to_owned()]
+        );
+        let mut matched = matched.expect("no matched queries");
+        matched.sort();
+        assert_eq!(matched, vec!["animal".to_owned(), "speed".to_owned()]);
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_benchmark() {
+        Spi::run("CREATE TABLE test_benchmark AS SELECT * FROM generate_series(1, 100);");
+        Spi::run("CREATE INDEX idxtest_benchmark ON test_benchmark USING zombodb ((test_benchmark.*));");
+        let result = Spi::get_one::<JsonB>(
+            "SELECT zdb.benchmark('idxtest_benchmark', ARRAY['generate_series:42', 'generate_series < 50'], 3);",
+        )
+        .expect("failed to get SPI result");
+
+        let entries = result.0.as_array().expect("benchmark result is not an array");
+        assert_eq!(entries.len(), 2);
+        for entry in entries {
+            assert!(entry.get("p50_ms").is_some());
+            assert!(entry.get("p95_ms").is_some());
+            assert!(entry.get("max_ms").is_some());
+        }
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_pit_search() {
+        Spi::run("CREATE TABLE test_pit AS SELECT n FROM generate_series(1, 10) n;");
+        Spi::run("CREATE INDEX idxtest_pit ON test_pit USING zombodb ((test_pit.*));");
+
+        let pit_id = Spi::get_one::<String>("SELECT zdb.open_pit('idxtest_pit', '5m');")
+            .expect("failed to open point-in-time");
+        Spi::run("INSERT INTO test_pit SELECT n FROM generate_series(11, 20) n;");
+
+        let live = Spi::get_one::<i64>("SELECT zdb.count('idxtest_pit', '');");
+        let pit = Spi::get_one_with_args::<i64>(
+            "SELECT count(*) FROM zdb.pit_search('idxtest_pit', $1, '');",
+            vec![(PgBuiltInOids::TEXTOID.oid(), pit_id.clone().into_datum())],
+        );
+        let closed = Spi::get_one_with_args::<bool>(
+            "SELECT zdb.close_pit('idxtest_pit', $1);",
+            vec![(PgBuiltInOids::TEXTOID.oid(), pit_id.into_datum())],
+        );
+
+        assert_eq!(live, Some(20));
+        assert_eq!(pit, Some(10));
+        assert_eq!(closed, Some(true));
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_pit_search_pages() {
+        Spi::run("CREATE TABLE test_pit AS SELECT n FROM generate_series(1, 35) n;");
+        Spi::run("CREATE INDEX idxtest_pit ON test_pit USING zombodb ((test_pit.*)) WITH (max_result_window=10);");
+
+        // 35 docs, 10 at a time, each page following the point-in-time id the previous one returned
+        let pit_id = Spi::get_one::<String>("SELECT zdb.open_pit('idxtest_pit', '5m');")
+            .expect("failed to open point-in-time");
+        let pit = Spi::get_one_with_args::<i64>(
+            "SELECT count(DISTINCT t) FROM zdb.pit_search('idxtest_pit', $1, '') t;",
+            vec![(PgBuiltInOids::TEXTOID.oid(), pit_id.clone().into_datum())],
+        );
+        Spi::get_one_with_args::<bool>(
+            "SELECT zdb.close_pit('idxtest_pit', $1);",
+            vec![(PgBuiltInOids::TEXTOID.oid(), pit_id.into_datum())],
+        );
+
+        assert_eq!(pit, Some(35));
+    }
+
+    #[pg_test]
+    #[initialize(es = true)]
+    fn test_bool_query() {
+        Spi::run("CREATE TABLE test_bool_query AS SELECT n FROM generate_series(1, 20) n;");
+        Spi::run(
+            "CREATE INDEX idxtest_bool_query ON test_bool_query USING zombodb ((test_bool_query.*));",
+        );
+
+        // with a must clause, should clauses only affect scoring
+        let ns = Spi::get_one::<Vec<i32>>(
+            "SELECT array_agg(t.n ORDER BY t.n)
+               FROM zdb.bool_query('idxtest_bool_query', ARRAY['n:[1 TO 10]'], ARRAY['n:15', 'n:2'], ARRAY['n:5', 'n:7'], 100) b(tid)
+               JOIN test_bool_query t ON t.ctid = b.tid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(ns, vec![1, 2, 3, 4, 6, 8, 9, 10]);
+
+        // without one, at least one should clause has to match
+        let ns = Spi::get_one::<Vec<i32>>(
+            "SELECT array_agg(t.n ORDER BY t.n)
+               FROM zdb.bool_query('idxtest_bool_query', ARRAY[]::text[], ARRAY['n:3', 'n:[18 TO 20]'], ARRAY['n:19'], 100) b(tid)
+               JOIN test_bool_query t ON t.ctid = b.tid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(ns, vec![3, 18, 20]);
+
+        // and the limit keeps the best scoring hits, which match the most should clauses
+        let ns = Spi::get_one::<Vec<i32>>(
+            "SELECT array_agg(t.n ORDER BY t.n)
+               FROM zdb.bool_query('idxtest_bool_query', ARRAY['n:[1 TO 10]'], ARRAY['n:4'], ARRAY[]::text[], 1) b(tid)
+               JOIN test_bool_query t ON t.ctid = b.tid;",
+        )
+        .expect("failed to get SPI result");
+        assert_eq!(ns, vec![4]);
+    }
+}
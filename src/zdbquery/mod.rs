@@ -5,6 +5,7 @@ mod cast;
 pub mod mvcc;
 mod opclass;
 
+use crate::access_method::options::ZDBIndexOptions;
 use crate::gucs::ZDB_DEFAULT_ROW_ESTIMATE;
 use crate::query_dsl::nested::pg_catalog::ScoreMode;
 use crate::zql::ast::{Expr, IndexLink, QualifiedField};
@@ -460,11 +461,29 @@ impl ZDBQuery {
             .link_options()
             .unwrap_or_else(|| IndexLink::from_zdb(index));
         self.rewrite(index, &index_links, target_link);
-        let json = serde_json::to_value(&self.query_dsl)
+        let options = ZDBIndexOptions::from_relation(index);
+        self.apply_default_boost(options.default_query_boost());
+        let mut json = serde_json::to_value(&self.query_dsl)
             .expect("failed to convert ZDBQuery to a json Value");
+        if let Some(minimum_should_match) = options.default_minimum_should_match() {
+            apply_default_minimum_should_match(&mut json, minimum_should_match);
+        }
+        apply_allow_leading_wildcard(&mut json, options.allow_leading_wildcard());
         ZDBPreparedQuery(self, json)
     }
 
+    fn apply_default_boost(&mut self, boost: f32) {
+        if (boost - 1.0).abs() > std::f32::EPSILON {
+            // a single-query dis_max multiplies scores by its boost, whereas a constant_score
+            // would throw away the relevance of the wrapped query
+            let query_dsl = self
+                .query_dsl
+                .take()
+                .expect("ZDBQuery does not contain query dsl");
+            self.query_dsl = Some(ZDBQueryClause::dis_max(vec![query_dsl], Some(boost), None));
+        }
+    }
+
     fn rewrite(
         &mut self,
         index: &PgRelation,
@@ -732,6 +751,71 @@ impl ZDBQueryClause {
 
 pub struct ZDBPreparedQuery(ZDBQuery, serde_json::Value);
 
+/// Sets `minimum_should_match` on every `match` query in `query_dsl` that doesn't already specify
+/// one.  Only the `{"match": {"field": {"query": ...}}}` form is considered, which is what both
+/// ZQL and `dsl.match()` generate
+fn apply_default_minimum_should_match(query_dsl: &mut Value, minimum_should_match: &str) {
+    match query_dsl {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key == "match" {
+                    if let Value::Object(fields) = value {
+                        for (_, field) in fields.iter_mut() {
+                            if let Value::Object(field) = field {
+                                if field.contains_key("query")
+                                    && !field.contains_key("minimum_should_match")
+                                {
+                                    field.insert(
+                                        "minimum_should_match".into(),
+                                        Value::String(minimum_should_match.into()),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+
+                apply_default_minimum_should_match(value, minimum_should_match);
+            }
+        }
+        Value::Array(array) => {
+            for value in array {
+                apply_default_minimum_should_match(value, minimum_should_match);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Sets `allow_leading_wildcard` on every `query_string` query in `query_dsl` that doesn't already
+/// specify it.  Elasticsearch has no index setting for this, so it has to be set on each query
+fn apply_allow_leading_wildcard(query_dsl: &mut Value, allow_leading_wildcard: bool) {
+    match query_dsl {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key == "query_string" {
+                    if let Value::Object(query_string) = value {
+                        if !query_string.contains_key("allow_leading_wildcard") {
+                            query_string.insert(
+                                "allow_leading_wildcard".into(),
+                                Value::Bool(allow_leading_wildcard),
+                            );
+                        }
+                    }
+                }
+
+                apply_allow_leading_wildcard(value, allow_leading_wildcard);
+            }
+        }
+        Value::Array(array) => {
+            for value in array {
+                apply_allow_leading_wildcard(value, allow_leading_wildcard);
+            }
+        }
+        _ => {}
+    }
+}
+
 impl ZDBPreparedQuery {
     pub fn query_dsl(&self) -> &serde_json::Value {
         &self.1
@@ -900,6 +984,66 @@ mod tests {
     use crate::zdbquery::*;
     use serde_json::json;
 
+    #[pg_test]
+    fn test_apply_default_minimum_should_match() {
+        let mut query_dsl = json! {
+            {
+                "bool": {
+                    "must": [
+                        { "match": { "title": { "query": "quick brown fox" } } },
+                        { "match": { "body": { "query": "lazy dog", "minimum_should_match": 1 } } },
+                        { "span_multi": { "match": { "prefix": { "title": { "value": "qu" } } } } }
+                    ]
+                }
+            }
+        };
+        apply_default_minimum_should_match(&mut query_dsl, "75%");
+
+        assert_eq!(
+            query_dsl,
+            json! {
+                {
+                    "bool": {
+                        "must": [
+                            { "match": { "title": { "query": "quick brown fox", "minimum_should_match": "75%" } } },
+                            { "match": { "body": { "query": "lazy dog", "minimum_should_match": 1 } } },
+                            { "span_multi": { "match": { "prefix": { "title": { "value": "qu" } } } } }
+                        ]
+                    }
+                }
+            }
+        );
+    }
+
+    #[pg_test]
+    fn test_apply_allow_leading_wildcard() {
+        let mut query_dsl = json! {
+            {
+                "bool": {
+                    "must": [
+                        { "query_string": { "query": "*fox" } },
+                        { "query_string": { "query": "*dog", "allow_leading_wildcard": false } }
+                    ]
+                }
+            }
+        };
+        apply_allow_leading_wildcard(&mut query_dsl, true);
+
+        assert_eq!(
+            query_dsl,
+            json! {
+                {
+                    "bool": {
+                        "must": [
+                            { "query_string": { "query": "*fox", "allow_leading_wildcard": true } },
+                            { "query_string": { "query": "*dog", "allow_leading_wildcard": false } }
+                        ]
+                    }
+                }
+            }
+        );
+    }
+
     #[pg_test]
     fn test_zdbquery_in_with_query_string() {
         let input = std::ffi::CStr::from_bytes_with_nul(b"this is a test\0").unwrap();
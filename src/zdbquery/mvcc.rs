@@ -144,7 +144,8 @@ pub fn build_visibility_clause(index_name: &str) -> serde_json::Value {
                                           "zdb_xmin": {
                                             "index": index_name,
                                             "path": "zdb_aborted_xids",
-                                            "id": "zdb_aborted_xids"
+                                            "id": "zdb_aborted_xids",
+                                            "routing": "zdb_aborted_xids"
                                           }
                                         }
                                       }
@@ -229,7 +230,8 @@ pub fn build_visibility_clause(index_name: &str) -> serde_json::Value {
                                                       "zdb_xmax": {
                                                         "index": index_name,
                                                         "path": "zdb_aborted_xids",
-                                                        "id": "zdb_aborted_xids"
+                                                        "id": "zdb_aborted_xids",
+                                                        "routing": "zdb_aborted_xids"
                                                       }
                                                     }
                                                   },